@@ -0,0 +1,203 @@
+//! The results of the project's Addressables analyze rules, run
+//! through an injected script (see `scripts/AnalyzeAddressables.cs`),
+//! which logs each result a rule found as
+//! `[unitool] finding <rule>\t<severity>\t<result>`. A result is the
+//! rule's `:`-separated path to the issue, e.g. for duplicated
+//! dependencies the group and bundle they're pulled into and the
+//! asset itself:
+//!
+//! ```text
+//! [unitool] finding Check Duplicate Bundle Dependencies    Warning    Characters:characters_assets_all.bundle:Assets/Textures/Shared.png
+//! ```
+
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
+use crate::{build::MARKER, display::*};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// The rule's name, e.g. `Check Duplicate Bundle Dependencies`
+    pub rule: String,
+
+    /// `Info`, `Warning` or `Error`
+    pub severity: String,
+
+    /// The result's path, e.g. the group, bundle and asset
+    pub path: Vec<String>,
+}
+impl Finding {
+    /// The asset the finding is about, if it names one.
+    pub fn asset(&self) -> Option<&str> {
+        self.path.iter()
+            .map(|segment| segment.trim())
+            .find(|segment| segment.starts_with("Assets/") || segment.starts_with("Packages/"))
+    }
+
+    /// Whether it's something to fix, rather than just information.
+    pub fn is_issue(&self) -> bool {
+        matches!(self.severity.as_str(), "Warning" | "Error")
+    }
+}
+
+/// An asset built into more than one bundle (or into a bundle and the
+/// player), because it's a dependency of assets in each but isn't
+/// addressable itself, so it takes up space (and memory) in each.
+#[derive(Debug, Serialize)]
+pub struct Duplicate {
+    pub asset: String,
+
+    /// The groups and bundles it's duplicated into
+    pub into: Vec<String>,
+
+    /// The rule that found it
+    pub rule: String,
+}
+
+/// The analyze results logged by the analyze script.
+#[derive(Debug, Default, Serialize)]
+pub struct AnalyzeReport {
+    pub result: String,
+
+    /// The rules that were run
+    pub rules: Vec<String>,
+    pub findings: Vec<Finding>,
+
+    /// The duplicated dependencies among the findings, by asset
+    pub duplicates: Vec<Duplicate>,
+
+    pub errors: Vec<String>,
+}
+impl AnalyzeReport {
+    /// Parse the report from the editor log,
+    /// if the analyze script got as far as logging one.
+    pub(crate) fn parse(log: &str) -> Option<AnalyzeReport> {
+        let mut report = AnalyzeReport::default();
+        let mut found = false;
+        for line in log.lines() {
+            let Some((key, value)) = line.strip_prefix(MARKER)
+                .and_then(|rest| rest.trim_end_matches('\r').split_once(' ')) else { continue };
+            match key {
+                "result" => {
+                    report.result = value.to_string();
+                    found = true;
+                },
+                "rule" => report.rules.push(value.to_string()),
+                "finding" => {
+                    let mut parts = value.splitn(3, '\t');
+                    let (Some(rule), Some(severity), Some(result)) = (parts.next(), parts.next(), parts.next()) else { continue };
+                    report.findings.push(Finding {
+                        rule: rule.to_string(),
+                        severity: severity.to_string(),
+                        path: result.split(':').map(|segment| segment.trim().to_string()).collect(),
+                    });
+                },
+                "error" => report.errors.push(value.to_string()),
+                _ => continue,
+            }
+        }
+        report.duplicates = duplicates(&report.findings);
+        if found { Some(report) } else { None }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.result == "Succeeded"
+    }
+
+    /// The findings that are something to fix.
+    pub fn issues(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|finding| finding.is_issue())
+    }
+}
+impl Display for AnalyzeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        if !self.duplicates.is_empty() {
+            lines.push(format!("{} {}", yellow("Duplicated dependencies"),
+                muted(&format!("({} assets)", self.duplicates.len()))));
+            for duplicate in &self.duplicates {
+                lines.push(format!("  {} {}", duplicate.asset,
+                    muted(&format!("in {}", duplicate.into.join(", ")))));
+            }
+            for hint in self.duplicates.iter().filter_map(|duplicate| hint(&duplicate.rule))
+                .collect::<std::collections::BTreeSet<_>>() {
+                lines.push(muted(&format!("  {}", hint)).to_string());
+            }
+            lines.push(String::new());
+        }
+
+        // Everything else, by rule
+        let mut by_rule: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+        for finding in &self.findings {
+            if is_duplicate_rule(&finding.rule) && finding.asset().is_some() { continue }
+            by_rule.entry(&finding.rule).or_default().push(finding);
+        }
+        for (rule, findings) in by_rule {
+            lines.push(rule.to_string());
+            for finding in findings {
+                let path = finding.path.join(" > ");
+                lines.push(match finding.severity.as_str() {
+                    "Error" => format!("  {}", red(&path)),
+                    "Warning" => format!("  {}", yellow(&path)),
+                    _ => format!("  {}", muted(&path)),
+                });
+            }
+            lines.push(String::new());
+        }
+
+        // Counting each duplicate once, rather than each bundle it's in
+        let issues = self.duplicates.len() + self.issues()
+            .filter(|finding| !(is_duplicate_rule(&finding.rule) && finding.asset().is_some()))
+            .count();
+        let stats = format!("{} rules", self.rules.len());
+        if !self.succeeded() {
+            lines.push(format!("{} {}", on_red(" Analysis failed "), muted(&stats)));
+        } else if issues > 0 {
+            lines.push(format!("{} {}", on_red(&format!(" {} issues found ", issues)), muted(&stats)));
+        } else {
+            lines.push(format!("{} {}", on_green(" No issues found "), muted(&stats)));
+        }
+        for err in &self.errors {
+            lines.push(format!("  {}", red(err)));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Whether the rule is one of the `Check ... Duplicate ... Dependencies` ones.
+fn is_duplicate_rule(rule: &str) -> bool {
+    rule.contains("Duplicate") && rule.contains("Dependencies")
+}
+
+/// What to do about a duplicate the rule found.
+fn hint(rule: &str) -> Option<&'static str> {
+    if rule.contains("Resources") {
+        Some("Assets in Resources are built into the player too; move them out of Resources, or load them from Resources rather than Addressables")
+    } else if rule.contains("Scene") {
+        Some("Assets used by scenes in the build settings are built into the player too; make those scenes addressable, or the assets not")
+    } else if rule.contains("Bundle") {
+        Some("Add duplicated assets to a group (e.g. a shared one) so they're built into one bundle, which the others depend on")
+    } else {
+        None
+    }
+}
+
+/// Group the duplicate rules' findings by asset.
+fn duplicates(findings: &[Finding]) -> Vec<Duplicate> {
+    let mut by_asset: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for finding in findings.iter().filter(|finding| is_duplicate_rule(&finding.rule)) {
+        let Some(asset) = finding.asset() else { continue };
+        let into = by_asset.entry((asset, &finding.rule)).or_default();
+        for segment in &finding.path {
+            if segment != asset && !into.contains(segment) {
+                into.push(segment.clone());
+            }
+        }
+    }
+    by_asset.into_iter()
+        .map(|((asset, rule), into)| Duplicate {
+            asset: asset.to_string(),
+            into,
+            rule: rule.to_string(),
+        })
+        .collect()
+}
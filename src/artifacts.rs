@@ -0,0 +1,40 @@
+//! Copying the files failed tests attached to their results (e.g.
+//! the screenshots and image diffs graphics tests attach) into one
+//! directory, so CI can upload them as artifacts. Each test's go in
+//! a directory named after it, e.g.
+//! `<dir>/Game.Tests.RendersSprite/RendersSprite.png`.
+
+use anyhow::Result;
+use std::{fs, path::Path};
+use crate::TestSummary;
+
+/// Copy failed tests' attachments into `dir`, pointing the results'
+/// attachments at the copies, and return how many were copied.
+/// Attachments with relative paths are relative to `base`, e.g. the
+/// project; those that no longer exist are left as they were.
+pub fn collect(results: &mut TestSummary, base: &Path, dir: &Path) -> Result<usize> {
+    let mut copied = 0;
+    for case in results.cases_mut() {
+        if !case.failed() { continue }
+        let case_dir = dir.join(dir_name(case.key()));
+        for attachment in case.attachments_mut() {
+            let src = base.join(&attachment.file_path);
+            let Some(file_name) = src.file_name() else { continue };
+            if !src.is_file() { continue }
+            fs::create_dir_all(&case_dir)?;
+            let dest = case_dir.join(file_name);
+            fs::copy(&src, &dest)?;
+            attachment.file_path = dest.to_string_lossy().to_string();
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// The test's name, safe to use as a directory name;
+/// e.g. parameterized tests' names have `(`, `"` and `,`.
+fn dir_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
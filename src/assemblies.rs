@@ -0,0 +1,148 @@
+//! Finding the project's test assemblies from its assembly
+//! definitions (`.asmdef`s), so they needn't be listed by hand.
+//! Editor-only test assemblies hold edit mode tests, and
+//! the rest play mode tests.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Display, fs, path::{Path, PathBuf}};
+use crate::{TestMode, json, display::*};
+
+/// The test runner assemblies, which test assemblies reference
+/// (by name, or by GUID when "Use GUIDs" is set).
+const TEST_RUNNER_REFERENCES: &[&str] = &[
+    "UnityEngine.TestRunner",
+    "UnityEditor.TestRunner",
+    "GUID:27619889b8ba8c24980f49ee34dbb44a",
+    "GUID:0acc523941302664db1f4e527237feb3",
+];
+
+/// An assembly definition in the project.
+#[derive(Debug, Serialize)]
+pub struct Assembly {
+    pub name: String,
+
+    /// The `.asmdef`, relative to the project
+    pub path: PathBuf,
+
+    /// Whether it references the test framework
+    pub is_test: bool,
+
+    /// Whether it only builds for the editor
+    pub editor_only: bool,
+}
+impl Assembly {
+    /// The mode its tests run in, if it's a test assembly.
+    pub fn test_mode(&self) -> Option<TestMode> {
+        match (self.is_test, self.editor_only) {
+            (false, _) => None,
+            (true, true) => Some(TestMode::EditMode),
+            (true, false) => Some(TestMode::PlayMode),
+        }
+    }
+}
+
+/// An assembly definition that couldn't be read, so was skipped.
+#[derive(Debug, Serialize)]
+pub struct InvalidAsmdef {
+    /// The `.asmdef`, relative to the project
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// The assemblies found, and which tests they hold.
+#[derive(Debug, Serialize)]
+pub struct Assemblies {
+    pub assemblies: Vec<Assembly>,
+
+    /// The assembly definitions skipped
+    pub invalid: Vec<InvalidAsmdef>,
+}
+impl Assemblies {
+    /// The test assemblies for the mode, `;`-delimited as
+    /// `-assemblyNames` takes them, or `None` if there are none.
+    pub fn for_mode(&self, mode: &TestMode) -> Option<String> {
+        let names: Vec<&str> = self.assemblies.iter()
+            .filter(|assembly| match (assembly.test_mode(), mode) {
+                (None, _) => false,
+                (Some(_), TestMode::All) => true,
+                (Some(found), mode) => found == *mode,
+            })
+            .map(|assembly| assembly.name.as_str())
+            .collect();
+        if names.is_empty() { None } else { Some(names.join(";")) }
+    }
+}
+impl Display for Assemblies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        for (mode, label) in [(TestMode::EditMode, "Edit mode"), (TestMode::PlayMode, "Play mode")] {
+            let tests: Vec<&Assembly> = self.assemblies.iter()
+                .filter(|assembly| assembly.test_mode().as_ref() == Some(&mode))
+                .collect();
+            lines.push(format!("{} test assemblies:", label));
+            if tests.is_empty() {
+                lines.push(format!("  {}", muted("none")));
+            }
+            for assembly in tests {
+                lines.push(format!("  {} {}", green(&assembly.name), muted(&assembly.path.display().to_string())));
+            }
+        }
+        let others = self.assemblies.iter().filter(|assembly| !assembly.is_test).count();
+        lines.push(muted(&format!("({} other assemblies)", others)).to_string());
+        for invalid in &self.invalid {
+            lines.push(format!("{} {}", yellow(&format!("Skipped {}:", invalid.path.display())), invalid.error));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Read the assembly definitions under `Assets/`, skipping
+/// (rather than failing on) any that can't be read.
+pub fn discover(project_path: &Path) -> Result<Assemblies> {
+    let mut paths = vec![];
+    find_asmdefs(&project_path.join("Assets"), &mut paths);
+    paths.sort();
+
+    let mut assemblies = vec![];
+    let mut invalid = vec![];
+    for path in paths {
+        let relative = path.strip_prefix(project_path).unwrap_or(&path).to_path_buf();
+        let asmdef = match fs::read_to_string(&path).map_err(|err| err.to_string())
+            .and_then(|contents| json::parse(&contents).map_err(|err| err.to_string())) {
+            Ok(asmdef) => asmdef,
+            Err(error) => {
+                invalid.push(InvalidAsmdef { path: relative, error });
+                continue;
+            },
+        };
+        let Some(name) = asmdef.get("name").and_then(|name| name.as_str()) else { continue };
+
+        let references = asmdef.get("references").map(|v| v.strings()).unwrap_or_default();
+        let optional = asmdef.get("optionalUnityReferences").map(|v| v.strings()).unwrap_or_default();
+        let precompiled = asmdef.get("precompiledReferences").map(|v| v.strings()).unwrap_or_default();
+        let is_test = references.iter().any(|r| TEST_RUNNER_REFERENCES.contains(r))
+            || optional.contains(&"TestAssemblies")
+            || precompiled.contains(&"nunit.framework.dll");
+        let platforms = asmdef.get("includePlatforms").map(|v| v.strings()).unwrap_or_default();
+        assemblies.push(Assembly {
+            name: name.to_string(),
+            path: relative,
+            is_test,
+            editor_only: platforms == ["Editor"],
+        });
+    }
+    Ok(Assemblies { assemblies, invalid })
+}
+
+fn find_asmdefs(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_asmdefs(&path, paths);
+        } else if path.extension().is_some_and(|ext| ext == "asmdef") {
+            paths.push(path);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Known-failing and quarantined tests, from `.unitool/baseline.toml`,
+//! which don't fail a test run. Known failures are expected to fail
+//! (and are called out if they start passing); quarantined tests,
+//! e.g. flaky ones, are ignored whatever their result. Each test is
+//! keyed by full name, with an optional reason:
+//!
+//! ```toml
+//! [known_failing]
+//! "Game.Core.MathTests.Divides" = "#123, rounding is off"
+//!
+//! [quarantined]
+//! "Game.Net.SyncTests.Reconnects" = "flaky on CI"
+//! ```
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{collections::{BTreeMap, HashSet}, fmt::Display, fs, io::ErrorKind, path::{Path, PathBuf}};
+use crate::{TestResult, TestSummary, config::{self, Value}, display::*, state_dir};
+
+fn baseline_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("baseline.toml")
+}
+
+/// Tests by full name, with the reasons they're in the baseline.
+#[derive(Debug, Default, Serialize)]
+pub struct Baseline {
+    pub known_failing: BTreeMap<String, String>,
+    pub quarantined: BTreeMap<String, String>,
+}
+impl Baseline {
+    /// The project's baseline, empty if it has none.
+    pub fn load(project_path: &Path) -> Result<Baseline> {
+        let path = baseline_path(project_path);
+        match fs::read_to_string(&path) {
+            Ok(src) => Baseline::parse(&src).map_err(|err| anyhow!("{}:{}", path.display(), err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Baseline::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.known_failing.is_empty() && self.quarantined.is_empty()
+    }
+
+    /// Errors are prefixed with the line number.
+    fn parse(src: &str) -> Result<Baseline> {
+        let mut baseline = Baseline::default();
+        let mut table = None;
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if config::is_comment(line) { continue }
+            let line_no = i + 1;
+
+            if let Some(rest) = line.strip_prefix('[') {
+                let Some((name, rest)) = rest.split_once(']') else {
+                    bail!("{}: Unclosed table header", line_no);
+                };
+                if !config::is_comment(rest) {
+                    bail!("{}: Unexpected text after table header", line_no);
+                }
+                table = match name.trim() {
+                    "known_failing" => Some(&mut baseline.known_failing),
+                    "quarantined" => Some(&mut baseline.quarantined),
+                    name => bail!("{}: Unknown table `{}`; use `known_failing` or `quarantined`", line_no, name),
+                };
+                continue;
+            }
+
+            // Test names have dots, so are usually quoted
+            let (name, rest) = if line.starts_with(['"', '\'']) {
+                let (Value::Str(name), rest) = config::parse_value(line)
+                    .map_err(|err| anyhow!("{}: {}", line_no, err))? else {
+                    bail!("{}: Expected a test name", line_no);
+                };
+                let Some(rest) = rest.trim().strip_prefix('=') else {
+                    bail!("{}: Expected `=` after the test name", line_no);
+                };
+                (name, rest)
+            } else {
+                let Some((name, rest)) = line.split_once('=') else {
+                    bail!("{}: Expected `\"Test.Name\" = \"reason\"`", line_no);
+                };
+                (name.trim().to_string(), rest)
+            };
+            let (reason, rest) = config::parse_value(rest.trim())
+                .map_err(|err| anyhow!("{}: {}", line_no, err))?;
+            let Value::Str(reason) = reason else {
+                bail!("{}: The reason should be a string, not {}", line_no, reason.kind());
+            };
+            if !config::is_comment(rest) {
+                bail!("{}: Unexpected text after value", line_no);
+            }
+            match &mut table {
+                Some(table) => { table.insert(name, reason); },
+                None => bail!("{}: Tests go under `[known_failing]` or `[quarantined]`", line_no),
+            }
+        }
+        Ok(baseline)
+    }
+
+    /// The baseline updated from the run: adding the tests that
+    /// failed, and dropping those that passed. Tests that didn't
+    /// run (e.g. with a filter) are kept, as are the quarantined
+    /// tests and the reasons for known failures.
+    pub fn updated(&self, results: &TestSummary) -> Baseline {
+        let cases = results.cases();
+        let passed: HashSet<&str> = cases.iter()
+            .filter(|case| case.result == TestResult::Passed)
+            .map(|case| case.key())
+            .collect();
+        let mut known_failing: BTreeMap<String, String> = self.known_failing.iter()
+            .filter(|(name, _)| !passed.contains(name.as_str()))
+            .map(|(name, reason)| (name.clone(), reason.clone()))
+            .collect();
+        for case in cases.iter().filter(|case| case.failed()) {
+            if !self.quarantined.contains_key(case.key()) {
+                known_failing.entry(case.key().to_string()).or_default();
+            }
+        }
+        Baseline { known_failing, quarantined: self.quarantined.clone() }
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<()> {
+        let mut lines = vec![
+            "# Tests which don't fail `unitool test`, keyed by full name,".to_string(),
+            "# with the reasons. Update `known_failing` from a run".to_string(),
+            "# with `unitool test --update-baseline`.".to_string(),
+        ];
+        for (table, tests) in [("known_failing", &self.known_failing), ("quarantined", &self.quarantined)] {
+            lines.push("".to_string());
+            lines.push(format!("[{}]", table));
+            lines.extend(tests.iter().map(|(name, reason)| format!("{} = {}", quote(name), quote(reason))));
+        }
+        let path = baseline_path(project_path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, lines.join("\n") + "\n")?;
+        Ok(())
+    }
+
+    /// Check the run's results against the baseline.
+    pub fn check(&self, results: &TestSummary) -> BaselineOutcome {
+        let mut outcome = BaselineOutcome::default();
+        for case in results.iter_cases() {
+            let name = case.key();
+            if self.quarantined.contains_key(name) {
+                if case.failed() {
+                    outcome.quarantined.push(name.to_string());
+                }
+            } else if self.known_failing.contains_key(name) {
+                match case.result {
+                    TestResult::Failed => outcome.expected_failures.push(name.to_string()),
+                    TestResult::Passed => outcome.now_passing.push(name.to_string()),
+                    TestResult::Skipped => {},
+                }
+            } else if case.failed() {
+                outcome.unexpected_failures += 1;
+            }
+        }
+        outcome
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t"))
+}
+
+/// How a run went against the baseline.
+#[derive(Debug, Default, Serialize)]
+pub struct BaselineOutcome {
+    /// Known-failing tests that failed
+    pub expected_failures: Vec<String>,
+
+    /// Quarantined tests that failed
+    pub quarantined: Vec<String>,
+
+    /// Known-failing tests that passed, which
+    /// can be taken out of the baseline
+    pub now_passing: Vec<String>,
+
+    /// How many failures weren't in the baseline
+    pub unexpected_failures: usize,
+}
+impl BaselineOutcome {
+    pub fn failed(&self) -> bool {
+        self.unexpected_failures > 0
+    }
+
+    /// Whether no tests in the baseline failed or passed.
+    pub fn is_empty(&self) -> bool {
+        self.expected_failures.is_empty() && self.quarantined.is_empty() && self.now_passing.is_empty()
+    }
+}
+impl Display for BaselineOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        let mut section = |title: String, names: &[String]| {
+            if !names.is_empty() {
+                lines.push(title);
+                lines.extend(names.iter().map(|name| format!("  {}", name)));
+            }
+        };
+        section(yellow(&format!("{} expected failures (in the baseline):", self.expected_failures.len())).to_string(),
+            &self.expected_failures);
+        section(muted(&format!("{} quarantined tests failed:", self.quarantined.len())).to_string(),
+            &self.quarantined);
+        section(green(&format!("{} known failures now pass (take them out of the baseline):", self.now_passing.len())).to_string(),
+            &self.now_passing);
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,112 @@
+//! Finding which test makes another fail when the tests run in a
+//! random order (e.g. by leaving static state behind), by bisecting
+//! the tests that ran before it: each half is run again with the
+//! failing test, keeping whichever half it still fails with.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Display, fs, path::{Path, PathBuf}};
+use crate::{state_dir, display::*};
+
+#[derive(Debug, Serialize)]
+pub struct Bisection {
+    /// The test that only fails after others
+    pub test: String,
+
+    /// The random order seed it failed with
+    pub seed: u32,
+
+    /// Whether it failed again with just the tests that ran before it
+    pub reproduced: bool,
+
+    /// The fewest tests found that it still fails after:
+    /// just the polluting one, if it was narrowed down to one
+    pub suspects: Vec<String>,
+
+    /// How many runs it took
+    pub runs: usize,
+}
+impl Bisection {
+    /// The test that makes it fail, if there's just the one.
+    pub fn polluter(&self) -> Option<&str> {
+        match self.suspects.as_slice() {
+            [polluter] if self.reproduced => Some(polluter),
+            _ => None,
+        }
+    }
+}
+impl Display for Bisection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let runs = muted(&format!("(seed {}, {} {})", self.seed, self.runs,
+            if self.runs == 1 { "run" } else { "runs" }));
+        let mut lines = vec![];
+        if !self.reproduced {
+            let before = match self.suspects.len() {
+                1 => "the test".to_string(),
+                n => format!("the {} tests", n),
+            };
+            lines.push(format!("{} {}", yellow(&format!(
+                "{} didn't fail again with just {} before it; it may depend on more than their order",
+                self.test, before)), runs));
+        } else if let Some(polluter) = self.polluter() {
+            lines.push(format!("{} {}", red(&format!("{} fails when run after {}", self.test, polluter)), runs));
+            lines.push(muted(&format!("Look for state {} leaves behind, e.g. statics, \
+                singletons, or objects it doesn't destroy", polluter)).to_string());
+        } else {
+            lines.push(format!("{} {}", red(&format!("{} fails when run after some of these {} tests:",
+                self.test, self.suspects.len())), runs));
+            for suspect in &self.suspects {
+                lines.push(format!("  {}", suspect));
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Bisect `before` (the tests that ran before `test` when it failed)
+/// for the one it fails after. `fails` runs the tests along with
+/// `test`, returning whether `test` failed.
+pub fn bisect(test: &str, seed: u32, before: Vec<String>, mut fails: impl FnMut(&[String]) -> Result<bool>) -> Result<Bisection> {
+    let mut bisection = Bisection {
+        test: test.to_string(),
+        seed,
+        reproduced: false,
+        suspects: before,
+        runs: 1,
+    };
+    // Run with them all first, as its order among just
+    // them can differ from its order among all the tests
+    bisection.reproduced = fails(&bisection.suspects)?;
+    if !bisection.reproduced {
+        return Ok(bisection);
+    }
+    while bisection.suspects.len() > 1 {
+        let (first, second) = bisection.suspects.split_at(bisection.suspects.len() / 2);
+        bisection.runs += 1;
+        if fails(first)? {
+            bisection.suspects = first.to_vec();
+            continue;
+        }
+        bisection.runs += 1;
+        if fails(second)? {
+            bisection.suspects = second.to_vec();
+            continue;
+        }
+        // It takes tests from both halves (or they
+        // ran after it in the halves' orders)
+        break;
+    }
+    Ok(bisection)
+}
+
+/// Write an ordered test list (for `-orderedTestListFile`) of the
+/// tests, so a run of them keeps to this order, returning its path.
+pub fn save_order(project_path: &Path, tests: &[String]) -> Result<PathBuf> {
+    let path = state_dir(project_path).join("bisect-order.txt");
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents: String = tests.iter().map(|name| format!("{}\n", name)).collect();
+    fs::write(&path, contents)?;
+    Ok(path)
+}
@@ -0,0 +1,281 @@
+//! Building players, through an injected build script
+//! (see `scripts/Build.cs`) which logs a summary of
+//! Unity's build report for us to parse.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{fmt::Display, path::Path};
+use crate::{burst::{self, BurstError}, display::*, il2cpp, sizes::SizeReport};
+
+/// Prefix for lines logged by injected scripts.
+pub(crate) const MARKER: &str = "[unitool] ";
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum BuildTarget {
+    #[value(name = "linux64")]
+    Linux64,
+    #[value(name = "win64")]
+    Win64,
+    #[value(name = "osx")]
+    OSXUniversal,
+    #[value(name = "android")]
+    Android,
+    #[value(name = "ios")]
+    IOS,
+    #[value(name = "webgl")]
+    WebGL,
+}
+impl BuildTarget {
+    /// The value for Unity's `-buildTarget`.
+    pub fn arg(&self) -> &'static str {
+        match self {
+            BuildTarget::Linux64 => "Linux64",
+            BuildTarget::Win64 => "Win64",
+            BuildTarget::OSXUniversal => "OSXUniversal",
+            BuildTarget::Android => "Android",
+            BuildTarget::IOS => "iOS",
+            BuildTarget::WebGL => "WebGL",
+        }
+    }
+
+    /// The value for the test runner's `-testPlatform`,
+    /// to run play mode tests in a player for the target.
+    pub fn test_platform(&self) -> &'static str {
+        match self {
+            BuildTarget::Linux64 => "StandaloneLinux64",
+            BuildTarget::Win64 => "StandaloneWindows64",
+            BuildTarget::OSXUniversal => "StandaloneOSX",
+            BuildTarget::Android => "Android",
+            BuildTarget::IOS => "iOS",
+            BuildTarget::WebGL => "WebGL",
+        }
+    }
+
+    /// The editor directory this target's support is installed to,
+    /// and the Unity Hub module that installs it.
+    pub(crate) fn module(&self) -> (&'static str, &'static str) {
+        match self {
+            BuildTarget::Linux64 => ("LinuxStandaloneSupport", "linux-mono"),
+            BuildTarget::Win64 => ("WindowsStandaloneSupport", "windows-mono"),
+            BuildTarget::OSXUniversal => ("MacStandaloneSupport", "mac-mono"),
+            BuildTarget::Android => ("AndroidPlayer", "android"),
+            BuildTarget::IOS => ("iOSSupport", "ios"),
+            BuildTarget::WebGL => ("WebGLSupport", "webgl"),
+        }
+    }
+
+    /// The target for the platform this is running on, which the
+    /// editor can build for without any extra modules.
+    pub fn host() -> Option<BuildTarget> {
+        match std::env::consts::OS {
+            "linux" => Some(BuildTarget::Linux64),
+            "windows" => Some(BuildTarget::Win64),
+            "macos" => Some(BuildTarget::OSXUniversal),
+            _ => None,
+        }
+    }
+
+    /// Whether the editor can build for this target
+    /// without any extra modules.
+    fn is_builtin(&self) -> bool {
+        matches!((self, std::env::consts::OS),
+            (BuildTarget::Linux64, "linux")
+            | (BuildTarget::Win64, "windows")
+            | (BuildTarget::OSXUniversal, "macos"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ScriptingBackend {
+    Mono,
+    Il2cpp,
+}
+impl ScriptingBackend {
+    /// The name of Unity's `ScriptingImplementation`.
+    pub(crate) fn arg(&self) -> &'static str {
+        match self {
+            ScriptingBackend::Mono => "Mono2x",
+            ScriptingBackend::Il2cpp => "IL2CPP",
+        }
+    }
+}
+
+/// How much unused managed code the linker strips.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum StripLevel {
+    /// Not available with IL2CPP, which uses minimal
+    Disabled,
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+impl StripLevel {
+    /// The name of Unity's `ManagedStrippingLevel`.
+    pub(crate) fn arg(&self) -> &'static str {
+        match self {
+            StripLevel::Disabled => "Disabled",
+            StripLevel::Minimal => "Minimal",
+            StripLevel::Low => "Low",
+            StripLevel::Medium => "Medium",
+            StripLevel::High => "High",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Make a development build
+    pub development: bool,
+
+    /// Build with this scripting backend, rather
+    /// than the one in the player settings
+    pub scripting_backend: Option<ScriptingBackend>,
+
+    /// Strip managed code at this level, rather
+    /// than the one in the player settings
+    pub strip_level: Option<StripLevel>,
+}
+
+/// If the editor is missing the module needed to
+/// build for the target, the Hub ID of that module.
+pub fn missing_module(editor_path: &Path, target: &BuildTarget) -> Option<&'static str> {
+    if target.is_builtin() { return None }
+
+    // On macOS the binary is in `Unity.app/Contents/MacOS/`
+    // and modules are installed next to `Unity.app`;
+    // elsewhere they're under the binary's `Data/` directory.
+    let engines = if cfg!(target_os = "macos") {
+        editor_path.ancestors().nth(4).map(|root| root.join("PlaybackEngines"))
+    } else {
+        editor_path.parent().map(|dir| dir.join("Data/PlaybackEngines"))
+    }?;
+
+    let (dir, module) = target.module();
+    if engines.join(dir).exists() { None } else { Some(module) }
+}
+
+/// The build results logged by the build script,
+/// and what else the editor logged about the build.
+#[derive(Debug, Default, Serialize)]
+pub struct BuildReport {
+    pub result: String,
+
+    /// Total size of the build, in bytes
+    pub size: u64,
+
+    /// Build time, in seconds
+    pub time: f64,
+
+    pub warnings: usize,
+    pub errors: Vec<String>,
+
+    /// Errors from IL2CPP and the build of its C++,
+    /// rather than from compiling the C# scripts
+    pub il2cpp_errors: Vec<String>,
+
+    /// Warnings from the linker stripping managed code,
+    /// e.g. about code only reached by reflection
+    pub stripping_warnings: Vec<String>,
+
+    /// Errors from compiling jobs with Burst
+    pub burst_errors: Vec<BurstError>,
+
+    /// The sizes of the build's assets, by category and
+    /// asset, if the editor logged its build report
+    pub sizes: Option<SizeReport>,
+}
+impl BuildReport {
+    /// Parse the report from the editor log,
+    /// if the build script got as far as logging one.
+    pub(crate) fn parse(log: &str) -> Option<BuildReport> {
+        let il2cpp = il2cpp::diagnostics(log);
+        let mut report = BuildReport {
+            il2cpp_errors: il2cpp.errors,
+            stripping_warnings: il2cpp.stripping_warnings,
+            burst_errors: burst::errors(log),
+            sizes: SizeReport::parse(log),
+            ..Default::default()
+        };
+        let mut found = false;
+        for line in log.lines() {
+            let Some((key, value)) = line.strip_prefix(MARKER)
+                .and_then(|rest| rest.split_once(' ')) else { continue };
+            match key {
+                "result" => {
+                    report.result = value.to_string();
+                    found = true;
+                },
+                "size" => report.size = value.parse().unwrap_or(0),
+                "time" => report.time = value.parse().unwrap_or(0.),
+                "warnings" => report.warnings = value.parse().unwrap_or(0),
+                "error" => report.errors.push(value.to_string()),
+                _ => continue,
+            }
+        }
+        if found { Some(report) } else { None }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.result == "Succeeded"
+    }
+
+    /// The size of the whole build, in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.sizes.as_ref().and_then(|sizes| sizes.complete).unwrap_or(self.size)
+    }
+}
+impl Display for BuildReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        let stats = format!("{} in {:.1}s, {} warnings",
+            format_size(self.size), self.time, self.warnings);
+        if self.succeeded() {
+            lines.push(format!("{} {}", on_green(" Build succeeded "), muted(&stats)));
+        } else {
+            lines.push(format!("{} {}", on_red(&format!(" Build {} ", self.result.to_lowercase())), muted(&stats)));
+        }
+        for err in &self.errors {
+            lines.push(format!("  {}", red(err)));
+        }
+        if let Some(sizes) = self.sizes.as_ref().filter(|sizes| !sizes.categories.is_empty()) {
+            lines.push(String::new());
+            lines.extend(sizes.to_string().lines().map(|line| format!("  {}", line)));
+        }
+        if !self.il2cpp_errors.is_empty() {
+            lines.push(format!("\n{}", red(&format!("IL2CPP errors ({}):", self.il2cpp_errors.len()))));
+            for err in &self.il2cpp_errors {
+                lines.push(format!("  {}", red(err)));
+            }
+        }
+        if !self.burst_errors.is_empty() {
+            lines.push(format!("\n{}", red(&format!("Burst errors ({}):", self.burst_errors.len()))));
+            for err in &self.burst_errors {
+                lines.extend(err.to_string().lines().map(|line| format!("  {}", line)));
+            }
+        }
+        if !self.stripping_warnings.is_empty() {
+            lines.push(format!("\n{}", yellow(&format!("Stripping warnings ({}):", self.stripping_warnings.len()))));
+            for warning in &self.stripping_warnings {
+                lines.push(format!("  {}", yellow(warning)));
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Human-readable byte counts.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,99 @@
+//! Building AssetBundles or Addressables content, through
+//! injected build scripts (see `scripts/BuildBundles.cs` and
+//! `scripts/BuildAddressables.cs`) which log each bundle
+//! built for us to parse.
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::{build::{MARKER, format_size}, display::*};
+
+#[derive(Debug, Serialize)]
+pub struct Bundle {
+    pub name: String,
+
+    /// In bytes
+    pub size: u64,
+}
+
+/// The bundle build results logged by the build script.
+#[derive(Debug, Default, Serialize)]
+pub struct BundleReport {
+    pub result: String,
+
+    /// Largest first
+    pub bundles: Vec<Bundle>,
+
+    /// Where Addressables put the content, if reported
+    pub output: Option<String>,
+
+    /// Build time, in seconds
+    pub time: f64,
+
+    pub errors: Vec<String>,
+}
+impl BundleReport {
+    /// Parse the report from the editor log,
+    /// if the build script got as far as logging one.
+    pub(crate) fn parse(log: &str) -> Option<BundleReport> {
+        let mut report = BundleReport::default();
+        let mut found = false;
+        for line in log.lines() {
+            let Some((key, value)) = line.strip_prefix(MARKER)
+                .and_then(|rest| rest.trim_end_matches('\r').split_once(' ')) else { continue };
+            match key {
+                "result" => {
+                    report.result = value.to_string();
+                    found = true;
+                },
+                "bundle" => {
+                    let Some((name, size)) = value.rsplit_once('\t') else { continue };
+                    report.bundles.push(Bundle {
+                        name: name.to_string(),
+                        size: size.parse().unwrap_or(0),
+                    });
+                },
+                "output" if !value.is_empty() => report.output = Some(value.to_string()),
+                "time" => report.time = value.parse().unwrap_or(0.),
+                "error" => report.errors.push(value.to_string()),
+                _ => continue,
+            }
+        }
+        report.bundles.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        if found { Some(report) } else { None }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.result == "Succeeded"
+    }
+
+    pub fn size(&self) -> u64 {
+        self.bundles.iter().map(|bundle| bundle.size).sum()
+    }
+}
+impl Display for BundleReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        let width = self.bundles.iter().map(|bundle| bundle.name.len()).max().unwrap_or(0);
+        for bundle in &self.bundles {
+            lines.push(format!("{:width$} {:>10}", bundle.name, format_size(bundle.size), width = width));
+        }
+        if !self.bundles.is_empty() {
+            lines.push(String::new());
+        }
+
+        let stats = format!("{} bundles, {} in {:.1}s",
+            self.bundles.len(), format_size(self.size()), self.time);
+        if self.succeeded() {
+            lines.push(format!("{} {}", on_green(" Bundles built "), muted(&stats)));
+        } else {
+            lines.push(format!("{} {}", on_red(" Bundle build failed "), muted(&stats)));
+        }
+        if let Some(output) = &self.output {
+            lines.push(muted(&format!("Written to {}", output)).to_string());
+        }
+        for err in &self.errors {
+            lines.push(format!("  {}", red(err)));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
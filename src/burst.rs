@@ -0,0 +1,138 @@
+//! Burst compilation errors, from the editor log. Burst compiles jobs
+//! and function pointers ahead of time when building a player, and
+//! its errors (e.g. using managed types in a job) don't show up when
+//! the scripts compile, or as `error CS`. They look like:
+//!
+//! ```text
+//! /path/to/Assets/Scripts/MoveJob.cs(22,13): Burst error BC1016: The managed function `System.String.Concat(...)` is not supported
+//!  at Game.MoveJob.Execute() (at /path/to/Assets/Scripts/MoveJob.cs:22)
+//!
+//! While compiling job: System.Void Unity.Jobs.IJobExtensions/JobStruct`1<Game.MoveJob>::Execute(...)
+//! at /path/to/Assets/Scripts/MoveJob.cs:line 22
+//! ```
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::display::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BurstError {
+    /// e.g. `BC1016`
+    pub code: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+
+    /// The method the error is in, e.g. `Game.MoveJob.Execute()`
+    pub method: Option<String>,
+
+    /// The job (or function pointer) being compiled, e.g. `Game.MoveJob`
+    pub job: Option<String>,
+}
+impl Display for BurstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location = match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!(" {}:{}", file, line),
+            (Some(file), None) => format!(" {}", file),
+            _ => String::new(),
+        };
+        let mut lines = vec![format!("{}{} {}", red(&self.code), location, self.message)];
+        if let Some(job) = &self.job {
+            lines.push(muted(&format!("  compiling {}", job)).to_string());
+        }
+        if let Some(method) = &self.method {
+            lines.push(muted(&format!("  at {}", method)).to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+const MARKER: &str = "Burst error ";
+
+/// The Burst errors in the log, each once.
+pub(crate) fn errors(log: &str) -> Vec<BurstError> {
+    let mut errors: Vec<BurstError> = vec![];
+    let mut current: Option<BurstError> = None;
+    for line in log.lines() {
+        let trimmed = line.trim();
+        if let Some(err) = parse_error(trimmed) {
+            if let Some(prev) = current.replace(err) {
+                push(&mut errors, prev);
+            }
+            continue;
+        }
+        let Some(err) = &mut current else { continue };
+        if let Some(job) = trimmed.strip_prefix("While compiling job: ") {
+            err.job = Some(job_name(job));
+        } else if let Some(function) = trimmed.strip_prefix("While compiling function pointer: ") {
+            err.job = Some(job_name(function));
+        } else if let Some(frame) = trimmed.strip_prefix("at ") {
+            // The first frame, e.g. `Game.MoveJob.Execute() (at /path/MoveJob.cs:22)`,
+            // rather than the job's location after it
+            if err.method.is_none() && !frame.starts_with('/') && !frame.starts_with('<') {
+                err.method = Some(frame.split(" (at ").next().unwrap_or(frame).trim().to_string());
+            }
+        } else if !trimmed.is_empty() && err.job.is_some() {
+            if let Some(prev) = current.take() {
+                push(&mut errors, prev);
+            }
+        }
+    }
+    if let Some(err) = current {
+        push(&mut errors, err);
+    }
+    errors
+}
+
+/// Burst logs the same error for each job it's in,
+/// which are kept apart, but not repeats of the same one.
+fn push(errors: &mut Vec<BurstError>, err: BurstError) {
+    if !errors.contains(&err) {
+        errors.push(err);
+    }
+}
+
+/// Parse e.g. `/path/Foo.cs(22,13): Burst error BC1016: The managed function...`.
+fn parse_error(line: &str) -> Option<BurstError> {
+    let (location, rest) = line.split_once(MARKER)?;
+    let (code, message) = rest.split_once(':')?;
+    if !code.starts_with("BC") { return None }
+
+    let location = location.trim().trim_end_matches(':');
+    let (file, line, column) = match location.rsplit_once('(') {
+        Some((file, position)) => {
+            let mut position = position.trim_end_matches(')').split(',')
+                .map(|n| n.trim().parse::<usize>().ok());
+            let (line, column) = (position.next().flatten(), position.next().flatten());
+            // Burst uses `(0,0)` when it doesn't know where
+            let file = (!file.is_empty()).then(|| file.to_string());
+            (file, line.filter(|&line| line > 0), column.filter(|&column| column > 0))
+        },
+        None => (None, None, None),
+    };
+    Some(BurstError {
+        code: code.to_string(),
+        message: message.trim().to_string(),
+        file,
+        line,
+        column,
+        method: None,
+        job: None,
+    })
+}
+
+/// The job's own type from the job struct's method, e.g.
+/// `Game.MoveJob` from ``System.Void Unity.Jobs.IJobExtensions/JobStruct`1<Game.MoveJob>::Execute(...)``;
+/// otherwise the method, less its return type and parameters.
+fn job_name(signature: &str) -> String {
+    let method = signature.split('(').next().unwrap_or(signature);
+    if let Some(start) = method.find('<') {
+        if let Some(end) = method.rfind('>') {
+            if end > start {
+                return method[start + 1..end].to_string();
+            }
+        }
+    }
+    method.split_whitespace().last().unwrap_or(method).replace("::", ".")
+}
@@ -0,0 +1,111 @@
+//! Caching compile results, so an unchanged project needn't be
+//! compiled again. Each compile records its errors and warnings
+//! with a fingerprint of everything that affects compilation
+//! (scripts, assembly definitions, the package manifest and
+//! project settings, and the editor options), and a cached
+//! compile returns them if the fingerprint still matches.
+
+use anyhow::Result;
+use std::{fs, path::{Path, PathBuf}};
+use crate::{CompileOutput, RunOptions, compiler::CompileError, state_dir};
+
+fn cache_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("compile-cache")
+}
+
+/// The last compile's results, if nothing
+/// that affects compilation has changed since.
+pub fn lookup(project_path: &Path, options: &RunOptions) -> Option<CompileOutput> {
+    let contents = fs::read_to_string(cache_path(project_path)).ok()?;
+    let mut lines = contents.lines();
+    if lines.next()? != fingerprint(project_path, options) {
+        return None;
+    }
+    let mut output = CompileOutput::default();
+    for line in lines {
+        match line.split_once('\t') {
+            Some(("error", raw)) => { output.errors.insert(CompileError::parse(raw)); },
+            Some(("warning", raw)) => { output.warnings.insert(raw.to_string()); },
+            _ => continue,
+        }
+    }
+    Some(output)
+}
+
+/// Record a compile's results under the fingerprint
+/// the project had when the compile started.
+pub(crate) fn record(project_path: &Path, fingerprint: &str, output: &CompileOutput) -> Result<()> {
+    let mut contents = format!("{}\n", fingerprint);
+    for err in &output.errors {
+        contents.push_str(&format!("error\t{}\n", err.raw));
+    }
+    for warning in &output.warnings {
+        contents.push_str(&format!("warning\t{}\n", warning));
+    }
+    let path = cache_path(project_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// A hash of the project's compilation inputs and the editor options.
+pub(crate) fn fingerprint(project_path: &Path, options: &RunOptions) -> String {
+    let mut files = vec![];
+    collect(&project_path.join("Assets"), &mut files, &|path| {
+        matches!(path.extension().and_then(|ext| ext.to_str()), Some("cs" | "asmdef" | "asmref" | "rsp"))
+    });
+    collect(&project_path.join("ProjectSettings"), &mut files, &|_| true);
+    files.push(project_path.join("Packages/manifest.json"));
+    files.sort();
+
+    let mut hash = Fnv::default();
+    if let Some(unity_path) = &options.unity_path {
+        hash.write(unity_path.to_string_lossy().as_bytes());
+    }
+    for arg in &options.extra_args {
+        hash.write(arg.as_bytes());
+    }
+    for (code, severity) in &options.severities {
+        hash.write(format!("{}={:?}", code, severity).as_bytes());
+    }
+    for path in files {
+        let Ok(contents) = fs::read(&path) else { continue };
+        let relative = path.strip_prefix(project_path).unwrap_or(&path);
+        hash.write(relative.to_string_lossy().as_bytes());
+        hash.write(&contents);
+    }
+    format!("{:016x}", hash.0)
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>, include: &dyn Fn(&Path) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files, include);
+        } else if include(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which (unlike std's hasher)
+/// is the same from one build to the next.
+pub(crate) struct Fnv(pub(crate) u64);
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+}
+impl Fnv {
+    /// Hash the bytes, then a separator, so
+    /// e.g. paths can't run into contents.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().chain(&[0xff]) {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Cleaning up after interrupted runs, which can leave headless
+//! editors running with the project open and lock files behind,
+//! blocking later runs.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Display, fs, path::{Path, PathBuf}, process::{Command, Stdio}, thread, time::{Duration, Instant}};
+use crate::{state_dir, lock::editor_has_project, seats::{is_running, is_stale}, display::*};
+
+/// How long to give an editor to exit before killing it.
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// An editor process with the project open.
+#[derive(Debug, Serialize)]
+pub struct EditorProcess {
+    pub pid: u32,
+    pub command: String,
+}
+
+/// What was cleaned up.
+#[derive(Debug, Serialize)]
+pub struct Cleanup {
+    pub terminated: Vec<EditorProcess>,
+
+    /// Lock files that were removed
+    pub removed: Vec<PathBuf>,
+}
+impl Display for Cleanup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.terminated.is_empty() && self.removed.is_empty() {
+            return write!(f, "{}", green("Nothing to clean up"));
+        }
+        let mut lines = vec![];
+        for process in &self.terminated {
+            lines.push(format!("Terminated pid {} {}", process.pid, muted(&process.command)));
+        }
+        for path in &self.removed {
+            lines.push(format!("Removed {}", path.display()));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Terminate any editors with the project open, then remove
+/// Unity's and unitool's lock files if they're stale.
+pub fn cleanup(project_path: &Path) -> Result<Cleanup> {
+    let project = project_path.canonicalize()?;
+    let terminated: Vec<EditorProcess> = processes().into_iter()
+        .filter(|process| process.pid != std::process::id())
+        .filter(|process| opened_project(process).is_some_and(|path| path == project))
+        .map(|process| EditorProcess { pid: process.pid, command: process.args.join(" ") })
+        .collect();
+    for process in &terminated {
+        terminate(process.pid);
+    }
+
+    let mut removed = vec![];
+    let unity_lock = project.join("Temp/UnityLockfile");
+    if unity_lock.exists() && !editor_has_project(&project) {
+        fs::remove_file(&unity_lock)?;
+        removed.push(unity_lock);
+    }
+    let run_lock = state_dir(&project).join("run.lock");
+    if is_stale(&run_lock) {
+        fs::remove_file(&run_lock)?;
+        removed.push(run_lock);
+    }
+    Ok(Cleanup { terminated, removed })
+}
+
+struct Process {
+    pid: u32,
+    args: Vec<String>,
+
+    /// For resolving a relative `-projectPath`
+    cwd: Option<PathBuf>,
+}
+
+/// The project a process was started with, if any.
+fn opened_project(process: &Process) -> Option<PathBuf> {
+    let idx = process.args.iter().position(|arg| arg.eq_ignore_ascii_case("-projectPath"))?;
+    let path = PathBuf::from(process.args.get(idx + 1)?);
+    let path = match &process.cwd {
+        Some(cwd) if path.is_relative() => cwd.join(path),
+        _ => path,
+    };
+    path.canonicalize().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn processes() -> Vec<Process> {
+    let Ok(procs) = fs::read_dir("/proc") else { return vec![] };
+    procs.flatten()
+        .filter_map(|proc| {
+            let pid = proc.file_name().to_str()?.parse().ok()?;
+            let cmdline = fs::read(proc.path().join("cmdline")).ok()?;
+            let args = cmdline.split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).to_string())
+                .collect();
+            let cwd = fs::read_link(proc.path().join("cwd")).ok();
+            Some(Process { pid, args, cwd })
+        })
+        .collect()
+}
+
+/// `ps` doesn't quote arguments, so project paths
+/// with spaces in them won't be matched.
+#[cfg(target_os = "macos")]
+fn processes() -> Vec<Process> {
+    let Ok(output) = Command::new("ps").args(["-axww", "-o", "pid=,args="]).output() else { return vec![] };
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(' ')?;
+            Some(Process {
+                pid: pid.parse().ok()?,
+                args: command.split_whitespace().map(String::from).collect(),
+                cwd: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn processes() -> Vec<Process> {
+    let script = "Get-CimInstance Win32_Process -Filter \"Name = 'Unity.exe'\" \
+        | ForEach-Object { \"$($_.ProcessId) $($_.CommandLine)\" }";
+    let Ok(output) = Command::new("powershell").args(["-NoProfile", "-Command", script]).output() else { return vec![] };
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(' ')?;
+            Some(Process {
+                pid: pid.parse().ok()?,
+                args: split_command_line(command),
+                cwd: None,
+            })
+        })
+        .collect()
+}
+
+/// Split a Windows command line, respecting double quotes.
+#[cfg(windows)]
+fn split_command_line(command: &str) -> Vec<String> {
+    let (mut args, mut arg, mut quoted) = (vec![], String::new(), false);
+    for c in command.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            },
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    args
+}
+
+/// Ask the process to exit, killing it if it's still
+/// running after the grace period.
+fn terminate(pid: u32) {
+    stop(pid, false);
+    let started = Instant::now();
+    while is_running(pid) {
+        if started.elapsed() > GRACE_PERIOD {
+            stop(pid, true);
+            break;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+#[cfg(unix)]
+fn stop(pid: u32, force: bool) {
+    let _ = Command::new("kill")
+        .args(["-s", if force { "KILL" } else { "TERM" }, &pid.to_string()])
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(windows)]
+fn stop(pid: u32, force: bool) {
+    let mut cmd = Command::new("taskkill");
+    cmd.args(["/T", "/PID", &pid.to_string()]);
+    if force {
+        cmd.arg("/F");
+    }
+    let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
@@ -0,0 +1,253 @@
+//! Parsing C# compiler diagnostics from the editor log, e.g.:
+//! `Assets/Foo.cs(12,34): error CS0103: The name 'x' does not exist...`
+//!
+//! Diagnostics from MSBuild-style builds end with the project (and
+//! target framework) they were reported for, e.g. `[Game.csproj]`,
+//! so the same error compiled for several is reported once each.
+//!
+//! Besides the compiler's own `CS` codes, Roslyn analyzers report
+//! diagnostics with their own codes (e.g. `UNT0001`), in the same
+//! format. Any code's severity can be overridden, e.g. to fail on
+//! an analyzer's warning or to ignore one entirely.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{fmt::Display, collections::BTreeMap};
+use crate::{CompileErrors, CompileWarnings};
+
+/// Whether a diagnostic came from the compiler itself
+/// or an analyzer (e.g. a team's own Roslyn analyzers).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSource {
+    Compiler,
+    Analyzer,
+}
+impl DiagnosticSource {
+    /// The compiler's codes are `CS` followed by a number.
+    pub fn of(code: &str) -> DiagnosticSource {
+        match code.strip_prefix("CS") {
+            Some(number) if number.chars().all(|c| c.is_ascii_digit()) => DiagnosticSource::Compiler,
+            _ => DiagnosticSource::Analyzer,
+        }
+    }
+}
+
+/// How to treat diagnostics with a code, regardless of their own severity.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Severity {
+    Error,
+    Warning,
+
+    /// Drop them
+    Ignore,
+}
+
+/// Severity overrides by code, e.g. `UNT0001 = "error"`.
+pub type SeverityOverrides = BTreeMap<String, Severity>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct CompileError {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+
+    /// The diagnostic ID, e.g. `CS0103`
+    pub code: Option<String>,
+
+    pub message: String,
+
+    pub source: DiagnosticSource,
+
+    /// The project (or assembly) it was reported for,
+    /// e.g. `Game.csproj::TargetFramework=net6.0`, if given
+    pub assembly: Option<String>,
+
+    /// The line as it appeared in the log
+    pub raw: String,
+}
+impl CompileError {
+    /// Parse a compiler error line. Anything that doesn't
+    /// match the expected format is kept as just the message.
+    pub fn parse(line: &str) -> CompileError {
+        let raw = line.trim().to_string();
+        parse_diagnostic(&raw).unwrap_or(CompileError {
+            file: None,
+            line: None,
+            column: None,
+            code: None,
+            message: raw.clone(),
+            source: DiagnosticSource::Compiler,
+            assembly: None,
+            raw,
+        })
+    }
+}
+impl Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// The same error, reported for one or more assemblies.
+#[derive(Debug)]
+pub struct ErrorGroup<'a> {
+    pub error: &'a CompileError,
+
+    /// The assemblies it was reported for, where given
+    pub assemblies: Vec<&'a str>,
+}
+
+/// Collapse errors that differ only by the assembly they were
+/// reported for, ordered by file, line and column.
+pub fn group_errors(errors: &CompileErrors) -> Vec<ErrorGroup<'_>> {
+    let mut groups: BTreeMap<_, ErrorGroup> = BTreeMap::new();
+    let mut errors: Vec<&CompileError> = errors.iter().collect();
+    errors.sort();
+    for err in errors {
+        let key = (&err.file, err.line, err.column, &err.code, &err.message);
+        let group = groups.entry(key).or_insert(ErrorGroup { error: err, assemblies: vec![] });
+        group.assemblies.extend(err.assembly.as_deref());
+    }
+    groups.into_values().collect()
+}
+
+/// How many (distinct) errors there are with each
+/// code, most frequent first, e.g. `[("CS0103", 12)]`.
+pub fn count_codes(groups: &[ErrorGroup]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for group in groups {
+        let code = group.error.code.clone().unwrap_or("error".to_string());
+        *counts.entry(code).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+/// Occurrences of the same warning (by code and message),
+/// e.g. one deprecated method called from many places.
+#[derive(Debug, Serialize)]
+pub struct WarningGroup {
+    pub code: Option<String>,
+    pub message: String,
+
+    /// `<file>:<line>` for each occurrence, where known
+    pub locations: Vec<String>,
+
+    pub count: usize,
+}
+
+/// Group warnings by code and message, most frequent first.
+pub fn group_warnings(warnings: &CompileWarnings) -> Vec<WarningGroup> {
+    let mut groups: BTreeMap<(Option<String>, String), WarningGroup> = BTreeMap::new();
+    for warning in warnings {
+        let raw = warning.trim();
+        let (code, message, location) = match parse_diagnostic(raw) {
+            Some(parsed) => {
+                let location = parsed.file.map(|file| match parsed.line {
+                    Some(line) => format!("{}:{}", file, line),
+                    None => file,
+                });
+                (parsed.code, parsed.message, location)
+            },
+            None => (None, raw.to_string(), None),
+        };
+        let group = groups.entry((code.clone(), message.clone()))
+            .or_insert(WarningGroup { code, message, locations: vec![], count: 0 });
+        group.count += 1;
+        group.locations.extend(location);
+    }
+    let mut groups: Vec<WarningGroup> = groups.into_values().collect();
+    for group in &mut groups {
+        group.locations.sort();
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+    groups
+}
+
+/// The severity (`error` or `warning`) and code of the
+/// diagnostic on the line, which is any code of capital
+/// letters then digits, e.g. `CS0103` or `UNT0001`.
+pub(crate) fn diagnostic(line: &str) -> Option<(&'static str, &str)> {
+    ["error", "warning"].into_iter()
+        .find_map(|severity| find_marker(line, severity).map(|(_, code)| (severity, code)))
+}
+
+/// Tools other than the compiler which log diagnostics in the same
+/// form, e.g. `Burst error BC1016:`, and are reported separately
+/// (see [`crate::burst`] and [`crate::build::BuildReport`]).
+const OTHER_TOOLS: &[&str] = &["Burst", "Trim analysis"];
+
+/// Where the diagnostic's `<severity> <code>:` starts, and its code.
+fn find_marker<'a>(line: &'a str, severity: &str) -> Option<(usize, &'a str)> {
+    line.match_indices(severity).find_map(|(idx, _)| {
+        if idx > 0 && !line[..idx].ends_with(' ') { return None }
+        if OTHER_TOOLS.iter().any(|tool| line[..idx].trim_end().ends_with(tool)) { return None }
+        let (code, _) = line[idx + severity.len()..].strip_prefix(' ')?.split_once(':')?;
+        let letters = code.chars().take_while(|c| c.is_ascii_uppercase()).count();
+        let is_code = letters > 0 && code.len() > letters
+            && code[letters..].chars().all(|c| c.is_ascii_digit());
+        is_code.then_some((idx, code))
+    })
+}
+
+/// Parse an error or warning line.
+pub(crate) fn parse_diagnostic(raw: &str) -> Option<CompileError> {
+    let (severity, _) = diagnostic(raw)?;
+    parse_parts(raw, severity)
+}
+
+/// Split `<file>(<line>,<col>): <severity> <code>: <message>`,
+/// where the location is optional.
+pub(crate) fn parse_parts(raw: &str, severity: &str) -> Option<CompileError> {
+    let (idx, _) = find_marker(raw, severity)?;
+    let (location, rest) = raw.split_at(idx);
+    let (code, message) = rest[severity.len() + 1..].split_once(':')?;
+
+    let (file, line, column) = match location.trim_end().strip_suffix("):") {
+        Some(location) => {
+            let (file, pos) = location.rsplit_once('(')?;
+            let (line, column) = pos.split_once(',')?;
+            (Some(file.to_string()), line.trim().parse().ok(), column.trim().parse().ok())
+        },
+        None => (None, None, None),
+    };
+
+    let message = message.trim();
+    let (message, assembly) = match message.strip_suffix(']').and_then(|rest| rest.rsplit_once(" [")) {
+        Some((message, assembly)) => (message.trim_end(), Some(assembly.to_string())),
+        None => (message, None),
+    };
+    let code = code.trim();
+    Some(CompileError {
+        file,
+        line,
+        column,
+        code: Some(code.to_string()),
+        message: message.to_string(),
+        source: DiagnosticSource::of(code),
+        assembly,
+        raw: raw.to_string(),
+    })
+}
+
+/// The log's errors and warnings, with their severities overridden
+/// (and the ignored ones left out).
+pub(crate) fn diagnostics(log: &str, overrides: &SeverityOverrides) -> (CompileErrors, CompileWarnings) {
+    let (mut errors, mut warnings) = (CompileErrors::new(), CompileWarnings::new());
+    for line in log.lines() {
+        let Some((severity, code)) = diagnostic(line) else { continue };
+        let severity = match overrides.get(code) {
+            Some(severity) => *severity,
+            None if severity == "error" => Severity::Error,
+            None => Severity::Warning,
+        };
+        match severity {
+            Severity::Error => { errors.insert(CompileError::parse(line)); },
+            Severity::Warning => { warnings.insert(line.to_string()); },
+            Severity::Ignore => {},
+        }
+    }
+    (errors, warnings)
+}
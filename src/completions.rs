@@ -0,0 +1,288 @@
+//! Generating shell completion scripts from the CLI's definition,
+//! covering subcommands, flags and their values (including the
+//! installed editor versions, for `--version`).
+
+use clap::{Arg, Command, ValueEnum, ValueHint};
+
+/// The hidden subcommand the scripts call
+/// to list the installed editor versions.
+pub const VERSIONS_COMMAND: &str = "__versions";
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// What to complete a flag's value with.
+#[derive(Debug, PartialEq)]
+enum Values {
+    /// The flag takes no value
+    None,
+    Choices(Vec<String>),
+    Paths,
+    /// The installed editor versions
+    Versions,
+    /// Anything, so nothing's suggested
+    Any,
+}
+
+struct Flag {
+    /// e.g. `-m` and `--mode`
+    names: Vec<String>,
+    values: Values,
+    help: String,
+}
+
+/// A command or subcommand, e.g. `unitool report merge`.
+struct CommandInfo {
+    path: String,
+    subcommands: Vec<(String, String)>,
+    flags: Vec<Flag>,
+
+    /// Whether its positional arguments are paths
+    takes_paths: bool,
+}
+
+/// Generate a completion script for the command.
+pub fn generate(shell: Shell, mut cmd: Command) -> String {
+    // Propagates the global flags to the subcommands
+    cmd.build();
+    let name = cmd.get_name().to_string();
+    let mut commands = vec![];
+    collect(&cmd, &name, &mut commands);
+    match shell {
+        Shell::Bash => bash(&name, &commands),
+        Shell::Zsh => format!("#compdef {}\nautoload -U +X bashcompinit && bashcompinit\n{}", name, bash(&name, &commands)),
+        Shell::Fish => fish(&name, &commands),
+        Shell::Powershell => powershell(&name, &commands),
+    }
+}
+
+fn collect(cmd: &Command, path: &str, commands: &mut Vec<CommandInfo>) {
+    let subcommands: Vec<&Command> = cmd.get_subcommands()
+        // clap's `help` subcommands just mirror the tree
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+        .collect();
+    let flags = cmd.get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .map(|arg| {
+            let mut names = vec![];
+            names.extend(arg.get_short_and_visible_aliases().unwrap_or_default()
+                .into_iter().map(|short| format!("-{}", short)));
+            names.extend(arg.get_long_and_visible_aliases().unwrap_or_default()
+                .into_iter().map(|long| format!("--{}", long)));
+            Flag { names, values: values(arg), help: first_line(arg.get_help()) }
+        })
+        .collect();
+    let takes_paths = cmd.get_positionals()
+        .any(|arg| values(arg) == Values::Paths);
+    commands.push(CommandInfo {
+        path: path.to_string(),
+        subcommands: subcommands.iter()
+            .map(|sub| (sub.get_name().to_string(), first_line(sub.get_about())))
+            .collect(),
+        flags,
+        takes_paths,
+    });
+    for sub in subcommands {
+        collect(sub, &format!("{} {}", path, sub.get_name()), commands);
+    }
+}
+
+fn values(arg: &Arg) -> Values {
+    if !arg.get_action().takes_values() {
+        return Values::None;
+    }
+    if arg.get_id() == "version" {
+        return Values::Versions;
+    }
+    let choices: Vec<String> = arg.get_possible_values().iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !choices.is_empty() {
+        return Values::Choices(choices);
+    }
+    match arg.get_value_hint() {
+        ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath | ValueHint::ExecutablePath => Values::Paths,
+        _ => Values::Any,
+    }
+}
+
+fn first_line(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(|help| help.to_string().lines().next().unwrap_or("").to_string())
+        .unwrap_or_default()
+}
+
+/// The subcommand paths, e.g. `"unitool report merge"`,
+/// for finding how far into the command the line is.
+fn subcommand_paths(commands: &[CommandInfo]) -> Vec<&str> {
+    commands.iter().skip(1).map(|cmd| cmd.path.as_str()).collect()
+}
+
+fn bash(name: &str, commands: &[CommandInfo]) -> String {
+    let func = format!("_{}", name.replace('-', "_"));
+    let paths = subcommand_paths(commands).iter()
+        .map(|path| format!("\"{}\"", path))
+        .collect::<Vec<_>>()
+        .join("|");
+    let mut lines = vec![
+        format!("{}() {{", func),
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"".to_string(),
+        format!("    local path=\"{}\" flags=\"\" commands=\"\" files=\"\" i", name),
+        "    for ((i = 1; i < COMP_CWORD; i++)); do".to_string(),
+        "        case \"$path ${COMP_WORDS[i]}\" in".to_string(),
+        format!("            {}) path=\"$path ${{COMP_WORDS[i]}}\" ;;", paths),
+        "        esac".to_string(),
+        "    done".to_string(),
+        "    case \"$path\" in".to_string(),
+    ];
+    for cmd in commands {
+        lines.push(format!("        \"{}\")", cmd.path));
+        lines.push("            case \"$prev\" in".to_string());
+        for flag in &cmd.flags {
+            let reply = match &flag.values {
+                Values::None => continue,
+                Values::Choices(choices) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", choices.join(" ")),
+                Values::Paths => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+                Values::Versions => format!("COMPREPLY=($(compgen -W \"$({} {} 2>/dev/null)\" -- \"$cur\"))", name, VERSIONS_COMMAND),
+                Values::Any => "COMPREPLY=()".to_string(),
+            };
+            lines.push(format!("                {}) {}; return ;;", flag.names.join("|"), reply));
+        }
+        lines.push("            esac".to_string());
+        let flags = cmd.flags.iter().flat_map(|flag| flag.names.iter().cloned()).collect::<Vec<_>>();
+        let subcommands = cmd.subcommands.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        lines.push(format!("            flags=\"{}\"", flags.join(" ")));
+        lines.push(format!("            commands=\"{}\"", subcommands.join(" ")));
+        if cmd.takes_paths {
+            lines.push("            files=1".to_string());
+        }
+        lines.push("            ;;".to_string());
+    }
+    lines.extend([
+        "    esac",
+        "    if [[ \"$cur\" == -* ]]; then",
+        "        COMPREPLY=($(compgen -W \"$flags\" -- \"$cur\"))",
+        "    else",
+        "        COMPREPLY=($(compgen -W \"$commands\" -- \"$cur\"))",
+        "        [[ -n \"$files\" ]] && COMPREPLY+=($(compgen -f -- \"$cur\"))",
+        "    fi",
+        "}",
+    ].map(String::from));
+    lines.push(format!("complete -o filenames -F {} {}", func, name));
+    lines.join("\n")
+}
+
+fn fish(name: &str, commands: &[CommandInfo]) -> String {
+    let func = format!("__{}_path", name.replace('-', "_"));
+    let paths = subcommand_paths(commands).iter()
+        .map(|path| format!("\"{}\"", path))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut lines = vec![
+        format!("function {}", func),
+        format!("    set -l path {}", name),
+        "    for word in (commandline -opc)[2..-1]".to_string(),
+        "        switch \"$path $word\"".to_string(),
+        format!("            case {}", paths),
+        "                set path \"$path $word\"".to_string(),
+        "        end".to_string(),
+        "    end".to_string(),
+        "    echo $path".to_string(),
+        "end".to_string(),
+        "".to_string(),
+        format!("complete -c {} -f", name),
+    ];
+    for cmd in commands {
+        let cond = format!("-n '[ \"({})\" = \"{}\" ]'", func, cmd.path);
+        for (sub, help) in &cmd.subcommands {
+            lines.push(format!("complete -c {} {} -a '{}' -d '{}'", name, cond, sub, fish_escape(help)));
+        }
+        for flag in &cmd.flags {
+            let mut parts = vec![format!("complete -c {} {}", name, cond)];
+            for flag_name in &flag.names {
+                match flag_name.strip_prefix("--") {
+                    Some(long) => parts.push(format!("-l {}", long)),
+                    None => parts.push(format!("-s {}", &flag_name[1..])),
+                }
+            }
+            match &flag.values {
+                Values::None => {},
+                Values::Choices(choices) => parts.push(format!("-r -f -a '{}'", choices.join(" "))),
+                Values::Paths => parts.push("-r -F".to_string()),
+                Values::Versions => parts.push(format!("-r -f -a '({} {} 2>/dev/null)'", name, VERSIONS_COMMAND)),
+                Values::Any => parts.push("-r -f".to_string()),
+            }
+            if !flag.help.is_empty() {
+                parts.push(format!("-d '{}'", fish_escape(&flag.help)));
+            }
+            lines.push(parts.join(" "));
+        }
+        if cmd.takes_paths {
+            lines.push(format!("complete -c {} {} -F", name, cond));
+        }
+    }
+    lines.join("\n")
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Returning nothing falls back to PowerShell's own path
+/// completion, which covers paths and positional arguments.
+fn powershell(name: &str, commands: &[CommandInfo]) -> String {
+    let paths = subcommand_paths(commands).iter()
+        .map(|path| format!("'{}'", path))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut lines = vec![
+        format!("Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{", name),
+        "    param($wordToComplete, $commandAst, $cursorPosition)".to_string(),
+        "    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })".to_string(),
+        "    if ($wordToComplete -ne '') { $words = @($words | Select-Object -SkipLast 1) }".to_string(),
+        format!("    $subcommands = @({})", paths),
+        format!("    $path = '{}'", name),
+        "    foreach ($word in $words) {".to_string(),
+        "        if ($subcommands -contains \"$path $word\") { $path = \"$path $word\" }".to_string(),
+        "    }".to_string(),
+        "    $prev = if ($words.Count -gt 0) { $words[-1] } else { '' }".to_string(),
+        "    $flags = @(); $commands = @(); $values = @{}".to_string(),
+        "    switch ($path) {".to_string(),
+    ];
+    for cmd in commands {
+        let quote = |items: Vec<&str>| items.iter().map(|item| format!("'{}'", item)).collect::<Vec<_>>().join(", ");
+        lines.push(format!("        '{}' {{", cmd.path));
+        lines.push(format!("            $flags = @({})",
+            quote(cmd.flags.iter().flat_map(|flag| flag.names.iter().map(String::as_str)).collect())));
+        lines.push(format!("            $commands = @({})",
+            quote(cmd.subcommands.iter().map(|(name, _)| name.as_str()).collect())));
+        for flag in &cmd.flags {
+            let values = match &flag.values {
+                Values::None => continue,
+                Values::Choices(choices) => format!("@({})", quote(choices.iter().map(String::as_str).collect())),
+                Values::Versions => format!("@({} {} 2>$null)", name, VERSIONS_COMMAND),
+                Values::Paths | Values::Any => "@()".to_string(),
+            };
+            for flag_name in &flag.names {
+                lines.push(format!("            $values['{}'] = {}", flag_name, values));
+            }
+        }
+        lines.push("        }".to_string());
+    }
+    lines.extend([
+        "    }",
+        "    $candidates = if ($values.ContainsKey($prev)) { $values[$prev] }",
+        "        elseif ($wordToComplete -like '-*') { $flags }",
+        "        else { $commands }",
+        "    $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {",
+        "        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)",
+        "    }",
+        "}",
+    ].map(String::from));
+    lines.join("\n")
+}
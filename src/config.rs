@@ -0,0 +1,283 @@
+//! Per-project defaults, from a `unitool.toml` in the
+//! project root, so teams don't have to pass the same
+//! flags on every invocation. For example:
+//!
+//! ```toml
+//! timeout = 600
+//! output = "text"
+//! license_seats = 2
+//! retries = 2
+//!
+//! [test]
+//! mode = "edit-mode"
+//! assemblies = "EditTests;PlayTests"
+//! categories = "Fast"
+//!
+//! [diagnostics]
+//! UNT0001 = "error"
+//! CS0618 = "ignore"
+//! ```
+//!
+//! Only the subset of TOML needed for this is supported:
+//! tables, strings, integers, booleans and comments.
+
+use anyhow::{Result, bail, anyhow};
+use clap::ValueEnum;
+use std::{fs, path::{Path, PathBuf}};
+use crate::{TestMode, compiler::{Severity, SeverityOverrides}};
+
+/// The config file name, looked for in the project root.
+pub const CONFIG_FILE: &str = "unitool.toml";
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Relative paths are resolved against the config's directory
+    pub unity_path: Option<PathBuf>,
+
+    /// In seconds
+    pub timeout: Option<u64>,
+
+    /// The output format's name, e.g. `json`
+    pub output: Option<String>,
+
+    pub verbose: Option<bool>,
+
+    /// The color mode's name, e.g. `never`
+    pub color: Option<String>,
+
+    /// The lock mode's name, e.g. `wait`
+    pub lock: Option<String>,
+
+    pub license_seats: Option<usize>,
+
+    /// How many times to retry transient editor failures
+    pub retries: Option<u32>,
+
+    /// In seconds
+    pub retry_backoff: Option<u64>,
+
+    /// How many full editor logs to keep
+    pub keep_logs: Option<usize>,
+
+    /// Lines of source to show around errors and failures
+    pub context: Option<usize>,
+
+    /// The Docker image to run the editor in, empty
+    /// for the GameCI image for the project's version
+    pub docker: Option<String>,
+
+    /// A webhook to post each run's outcome to
+    pub notify: Option<String>,
+
+    /// Show each run's outcome as a desktop notification
+    pub notify_desktop: Option<bool>,
+
+    /// Explain common compile errors
+    pub explain: Option<bool>,
+
+    /// When to open the first failure, e.g. `ask`
+    pub open_editor: Option<String>,
+
+    /// Let the API updater update scripts
+    pub accept_api_update: Option<bool>,
+
+    /// The command to open a failure with, e.g.
+    /// `code --goto {file}:{line}:{column}`
+    pub editor_command: Option<String>,
+
+    pub test: TestConfig,
+
+    /// The `[diagnostics]` table, of severities by code
+    pub diagnostics: SeverityOverrides,
+}
+
+/// The `[test]` table.
+#[derive(Debug, Default)]
+pub struct TestConfig {
+    pub mode: Option<TestMode>,
+    pub assemblies: Option<String>,
+    pub filters: Option<String>,
+    pub categories: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+impl Value {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Int(_) => "an integer",
+            Value::Bool(_) => "a boolean",
+        }
+    }
+}
+
+impl Config {
+    /// Load the project's config, if it has one.
+    pub fn for_project(project_path: &Path) -> Result<Config> {
+        let path = project_path.join(CONFIG_FILE);
+        if path.exists() {
+            Config::load(&path)
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Config> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Couldn't read {}: {}", path.display(), err))?;
+        let mut config = Config::parse(&src)
+            .map_err(|err| anyhow!("{}:{}", path.display(), err))?;
+        if let (Some(unity_path), Some(dir)) = (&config.unity_path, path.parent()) {
+            config.unity_path = Some(dir.join(unity_path));
+        }
+        Ok(config)
+    }
+
+    /// Errors are prefixed with the line number.
+    fn parse(src: &str) -> Result<Config> {
+        let mut config = Config::default();
+        let mut table = String::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let line_no = i + 1;
+
+            if let Some(rest) = line.strip_prefix('[') {
+                let Some((name, rest)) = rest.split_once(']') else {
+                    bail!("{}: Unclosed table header", line_no);
+                };
+                if !is_comment(rest) {
+                    bail!("{}: Unexpected text after table header", line_no);
+                }
+                table = name.trim().to_string();
+                if table != "test" && table != "diagnostics" {
+                    bail!("{}: Unknown table `{}`", line_no, table);
+                }
+                continue;
+            }
+
+            let Some((key, rest)) = line.split_once('=') else {
+                bail!("{}: Expected `key = value`", line_no);
+            };
+            let (value, rest) = parse_value(rest.trim())
+                .map_err(|err| anyhow!("{}: {}", line_no, err))?;
+            if !is_comment(rest) {
+                bail!("{}: Unexpected text after value", line_no);
+            }
+            config.set(&table, key.trim(), value)
+                .map_err(|err| anyhow!("{}: {}", line_no, err))?;
+        }
+        Ok(config)
+    }
+
+    fn set(&mut self, table: &str, key: &str, value: Value) -> Result<()> {
+        match (table, key, value) {
+            ("", "unity_path", Value::Str(s)) => self.unity_path = Some(s.into()),
+            ("", "timeout", Value::Int(n)) => self.timeout = Some(non_negative(n)? as u64),
+            ("", "output", Value::Str(s)) => self.output = Some(s),
+            ("", "verbose", Value::Bool(b)) => self.verbose = Some(b),
+            ("", "color", Value::Str(s)) => self.color = Some(s),
+            ("", "lock", Value::Str(s)) => self.lock = Some(s),
+            ("", "license_seats", Value::Int(n)) => self.license_seats = Some(non_negative(n)? as usize),
+            ("", "retries", Value::Int(n)) => self.retries = Some(non_negative(n)? as u32),
+            ("", "retry_backoff", Value::Int(n)) => self.retry_backoff = Some(non_negative(n)? as u64),
+            ("", "keep_logs", Value::Int(n)) => self.keep_logs = Some(non_negative(n)? as usize),
+            ("", "context", Value::Int(n)) => self.context = Some(non_negative(n)? as usize),
+            ("", "docker", Value::Str(s)) => self.docker = Some(s),
+            ("", "docker", Value::Bool(b)) => self.docker = b.then(String::new),
+            ("", "notify", Value::Str(s)) => self.notify = Some(s),
+            ("", "notify_desktop", Value::Bool(b)) => self.notify_desktop = Some(b),
+            ("", "explain", Value::Bool(b)) => self.explain = Some(b),
+            ("", "accept_api_update", Value::Bool(b)) => self.accept_api_update = Some(b),
+            ("", "open_editor", Value::Str(s)) => self.open_editor = Some(s),
+            ("", "editor_command", Value::Str(s)) => self.editor_command = Some(s),
+            ("test", "mode", Value::Str(s)) => self.test.mode = Some(TestMode::from_str(&s, true)
+                .map_err(|_| anyhow!("Unknown test mode `{}`", s))?),
+            ("test", "assemblies", Value::Str(s)) => self.test.assemblies = Some(s),
+            ("test", "filters", Value::Str(s)) => self.test.filters = Some(s),
+            ("test", "categories", Value::Str(s)) => self.test.categories = Some(s),
+            ("diagnostics", code, Value::Str(s)) => {
+                let severity = Severity::from_str(&s, true)
+                    .map_err(|_| anyhow!("Unknown severity `{}` for `{}`; expected `error`, `warning` or `ignore`", s, code))?;
+                self.diagnostics.insert(code.trim_matches('"').to_string(), severity);
+            },
+            (table, key, value) => {
+                let name = if table.is_empty() { key.to_string() } else { format!("{}.{}", table, key) };
+                match (table, key) {
+                    ("", "unity_path" | "output" | "color" | "lock" | "notify" | "open_editor" | "editor_command")
+                        | ("test", "mode" | "assemblies" | "filters" | "categories")
+                        | ("diagnostics", _) =>
+                        bail!("`{}` should be a string, not {}", name, value.kind()),
+                    ("", "timeout" | "license_seats" | "retries" | "retry_backoff" | "keep_logs" | "context") =>
+                        bail!("`{}` should be an integer, not {}", name, value.kind()),
+                    ("", "verbose" | "notify_desktop" | "explain" | "accept_api_update") =>
+                        bail!("`{}` should be a boolean, not {}", name, value.kind()),
+                    ("", "docker") =>
+                        bail!("`{}` should be an image name or a boolean, not {}", name, value.kind()),
+                    _ => bail!("Unknown key `{}`", name),
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+fn non_negative(n: i64) -> Result<i64> {
+    if n < 0 { bail!("Expected a non-negative integer") }
+    Ok(n)
+}
+
+/// Whether what's left of a line is blank or a comment.
+pub(crate) fn is_comment(rest: &str) -> bool {
+    let rest = rest.trim();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+/// Parse a value from the start of the text,
+/// returning it and whatever follows it.
+pub(crate) fn parse_value(text: &str) -> Result<(Value, &str)> {
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::Str(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => bail!("Unsupported escape `\\{}`", c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        bail!("Unclosed string");
+    }
+
+    // Literal strings have no escapes, which is handy for Windows paths
+    if let Some(rest) = text.strip_prefix('\'') {
+        let Some((value, rest)) = rest.split_once('\'') else {
+            bail!("Unclosed string");
+        };
+        return Ok((Value::Str(value.to_string()), rest));
+    }
+
+    let end = text.find(|c: char| c.is_whitespace() || c == '#').unwrap_or(text.len());
+    let (token, rest) = text.split_at(end);
+    let value = match token {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match token.replace('_', "").parse() {
+            Ok(n) => Value::Int(n),
+            Err(_) => bail!("Unsupported value `{}`", token),
+        },
+    };
+    Ok((value, rest))
+}
@@ -0,0 +1,173 @@
+//! Code coverage from Unity's Code Coverage package
+//! (`com.unity.testtools.codecoverage`), which writes
+//! OpenCover XML under the results path, e.g.
+//! `<path>/<Project>-opencov/EditMode/TestCoverageResults_0000.xml`.
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use quick_xml::{Reader, events::Event};
+use serde::Serialize;
+use std::{fs, fmt::Display, collections::BTreeMap, path::{Path, PathBuf}};
+use crate::{state_dir, display::*};
+
+/// Covered and total counts.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Counts {
+    pub covered: usize,
+    pub total: usize,
+}
+impl Counts {
+    pub fn percent(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(self.covered as f64 / self.total as f64 * 100.)
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssemblyCoverage {
+    pub name: String,
+    pub lines: Counts,
+    pub branches: Counts,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub assemblies: Vec<AssemblyCoverage>,
+}
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name_width = self.assemblies.iter()
+            .map(|assembly| assembly.name.chars().count())
+            .max().unwrap_or(0);
+        let mut lines = vec![format!("{:name_width$} {:>7} {:>9}", "", "Lines", "Branches").bold().to_string()];
+        for assembly in &self.assemblies {
+            lines.push(format!("{:name_width$} {} {}",
+                assembly.name,
+                colored_percent(assembly.lines, 7),
+                colored_percent(assembly.branches, 9)));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn colored_percent(counts: Counts, width: usize) -> String {
+    match counts.percent() {
+        Some(percent) => {
+            let color = if percent >= 80. {
+                green
+            } else if percent >= 50. {
+                yellow
+            } else {
+                red
+            };
+            align_right(&format!("{:.1}%", percent), width, color).to_string()
+        },
+        None => align_right("-", width, muted).to_string(),
+    }
+}
+
+/// Where coverage results are written.
+pub(crate) fn coverage_dir(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("coverage")
+}
+
+/// The editor arguments to enable coverage. Only the project's
+/// own assemblies are covered, minus the test assemblies.
+pub(crate) fn coverage_args(results_dir: &Path, test_assemblies: &str) -> Vec<String> {
+    let mut filters = vec!["+<assets>".to_string()];
+    filters.extend(test_assemblies.split(';')
+        .filter(|name| !name.is_empty())
+        .map(|name| format!("-{}", name)));
+    vec![
+        "-enableCodeCoverage".to_string(),
+        "-coverageResultsPath".to_string(),
+        results_dir.to_string_lossy().to_string(),
+        "-coverageOptions".to_string(),
+        format!("generateAdditionalMetrics;assemblyFilters:{}", filters.join(",")),
+    ]
+}
+
+/// Load the coverage results from the project's last test run.
+pub fn load_coverage(project_path: &Path) -> Result<CoverageReport> {
+    let mut files = vec![];
+    find_results(&coverage_dir(project_path), &mut files);
+    if files.is_empty() {
+        bail!("No coverage results were written; is the Code Coverage package (com.unity.testtools.codecoverage) installed?");
+    }
+    files.sort();
+
+    // Points are keyed by their ID, so that a point in several
+    // results files (e.g. from both test modes) is counted once
+    let mut points: BTreeMap<String, BTreeMap<(bool, String), bool>> = BTreeMap::new();
+    for file in files {
+        parse_opencover(&fs::read_to_string(&file)?, &mut points)?;
+    }
+
+    let assemblies = points.into_iter()
+        .map(|(name, points)| {
+            let mut lines = Counts::default();
+            let mut branches = Counts::default();
+            for ((is_branch, _), visited) in points {
+                let counts = if is_branch { &mut branches } else { &mut lines };
+                counts.total += 1;
+                counts.covered += visited as usize;
+            }
+            AssemblyCoverage { name, lines, branches }
+        })
+        .filter(|assembly| assembly.lines.total > 0)
+        .collect();
+    Ok(CoverageReport { assemblies })
+}
+
+fn find_results(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_results(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "xml") {
+            files.push(path);
+        }
+    }
+}
+
+/// Collect each module's sequence (line) and branch
+/// points, and whether they were visited.
+fn parse_opencover(xml: &str, points: &mut BTreeMap<String, BTreeMap<(bool, String), bool>>) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    let mut module = String::new();
+    let mut in_module_name = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"Module" => module.clear(),
+                b"ModuleName" => in_module_name = true,
+                name @ (b"SequencePoint" | b"BranchPoint") => {
+                    let attr = |key: &str| -> Result<String> {
+                        Ok(match e.try_get_attribute(key)? {
+                            Some(attr) => attr.unescape_value()?.to_string(),
+                            None => String::new(),
+                        })
+                    };
+                    let is_branch = name == b"BranchPoint";
+                    let id = attr("uspid")?;
+                    let visited = attr("vc")?.parse::<usize>().unwrap_or(0) > 0;
+                    let visited_before = points.entry(module.clone()).or_default()
+                        .entry((is_branch, id)).or_insert(false);
+                    *visited_before |= visited;
+                },
+                _ => {},
+            },
+            Event::Text(text) if in_module_name => {
+                module = text.unescape()?.trim().to_string();
+            },
+            Event::End(e) if e.name().as_ref() == b"ModuleName" => in_module_name = false,
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Detecting editor crashes. A crashed editor logs no
+//! compile errors, so without this a crash would look
+//! like a successful compile (or missing test results).
+
+use std::{fmt::Display, process::ExitStatus};
+use crate::display::*;
+
+/// Lines that start a native crash report in the log.
+const CRASH_MARKERS: &[&str] = &[
+    "Caught fatal signal",
+    "Native Crash Reporting",
+    "Crash!!!",
+];
+
+/// The most lines of a native stack trace to keep.
+const MAX_STACK_LINES: usize = 40;
+
+/// The editor crashed, rather than exiting normally.
+#[derive(Debug)]
+pub struct EditorCrashed {
+    /// The exit code, if the editor exited on its own
+    pub exit_code: Option<i32>,
+
+    /// The signal that killed the editor, if any
+    pub signal: Option<i32>,
+
+    /// The native stack trace, if one was logged
+    pub stack_trace: Vec<String>,
+
+    /// The last lines of the editor log
+    pub log_tail: Vec<String>,
+}
+impl Display for EditorCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let how = match (self.signal, self.exit_code) {
+            (Some(signal), _) => format!("was killed by signal {}", signal),
+            (None, Some(code)) => format!("exited with code {}", code),
+            (None, None) => "exited abnormally".to_string(),
+        };
+        let mut lines = vec![red(&format!("Unity crashed ({}).", how)).to_string()];
+        if !self.stack_trace.is_empty() {
+            lines.push("Native stack trace:".to_string());
+            lines.push(muted(&indent(&self.stack_trace.join("\n"))).to_string());
+        }
+        if !self.log_tail.is_empty() {
+            lines.push(format!("Last {} lines of the editor log:", self.log_tail.len()));
+            lines.push(muted(&indent(&self.log_tail.join("\n"))).to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for EditorCrashed {}
+
+/// Check whether the editor crashed, going by how it exited
+/// and whether it logged a crash report. Unity's own exit codes
+/// (0-3, e.g. 2 for failed tests) aren't crashes on their own.
+pub(crate) fn detect(status: &ExitStatus, log: &str, log_tail: &[String]) -> Option<EditorCrashed> {
+    let signal = signal(status);
+    let abnormal_exit = signal.is_some()
+        || !matches!(status.code(), Some(0..=3));
+    let report_start = log.lines()
+        .position(|line| CRASH_MARKERS.iter().any(|marker| line.contains(marker)));
+    if !abnormal_exit && report_start.is_none() {
+        return None;
+    }
+
+    let stack_trace = match report_start {
+        Some(start) => log.lines()
+            .skip(start + 1)
+            .skip_while(|line| line.trim().is_empty())
+            .take_while(|line| !line.trim().is_empty())
+            .take(MAX_STACK_LINES)
+            .map(|line| line.to_string())
+            .collect(),
+        None => vec![],
+    };
+    Some(EditorCrashed {
+        exit_code: status.code(),
+        signal,
+        stack_trace,
+        log_tail: log_tail.to_vec(),
+    })
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(windows)]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
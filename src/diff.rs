@@ -0,0 +1,100 @@
+//! Comparing two test runs, e.g. a PR's against main's,
+//! to see exactly which tests regressed or were fixed.
+
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
+use crate::{TestResult, TestSummary, history::RunRecord, display::*};
+
+/// A test that's only in one of the runs.
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    /// The test's full name
+    pub name: String,
+    pub result: TestResult,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TestDiff {
+    /// Tests that failed in the new run but not the old
+    pub newly_failing: Vec<String>,
+
+    /// Tests that failed in the old run but passed in the new
+    pub newly_passing: Vec<String>,
+
+    /// Tests only in the new run
+    pub added: Vec<DiffEntry>,
+
+    /// Tests only in the old run
+    pub removed: Vec<DiffEntry>,
+
+    /// How many tests are in both runs with the same result
+    pub unchanged: usize,
+}
+impl TestDiff {
+    /// Compare runs' results, by the tests' full names.
+    pub fn new(old: &Results, new: &Results) -> TestDiff {
+        let mut diff = TestDiff::default();
+        for (name, result) in &new.0 {
+            match old.0.get(name) {
+                None => diff.added.push(DiffEntry { name: name.clone(), result: result.clone() }),
+                Some(prev) if prev == result => diff.unchanged += 1,
+                Some(_) if *result == TestResult::Failed => diff.newly_failing.push(name.clone()),
+                Some(TestResult::Failed) if *result == TestResult::Passed => diff.newly_passing.push(name.clone()),
+                // Newly skipped, or no longer skipped
+                Some(_) => {},
+            }
+        }
+        for (name, result) in &old.0 {
+            if !new.0.contains_key(name) {
+                diff.removed.push(DiffEntry { name: name.clone(), result: result.clone() });
+            }
+        }
+        diff
+    }
+
+    /// Whether any test newly fails, including added ones.
+    pub fn regressed(&self) -> bool {
+        !self.newly_failing.is_empty()
+            || self.added.iter().any(|entry| entry.result == TestResult::Failed)
+    }
+}
+impl Display for TestDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        let mut section = |title: String, names: Vec<String>| {
+            if names.is_empty() {
+                return;
+            }
+            lines.push(title);
+            lines.extend(names.iter().map(|name| format!("  {}", name)));
+        };
+        section(red(&format!("{} newly failing:", self.newly_failing.len())).to_string(),
+            self.newly_failing.clone());
+        section(green(&format!("{} newly passing:", self.newly_passing.len())).to_string(),
+            self.newly_passing.clone());
+        section(format!("{} added:", self.added.len()),
+            self.added.iter().map(|entry| format!("{} {}", entry.result, entry.name)).collect());
+        section(muted(&format!("{} removed:", self.removed.len())).to_string(),
+            self.removed.iter().map(|entry| entry.name.clone()).collect());
+        lines.push(muted(&format!("{} unchanged", self.unchanged)).to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A run's results, by test full name.
+#[derive(Debug, Default)]
+pub struct Results(BTreeMap<String, TestResult>);
+impl From<&TestSummary> for Results {
+    fn from(summary: &TestSummary) -> Results {
+        Results(summary.cases().iter()
+            .map(|case| (case.key().to_string(), case.result.clone()))
+            .collect())
+    }
+}
+impl From<&RunRecord> for Results {
+    fn from(record: &RunRecord) -> Results {
+        Results(record.tests.iter()
+            .map(|test| (test.name.clone(), test.result.clone()))
+            .collect())
+    }
+}
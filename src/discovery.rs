@@ -0,0 +1,94 @@
+//! Listing the project's tests without running them, through an
+//! injected script (see `scripts/ListTests.cs`) which logs the
+//! test runner's tree of tests for us to parse.
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::{build::MARKER, display::*};
+
+/// A test found by the test runner.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredTest {
+    /// e.g. `Game.Core.MathTests.Divides(4,2)`
+    pub full_name: String,
+
+    pub assembly: String,
+
+    /// `EditMode` or `PlayMode`
+    pub mode: String,
+
+    pub categories: Vec<String>,
+}
+impl DiscoveredTest {
+    /// The test's fixture (i.e. class), e.g. `Game.Core.MathTests`.
+    pub fn fixture(&self) -> &str {
+        // Parameters may have dots in them
+        let name = self.full_name.split('(').next().unwrap_or(&self.full_name);
+        name.rsplit_once('.').map(|(fixture, _)| fixture).unwrap_or("")
+    }
+
+    /// The name within its fixture, e.g. `Divides(4,2)`.
+    pub fn name(&self) -> &str {
+        self.full_name[self.fixture().len()..].trim_start_matches('.')
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestList {
+    pub tests: Vec<DiscoveredTest>,
+}
+impl TestList {
+    /// Parse the tests from the editor log, if the
+    /// script got to the end of listing them.
+    pub(crate) fn parse(log: &str) -> Option<TestList> {
+        let mut tests = vec![];
+        let mut done = false;
+        for line in log.lines() {
+            let Some(rest) = line.strip_prefix(MARKER) else { continue };
+            if rest.trim_end() == "done" {
+                done = true;
+                continue;
+            }
+            let Some(test) = rest.strip_prefix("test ") else { continue };
+            let mut fields = test.trim_end_matches('\r').split('\t');
+            let (Some(mode), Some(assembly), Some(full_name)) = (fields.next(), fields.next(), fields.next()) else { continue };
+            let categories = fields.next().unwrap_or("").split(';')
+                .filter(|category| !category.is_empty())
+                .map(String::from)
+                .collect();
+            tests.push(DiscoveredTest {
+                full_name: full_name.to_string(),
+                assembly: assembly.to_string(),
+                mode: mode.to_string(),
+                categories,
+            });
+        }
+        if done { Some(TestList { tests }) } else { None }
+    }
+}
+impl Display for TestList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // By mode and assembly, then by fixture
+        let mut lines = vec![];
+        let (mut assembly, mut fixture) = (None, None);
+        for test in &self.tests {
+            if assembly != Some((&test.mode, &test.assembly)) {
+                let mode = if test.mode == "PlayMode" { "play mode" } else { "edit mode" };
+                lines.push(format!("{} {}", green(&test.assembly), muted(&format!("({})", mode))));
+                assembly = Some((&test.mode, &test.assembly));
+                fixture = None;
+            }
+            if fixture != Some(test.fixture()) {
+                lines.push(format!("  {}", test.fixture()));
+                fixture = Some(test.fixture());
+            }
+            let mut line = format!("    {}", test.name());
+            if !test.categories.is_empty() {
+                line.push_str(&format!(" {}", muted(&format!("[{}]", test.categories.join(", ")))));
+            }
+            lines.push(line);
+        }
+        lines.push(muted(&format!("{} tests", self.tests.len())).to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -1,5 +1,27 @@
+use clap::ValueEnum;
 use colored::{Colorize, ColoredString};
 
+/// When to color output.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum ColorMode {
+    /// Color when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Set whether all output is colored. With `Auto` it's left to
+/// `colored`'s detection, which checks for a terminal and honors
+/// `NO_COLOR` and `CLICOLOR_FORCE`.
+pub fn set_color_mode(mode: ColorMode) {
+    match mode {
+        ColorMode::Auto => colored::control::unset_override(),
+        ColorMode::Always => colored::control::set_override(true),
+        ColorMode::Never => colored::control::set_override(false),
+    }
+}
+
 // Some formatting stuff
 pub fn indent(text: &str) -> String {
     text.lines()
@@ -7,12 +29,28 @@ pub fn indent(text: &str) -> String {
         .collect::<Vec<String>>()
         .join("\n")
 }
+/// e.g. `3.21s`, `2m13s` or `1h04m10s`.
+pub fn human_duration(secs: f64) -> String {
+    if secs < 60. {
+        return format!("{:.2}s", secs);
+    }
+    let secs = secs.round() as u64;
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h{:02}m{:02}s", hours, mins, secs)
+    } else {
+        format!("{}m{:02}s", mins, secs)
+    }
+}
 pub fn green(text: &str) -> ColoredString {
     text.truecolor(0, 175, 135)
 }
 pub fn red(text: &str) -> ColoredString {
     text.truecolor(255, 47, 109)
 }
+pub fn yellow(text: &str) -> ColoredString {
+    text.truecolor(215, 175, 0)
+}
 pub fn muted(text: &str) -> ColoredString {
     text.truecolor(68, 68, 68)
 }
@@ -23,3 +61,12 @@ pub fn on_green(text: &str) -> ColoredString {
     text.on_truecolor(0, 175, 135).truecolor(28, 28, 28)
 }
 
+/// Pad `text` to `width`, aligned left, and color it. It's padded
+/// first, as the escape codes would otherwise count towards the width.
+pub fn align_left(text: &str, width: usize, color: fn(&str) -> ColoredString) -> ColoredString {
+    color(&format!("{:<width$}", text))
+}
+/// As [`align_left`], but aligned right.
+pub fn align_right(text: &str, width: usize, color: fn(&str) -> ColoredString) -> ColoredString {
+    color(&format!("{:>width$}", text))
+}
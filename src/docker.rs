@@ -0,0 +1,120 @@
+//! Running the editor in a GameCI container (`unityci/editor`),
+//! for machines without Unity installed. The project, and any
+//! other paths the editor is given (e.g. the results file), are
+//! mounted at the same paths in the container, so the arguments
+//! and the log read the same as for a local editor.
+
+use anyhow::{Result, anyhow, bail};
+use std::{ffi::OsString, fs, path::{Path, PathBuf}, process::{Command, ExitStatus, Stdio}, sync::atomic::{AtomicU32, Ordering}};
+use crate::{license, project_version};
+
+/// The editor in GameCI images, which runs Unity under `xvfb-run`
+/// so play mode tests that render have a display.
+pub(crate) const EDITOR: &str = "unity-editor";
+
+/// Where GameCI images look for a license file.
+const LICENSE_PATH: &str = "/root/.local/share/unity3d/Unity/Unity_lic.ulf";
+
+/// The image to run the editor in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Image {
+    /// The GameCI image for the project's editor version,
+    /// with only the Linux build support it comes with
+    ForProject,
+
+    /// An image by name, e.g. `unityci/editor:ubuntu-2022.3.10f1-android-3`
+    Named(String),
+}
+impl Image {
+    /// The image's name, which for [`Image::ForProject`]
+    /// needs the project.
+    pub fn name(&self, project_path: Option<&Path>) -> Result<String> {
+        match self {
+            Image::Named(name) => Ok(name.clone()),
+            Image::ForProject => {
+                let Some(version) = project_path.and_then(project_version) else {
+                    bail!("Couldn't tell which Unity version to run in Docker; pass the image, e.g. `--docker unityci/editor:ubuntu-2022.3.10f1-base-3`");
+                };
+                Ok(default_image(&version))
+            },
+        }
+    }
+}
+
+/// The GameCI image for an editor version.
+pub fn default_image(version: &str) -> String {
+    format!("unityci/editor:ubuntu-{}-base-3", version)
+}
+
+/// Counts containers started by this process, to name each uniquely.
+static CONTAINERS: AtomicU32 = AtomicU32::new(0);
+
+/// A `docker run` command for the editor, which the editor's
+/// arguments are passed on to, and the container's name
+/// (for [`kill`]).
+pub(crate) fn command(image: &Image, project_path: Option<&Path>, args: &[OsString]) -> Result<(Command, String)> {
+    let image = image.name(project_path)?;
+    let name = format!("unitool-{}-{}", std::process::id(), CONTAINERS.fetch_add(1, Ordering::Relaxed));
+
+    let mut cmd = Command::new("docker");
+    cmd.args(["run", "--rm", "--name", &name]);
+    for dir in mounts(project_path, args) {
+        cmd.arg("--volume").arg(volume(&dir, &dir, false));
+    }
+    if let Some(license) = license::status().path.filter(|path| path.is_file()) {
+        cmd.arg("--volume").arg(volume(&license, Path::new(LICENSE_PATH), true));
+    }
+    if let Some(project_path) = project_path {
+        cmd.arg("--workdir").arg(project_path);
+    }
+    cmd.arg(image).arg(EDITOR).args(args);
+    Ok((cmd, name))
+}
+
+/// The directories to mount: the project, and the
+/// parent of each absolute path in the arguments.
+fn mounts(project_path: Option<&Path>, args: &[OsString]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = project_path.map(Path::to_path_buf).into_iter().collect();
+    for arg in args {
+        let path = Path::new(arg);
+        if !path.is_absolute() { continue }
+        let dir = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+
+        // Output paths may not exist yet
+        let _ = fs::create_dir_all(dir);
+        if dir.parent().is_some() && !dirs.iter().any(|mounted| dir.starts_with(mounted)) {
+            dirs.retain(|mounted| !mounted.starts_with(dir));
+            dirs.push(dir.to_path_buf());
+        }
+    }
+    dirs
+}
+
+fn volume(host: &Path, container: &Path, read_only: bool) -> OsString {
+    let mut volume = OsString::from(host);
+    volume.push(":");
+    volume.push(container);
+    if read_only {
+        volume.push(":ro");
+    }
+    volume
+}
+
+/// Stop a container, e.g. after killing its `docker run`
+/// for a timeout, which leaves the container running.
+pub(crate) fn kill(name: &str) {
+    let _ = Command::new("docker")
+        .args(["kill", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Docker exits with 125 if the container couldn't be started
+/// (e.g. the daemon isn't running, or the image can't be pulled),
+/// rather than the editor failing.
+pub(crate) fn failed(status: &ExitStatus, stderr: &str) -> Option<anyhow::Error> {
+    if status.code() != Some(125) { return None }
+    let reason = stderr.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("no reason given");
+    Some(anyhow!("Docker couldn't start the editor's container: {}", reason))
+}
@@ -0,0 +1,204 @@
+//! Diagnosing the environment runs need: editors, a license,
+//! build support modules, disk space, a display, and the
+//! project's lock files, each with a hint for fixing it.
+
+use serde::Serialize;
+use std::{fmt::Display, path::Path, process::Command};
+use crate::{
+    UnitoolError, find_unity_path, has_display, project_version, state_dir,
+    build::{self, BuildTarget, format_size}, display::*, editors, license,
+    lock::editor_has_project, seats::is_stale,
+};
+
+/// Below this much free space, the Library may not fit
+/// (a reimport of a large project can easily take this).
+const LOW_DISK_SPACE: u64 = 5 << 30;
+const MIN_DISK_SPACE: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    /// What was checked, e.g. `License`
+    pub name: String,
+    pub status: Status,
+    pub message: String,
+
+    /// How to fix a warning or failure
+    pub hint: Option<String>,
+}
+impl Check {
+    fn pass(name: &str, message: String) -> Check {
+        Check { name: name.to_string(), status: Status::Pass, message, hint: None }
+    }
+
+    fn warn(name: &str, message: String, hint: &str) -> Check {
+        Check { name: name.to_string(), status: Status::Warn, message, hint: Some(hint.to_string()) }
+    }
+
+    fn fail(name: &str, message: String, hint: &str) -> Check {
+        Check { name: name.to_string(), status: Status::Fail, message, hint: Some(hint.to_string()) }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Diagnosis {
+    pub checks: Vec<Check>,
+}
+impl Diagnosis {
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|check| check.status == Status::Fail)
+    }
+}
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+        let mut lines = vec![];
+        for check in &self.checks {
+            let mark = match check.status {
+                Status::Pass => green("✓"),
+                Status::Warn => yellow("!"),
+                Status::Fail => red("✗"),
+            };
+            lines.push(format!("{} {:<width$}  {}", mark, check.name, check.message, width = width));
+            if let Some(hint) = &check.hint {
+                lines.push(muted(&format!("  {:<width$}  → {}", "", hint, width = width)).to_string());
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Check the environment, and the project if given. Build
+/// support is checked for each of `targets`.
+pub fn diagnose(project_path: Option<&Path>, unity_path: Option<&Path>, targets: &[BuildTarget]) -> Diagnosis {
+    let mut diagnosis = Diagnosis::default();
+    let checks = &mut diagnosis.checks;
+
+    let editors = editors::installed_editors();
+    checks.push(match editors.len() {
+        0 => Check::fail("Editors", "No Unity editors are installed".to_string(),
+            "Install one through Unity Hub, or pass `--unity-path` if yours is somewhere unusual"),
+        n => Check::pass("Editors", format!("{} installed ({})", n,
+            editors.iter().map(|editor| editor.version.as_str()).collect::<Vec<_>>().join(", "))),
+    });
+
+    let editor = match project_path {
+        Some(project_path) => check_project_editor(project_path, unity_path, &editors, checks),
+        None => None,
+    };
+    if let Some((editor, version)) = &editor {
+        for target in targets {
+            let name = format!("{} support", target.arg());
+            checks.push(match build::missing_module(editor, target) {
+                Some(module) => Check::fail(&name, format!("Unity {} can't build for {}", version, target.arg()),
+                    &format!("Install it with `unitool modules install --version {} {}`", version, module)),
+                None => Check::pass(&name, "Installed".to_string()),
+            });
+        }
+    }
+
+    let license = license::status();
+    checks.push(if license.activated {
+        Check::pass("License", "A license is activated".to_string())
+    } else {
+        Check::fail("License", "No license is activated".to_string(),
+            "Activate one with `unitool license activate`")
+    });
+
+    checks.push(if has_display() {
+        Check::pass("Display", "Available, for play mode tests that render".to_string())
+    } else {
+        Check::warn("Display", "None, so the editor runs with `-nographics`".to_string(),
+            "Play mode tests that render need one; run under `xvfb-run` (on Linux) and pass `--graphics`")
+    });
+
+    if let Some(project_path) = project_path {
+        checks.push(match free_space(project_path) {
+            Some(free) if free < MIN_DISK_SPACE => Check::fail("Disk space",
+                format!("Only {} free for the Library", format_size(free)),
+                "Free up space, or the editor will fail partway through importing"),
+            Some(free) if free < LOW_DISK_SPACE => Check::warn("Disk space",
+                format!("{} free for the Library", format_size(free)),
+                "Reimporting a large project may not fit; free up some space"),
+            Some(free) => Check::pass("Disk space", format!("{} free", format_size(free))),
+            None => Check::warn("Disk space", "Couldn't check the free space".to_string(),
+                "Make sure there's room for the Library (several GB for large projects)"),
+        });
+        checks.push(check_locks(project_path));
+    }
+    diagnosis
+}
+
+/// Check the editor the project would be run with, returning it and its version.
+fn check_project_editor(project_path: &Path, unity_path: Option<&Path>, editors: &[editors::Editor], checks: &mut Vec<Check>) -> Option<(std::path::PathBuf, String)> {
+    let Some(version) = project_version(project_path) else {
+        checks.push(Check::fail("Project", format!("{} has no `ProjectSettings/ProjectVersion.txt`", project_path.display()),
+            "Check the path points at the root of a Unity project"));
+        return None;
+    };
+    match find_unity_path(project_path, unity_path) {
+        Ok(path) => {
+            let found = editors.iter().find(|editor| editor.path == path).map(|editor| editor.version.clone());
+            match found {
+                Some(found) if found != version => checks.push(Check::warn("Project editor",
+                    format!("The project uses Unity {}, but it'll be run with {}", version, found),
+                    "The project will be upgraded (or downgraded) and reimported; pass the matching `--unity-path`")),
+                _ => checks.push(Check::pass("Project editor", format!("Unity {}, at {}", version, path.display()))),
+            }
+            Some((path, version))
+        },
+        Err(err) => {
+            let hint = match err.downcast_ref::<UnitoolError>() {
+                Some(UnitoolError::VersionNotInstalled { .. }) =>
+                    format!("Install Unity {} through Unity Hub (unityhub://{})", version, version),
+                _ => "Check `--unity-path`, `UNITY_PATH` or the config".to_string(),
+            };
+            checks.push(Check::fail("Project editor", err.to_string(), &hint));
+            None
+        },
+    }
+}
+
+/// Check for an open editor, and for lock files left by crashed runs.
+fn check_locks(project_path: &Path) -> Check {
+    if editor_has_project(project_path) {
+        return Check::warn("Lock files", "An editor has the project open".to_string(),
+            "Runs will fail until it's closed; pass `--lock wait` to wait for it");
+    }
+    let stale: Vec<&str> = [
+        ("Temp/UnityLockfile", project_path.join("Temp/UnityLockfile").exists()),
+        (".unitool/run.lock", is_stale(&state_dir(project_path).join("run.lock"))),
+    ].into_iter().filter(|(_, stale)| *stale).map(|(name, _)| name).collect();
+    if stale.is_empty() {
+        Check::pass("Lock files", "None left behind".to_string())
+    } else {
+        Check::warn("Lock files", format!("Stale: {}", stale.join(", ")),
+            "Remove them with `unitool cleanup`")
+    }
+}
+
+/// Free space on the path's filesystem, in bytes.
+#[cfg(unix)]
+fn free_space(path: &Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available * 1024)
+}
+
+#[cfg(windows)]
+fn free_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let drive = path.to_string_lossy().trim_start_matches(r"\\?\").chars().next()?;
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &format!("(Get-PSDrive {}).Free", drive)])
+        .output().ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
@@ -0,0 +1,133 @@
+//! Discovering installed Unity editors.
+//!
+//! Editors are found in Unity Hub's default install locations,
+//! in Hub's secondary install location (if one is configured),
+//! and in `/opt/Unity` on Linux. Each of these has one directory
+//! per editor version, e.g. `2022.3.10f1/`.
+
+use serde::Serialize;
+use std::{env, fs, cmp::Ordering, path::{Path, PathBuf}};
+
+/// Where the editor binary lives within a version's directory.
+#[cfg(target_os = "macos")]
+const EDITOR_BINARY: &str = "Unity.app/Contents/MacOS/Unity";
+#[cfg(target_os = "windows")]
+const EDITOR_BINARY: &str = "Editor\\Unity.exe";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const EDITOR_BINARY: &str = "Editor/Unity";
+
+/// Where build support modules live within a version's directory.
+#[cfg(target_os = "macos")]
+const PLAYBACK_ENGINES: &str = "PlaybackEngines";
+#[cfg(not(target_os = "macos"))]
+const PLAYBACK_ENGINES: &str = "Editor/Data/PlaybackEngines";
+
+/// An installed editor.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Editor {
+    pub version: String,
+
+    /// The editor binary
+    pub path: PathBuf,
+
+    /// The installed build support modules,
+    /// by directory name, e.g. `AndroidPlayer`
+    pub modules: Vec<String>,
+}
+
+/// All installed editors, oldest version first.
+pub fn installed_editors() -> Vec<Editor> {
+    let mut editors: Vec<Editor> = vec![];
+    for root in search_dirs() {
+        let Ok(entries) = fs::read_dir(&root) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path().join(EDITOR_BINARY);
+            if !path.exists() || editors.iter().any(|e| e.path == path) {
+                continue;
+            }
+            editors.push(Editor {
+                version: entry.file_name().to_string_lossy().to_string(),
+                path,
+                modules: installed_modules(&entry.path()),
+            });
+        }
+    }
+    editors.sort_by(|a, b| compare_versions(&a.version, &b.version));
+    editors
+}
+
+/// The build support modules in an editor's install directory.
+fn installed_modules(install_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(install_dir.join(PLAYBACK_ENGINES)) else { return vec![] };
+    let mut modules: Vec<String> = entries.flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    modules.sort();
+    modules
+}
+
+/// The directories which contain per-version editor installs.
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(PathBuf::from);
+
+    if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/Applications/Unity/Hub/Editor"));
+    } else if cfg!(target_os = "windows") {
+        dirs.push(PathBuf::from("C:\\Program Files\\Unity\\Hub\\Editor"));
+    } else {
+        if let Some(home) = &home {
+            dirs.push(home.join("Unity/Hub/Editor"));
+        }
+        dirs.push(PathBuf::from("/opt/Unity"));
+    }
+
+    if let Some(path) = secondary_install_path(home) {
+        dirs.push(path);
+    }
+    dirs
+}
+
+/// Hub stores its user-configured install location
+/// as a JSON string, e.g. `"D:\\Unity"`.
+fn secondary_install_path(home: Option<PathBuf>) -> Option<PathBuf> {
+    let config_dir = if cfg!(target_os = "macos") {
+        home?.join("Library/Application Support/UnityHub")
+    } else if cfg!(target_os = "windows") {
+        PathBuf::from(env::var_os("APPDATA")?).join("UnityHub")
+    } else {
+        home?.join(".config/UnityHub")
+    };
+    let contents = fs::read_to_string(config_dir.join("secondaryInstallPath.json")).ok()?;
+    let path = contents.trim().trim_matches('"').replace("\\\\", "\\");
+    if path.is_empty() { None } else { Some(path.into()) }
+}
+
+/// Order versions like `2022.3.9f1` < `2022.3.10f1`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    version_key(a).cmp(&version_key(b))
+}
+
+/// Split a version into its numeric and letter parts so they
+/// compare naturally, e.g. `2022.3.10f1` -> `[2022, 3, 10, 'f', 1]`.
+fn version_key(version: &str) -> Vec<(u32, String)> {
+    let mut parts = vec![];
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut num = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                num.push(c);
+                chars.next();
+            }
+            parts.push((num.parse().unwrap_or(u32::MAX), String::new()));
+        } else {
+            if c.is_alphabetic() {
+                parts.push((0, c.to_string()));
+            }
+            chars.next();
+        }
+    }
+    parts
+}
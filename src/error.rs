@@ -0,0 +1,65 @@
+//! Errors for the problems unitool knows how to explain.
+//! They come wrapped in `anyhow::Error`, like the other
+//! typed errors (e.g. [`crate::EditorCrashed`]); get at them
+//! with `err.downcast_ref::<UnitoolError>()`.
+
+use std::{fmt::Display, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum UnitoolError {
+    /// There's no editor at the given path, e.g. from `--unity-path`
+    EditorNotFound(PathBuf),
+
+    /// The project's editor version isn't installed
+    VersionNotInstalled {
+        version: String,
+        installed: Vec<String>,
+    },
+
+    NoEditorsInstalled,
+
+    /// The editor exists but couldn't be started
+    LaunchFailed {
+        editor: PathBuf,
+        source: io::Error,
+    },
+
+    /// There's no results file where one was expected
+    ResultsMissing(PathBuf),
+}
+impl Display for UnitoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitoolError::EditorNotFound(path) =>
+                write!(f, "There's no Unity editor at {}. Check `--unity-path`, `UNITY_PATH` or the config, \
+                    or run `unitool list-editors` to see what's installed.", path.display()),
+            UnitoolError::VersionNotInstalled { version, installed } =>
+                write!(f, "The project uses Unity {}, which isn't installed. Installed versions: {}",
+                    version,
+                    if installed.is_empty() { "none".to_string() } else { installed.join(", ") }),
+            UnitoolError::NoEditorsInstalled =>
+                write!(f, "No Unity editors are installed. Pass `--unity-path` if yours is somewhere unusual."),
+            UnitoolError::LaunchFailed { editor, source } =>
+                write!(f, "Couldn't start the editor at {}: {}", editor.display(), source),
+            UnitoolError::ResultsMissing(path) =>
+                write!(f, "No test results file at {}", path.display()),
+        }
+    }
+}
+impl std::error::Error for UnitoolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnitoolError::LaunchFailed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Map a failure to spawn the editor to what went wrong.
+pub(crate) fn launch_failed(editor: &std::path::Path, source: io::Error) -> UnitoolError {
+    if source.kind() == io::ErrorKind::NotFound {
+        UnitoolError::EditorNotFound(editor.to_path_buf())
+    } else {
+        UnitoolError::LaunchFailed { editor: editor.to_path_buf(), source }
+    }
+}
@@ -0,0 +1,111 @@
+//! Structured progress events, parsed from the editor
+//! log as it's output, for e.g. GUIs built on unitool.
+//! Per-test events come from an injected script
+//! (see `scripts/TestEvents.cs`).
+
+use serde::Serialize;
+use crate::{build::MARKER, compiler::{self, CompileError}, testing::TestResult};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunEvent {
+    /// The editor process was started
+    EditorStarted { pid: u32 },
+
+    /// The package manager started resolving the project's packages
+    ResolvingPackages,
+
+    /// The packages were resolved
+    PackagesResolved,
+
+    /// An asset started importing, by path
+    ImportingAsset(String),
+
+    /// Script compilation was requested
+    CompilationStarted,
+
+    /// An assembly started compiling, with how far through
+    /// the build it is (e.g. `(3, 10)`) if the editor said
+    CompilingAssembly { name: String, progress: Option<(usize, usize)> },
+
+    /// The editor started reloading scripts after compiling
+    ReloadingDomain,
+
+    CompileError(CompileError),
+
+    /// A test case started, by full name
+    TestStarted(String),
+
+    TestFinished { name: String, result: TestResult },
+
+    /// The editor exited, with its exit code
+    /// if it wasn't killed by a signal
+    Finished { exit_code: Option<i32> },
+}
+
+/// A callback for run events.
+pub type EventHandler<'a> = Box<dyn Fn(RunEvent) + Send + 'a>;
+
+/// The event a log line signals, if any.
+pub(crate) fn parse(line: &str) -> Option<RunEvent> {
+    if matches!(compiler::diagnostic(line), Some(("error", _))) {
+        return Some(RunEvent::CompileError(CompileError::parse(line)));
+    }
+
+    if line.starts_with("[ScriptCompilation] Requested script compilation") {
+        return Some(RunEvent::CompilationStarted);
+    }
+
+    // Older editors log each assembly they compile, newer ones'
+    // build system its steps, e.g. `[  3/ 10  1s] Csc Library/Bee/...`
+    if let Some(path) = line.strip_prefix("- Starting compile ") {
+        return Some(RunEvent::CompilingAssembly { name: assembly_name(path), progress: None });
+    }
+    if let Some((progress, step)) = line.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+        if let Some(path) = step.trim_start().strip_prefix("Csc ") {
+            let progress = progress.split_once('/').and_then(|(done, rest)| {
+                let total = rest.split_whitespace().next()?;
+                done.trim().parse().ok().zip(total.parse().ok())
+            });
+            return Some(RunEvent::CompilingAssembly { name: assembly_name(path), progress });
+        }
+    }
+
+    if let Some(rest) = line.strip_prefix("[Package Manager]") {
+        return Some(if rest.contains("Done resolving") || rest.trim_start().starts_with("Registered") {
+            RunEvent::PackagesResolved
+        } else {
+            RunEvent::ResolvingPackages
+        });
+    }
+    if let Some(rest) = line.strip_prefix("Start importing ") {
+        let path = rest.split(" using Guid(").next().unwrap_or(rest);
+        return Some(RunEvent::ImportingAsset(path.trim().to_string()));
+    }
+    if line.starts_with("Begin MonoManager ReloadAssembly") {
+        return Some(RunEvent::ReloadingDomain);
+    }
+
+    let (event, args) = line.strip_prefix(MARKER)?.split_once(' ')?;
+    match event {
+        "test-started" => Some(RunEvent::TestStarted(args.to_string())),
+        "test-finished" => {
+            let (status, name) = args.split_once(' ')?;
+            let result = match status {
+                "Passed" => TestResult::Passed,
+                "Failed" => TestResult::Failed,
+                _ => TestResult::Skipped,
+            };
+            Some(RunEvent::TestFinished { name: name.to_string(), result })
+        },
+        _ => None,
+    }
+}
+
+/// The assembly a compile step is for, e.g. `Game.Core`
+/// from `Library/Bee/artifacts/1900b0aE.dag/Game.Core.dll (+2 others)`.
+fn assembly_name(path: &str) -> String {
+    let path = path.split(" (+").next().unwrap_or(path).trim();
+    let file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    file.strip_suffix(".dll").unwrap_or(file).to_string()
+}
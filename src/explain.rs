@@ -0,0 +1,148 @@
+//! Explanations of common compile errors in Unity projects, with
+//! hints for fixing them (which are often about assembly
+//! definitions and packages, rather than the code itself).
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::{compiler::CompileError, display::*};
+
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    /// e.g. `CS0246`
+    pub code: &'static str,
+
+    /// What the error means
+    pub summary: &'static str,
+
+    /// How it's usually fixed in a Unity project
+    pub hint: &'static str,
+}
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}\n{}", red(self.code), self.summary, indent(self.hint))
+    }
+}
+
+const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "CS0006",
+        summary: "A referenced assembly's file couldn't be found.",
+        hint: "A precompiled plugin or package may be missing or failed to import; \
+            reimport it, or remove the reference from the `.asmdef`.",
+    },
+    Explanation {
+        code: "CS0103",
+        summary: "A name isn't defined where it's used.",
+        hint: "Check the spelling and scope. If it's defined behind `#if`, check the \
+            scripting define symbols for the platform you're compiling for.",
+    },
+    Explanation {
+        code: "CS0104",
+        summary: "A name is ambiguous between two namespaces.",
+        hint: "Often `Random` or `Debug` (in both `UnityEngine` and `System`); \
+            qualify it, or add e.g. `using Random = UnityEngine.Random;`.",
+    },
+    Explanation {
+        code: "CS0117",
+        summary: "A type doesn't have the member that's used.",
+        hint: "The API may have changed in this Unity or package version; check the \
+            upgrade guide, or whether the member is editor-only.",
+    },
+    Explanation {
+        code: "CS0234",
+        summary: "A namespace doesn't have the type or namespace that's used.",
+        hint: "The package providing it may not be installed, or the script's assembly \
+            definition doesn't reference its assembly.",
+    },
+    Explanation {
+        code: "CS0246",
+        summary: "A type or namespace couldn't be found.",
+        hint: "If it's in another assembly definition, add that to this script's \
+            `.asmdef` references; if it's in a package, check the package is installed. \
+            Editor-only types (`UnityEditor`) can't be used outside `Editor` folders \
+            or editor-only assemblies.",
+    },
+    Explanation {
+        code: "CS0433",
+        summary: "The same type is defined in two referenced assemblies.",
+        hint: "Usually a plugin DLL bundles a library another package also provides; \
+            remove one, or disable the DLL for the conflicting platforms.",
+    },
+    Explanation {
+        code: "CS0618",
+        summary: "A member is obsolete.",
+        hint: "The obsolete message usually names the replacement; the API updater \
+            (run when the project is opened) can sometimes fix it automatically.",
+    },
+    Explanation {
+        code: "CS0619",
+        summary: "A member is obsolete and can no longer be used.",
+        hint: "Use the replacement the message names; it was removed in this Unity version.",
+    },
+    Explanation {
+        code: "CS1061",
+        summary: "A type doesn't have the method, property or extension method that's used.",
+        hint: "For extension methods, add the `using` for their namespace and check the \
+            assembly definition references their assembly; otherwise the API may have \
+            changed in this Unity or package version.",
+    },
+    Explanation {
+        code: "CS1503",
+        summary: "An argument is the wrong type.",
+        hint: "Common with `Vector2`/`Vector3` and `float`/`double`; add a cast, or an `f` \
+            suffix to literals (`0.5f`).",
+    },
+    Explanation {
+        code: "CS1705",
+        summary: "A referenced assembly was built against a newer version of a dependency.",
+        hint: "Update the plugin or package to one built for this Unity version.",
+    },
+];
+
+/// Packages for namespaces that aren't part of the engine itself.
+const PACKAGE_NAMESPACES: &[(&str, &str)] = &[
+    ("UnityEngine.UI", "com.unity.ugui"),
+    ("TMPro", "com.unity.textmeshpro"),
+    ("UnityEngine.InputSystem", "com.unity.inputsystem"),
+    ("Unity.Mathematics", "com.unity.mathematics"),
+    ("Unity.Collections", "com.unity.collections"),
+    ("Unity.Burst", "com.unity.burst"),
+    ("Unity.Entities", "com.unity.entities"),
+    ("UnityEngine.AddressableAssets", "com.unity.addressables"),
+    ("Cinemachine", "com.unity.cinemachine"),
+    ("UnityEngine.Rendering.Universal", "com.unity.render-pipelines.universal"),
+    ("NUnit", "com.unity.test-framework"),
+    ("UnityEngine.TestTools", "com.unity.test-framework"),
+];
+
+/// The explanation for an error code, e.g. `CS0246` (or `cs0246`).
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// A hint for fixing the error, more specific than its
+/// code's explanation where the message says more (e.g. a
+/// missing namespace that comes from a package).
+pub fn hint(err: &CompileError) -> Option<String> {
+    let code = err.code.as_deref()?;
+    if matches!(code, "CS0246" | "CS0234") {
+        let name = err.message.split('\'').nth(1).unwrap_or_default();
+        let namespace = match code {
+            // `The type or namespace name 'UI' does not exist in
+            // the namespace 'UnityEngine'`: the full name is both
+            "CS0234" => err.message.split('\'').nth(3)
+                .map(|parent| format!("{}.{}", parent, name))
+                .unwrap_or(name.to_string()),
+            _ => name.to_string(),
+        };
+        let package = PACKAGE_NAMESPACES.iter()
+            .find(|(prefix, _)| namespace == *prefix || namespace.starts_with(&format!("{}.", prefix)))
+            .map(|(_, package)| package);
+        if let Some(package) = package {
+            return Some(format!("`{}` is in the `{}` package; install it with \
+                `unitool pkg add <project> {}`, and reference its assembly from \
+                the script's `.asmdef` if it has one", namespace, package, package));
+        }
+    }
+    explain(code).map(|explanation| explanation.hint.to_string())
+}
@@ -0,0 +1,180 @@
+//! Detecting runs that failed for reasons the editor only
+//! mentions in its log, e.g. another editor having the project
+//! open, which would otherwise look like a compile that found
+//! no errors (or tests that wrote no results).
+
+use std::{fmt::Display, process::ExitStatus};
+use crate::display::*;
+
+const ANOTHER_INSTANCE_MARKERS: &[&str] = &[
+    "It looks like another Unity instance is running with this project open",
+    "Multiple Unity instances cannot open the same project",
+];
+const ABORTED_MARKER: &str = "Aborting batchmode due to failure:";
+const SCRIPT_ERRORS_MARKER: &str = "Scripts have compiler errors";
+const PACKAGE_MARKERS: &[&str] = &[
+    "An error occurred while resolving packages",
+    "Project has invalid dependencies",
+];
+
+/// The API updater's prompt to update scripts that use obsolete
+/// APIs, which in batch mode can stall the run, or (with
+/// `-accept-apiupdate`) rewrite the scripts without asking.
+/// Only matched at the start of a line, so scripts (or tests)
+/// logging the same words don't stop the run.
+const API_UPDATE_MARKERS: &[&str] = &[
+    "API Update Required",
+    "This project contains scripts and/or assemblies that use obsolete APIs",
+];
+
+/// Package errors mentioning these are from the registry
+/// (or network) being unreachable, and may pass on a retry.
+const NETWORK_MARKERS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "ETIMEDOUT",
+    "ECONNRESET",
+    "ECONNREFUSED",
+    "ENOTFOUND",
+    "EAI_AGAIN",
+    "socket hang up",
+    "Unable to connect",
+    "503 Service Unavailable",
+];
+
+/// The most lines of details to keep, e.g. package errors.
+const MAX_DETAIL_LINES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FatalCause {
+    /// Another editor has the project open
+    AnotherInstance,
+
+    /// The editor said scripts have errors but logged none
+    /// we could parse, e.g. in a precompiled assembly
+    ScriptErrors,
+
+    /// Packages couldn't be resolved, with the editor's reasons
+    PackageResolution(Vec<String>),
+
+    /// The editor gave up on the run, e.g. because
+    /// an `-executeMethod` method couldn't be found
+    BatchmodeAborted(String),
+
+    /// The API updater wanted to update scripts, and wasn't
+    /// allowed to (the run's stopped rather than left to stall)
+    ApiUpdateRequired(Vec<String>),
+}
+impl FatalCause {
+    /// Whether the failure often goes away on its own, e.g.
+    /// an editor that was closing still having the project
+    /// open, or the package registry timing out.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FatalCause::AnotherInstance => true,
+            FatalCause::PackageResolution(errors) => errors.iter()
+                .any(|err| NETWORK_MARKERS.iter().any(|marker| err.contains(marker))),
+            FatalCause::ScriptErrors | FatalCause::BatchmodeAborted(_)
+                | FatalCause::ApiUpdateRequired(_) => false,
+        }
+    }
+}
+
+/// The editor failed for a reason it logged.
+#[derive(Debug)]
+pub struct FatalError {
+    pub cause: FatalCause,
+
+    /// The last lines of the editor log
+    pub log_tail: Vec<String>,
+}
+impl Display for FatalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = match &self.cause {
+            FatalCause::AnotherInstance => vec![
+                red("Another Unity editor has the project open.").to_string(),
+                "Close it, or if it was left by an interrupted run, `unitool cleanup` stops it.".to_string(),
+            ],
+            FatalCause::ScriptErrors => vec![
+                red("Unity says the scripts have compiler errors, but didn't log any.").to_string(),
+                "They may be in a precompiled or package assembly; check the log with `--verbose`.".to_string(),
+            ],
+            FatalCause::PackageResolution(errors) => {
+                let mut lines = vec![red("Unity couldn't resolve the project's packages:").to_string()];
+                lines.extend(errors.iter().map(|err| format!("  {}", err)));
+                lines.push("Check `Packages/manifest.json` (`unitool pkg list` shows what's there).".to_string());
+                lines
+            },
+            FatalCause::BatchmodeAborted(reason) => vec![
+                red(&format!("Unity aborted the run: {}", reason)).to_string(),
+            ],
+            FatalCause::ApiUpdateRequired(messages) => {
+                let mut lines = vec![red("Unity wants to run the API updater on scripts that use obsolete APIs, so the run was stopped:").to_string()];
+                lines.extend(messages.iter().map(|message| format!("  {}", message)));
+                lines.push("In batch mode it can stall, or rewrite the scripts without asking. Update them in the editor, \
+                    or pass `--accept-api-update` to let it (and commit the changes).".to_string());
+                lines
+            },
+        };
+        if !self.log_tail.is_empty() {
+            lines.push(format!("Last {} lines of the editor log:", self.log_tail.len()));
+            lines.push(muted(&indent(&self.log_tail.join("\n"))).to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for FatalError {}
+
+/// Check whether the editor failed for a reason it logged. Runs
+/// with compile errors are left alone, as those explain them.
+pub(crate) fn detect(status: &ExitStatus, log: &str, has_compile_errors: bool, log_tail: &[String]) -> Option<FatalError> {
+    if status.success() || has_compile_errors {
+        return None;
+    }
+    let lines: Vec<&str> = log.lines().collect();
+    let find = |markers: &[&str]| lines.iter()
+        .position(|line| markers.iter().any(|marker| line.contains(marker)));
+
+    let cause = if find(ANOTHER_INSTANCE_MARKERS).is_some() {
+        FatalCause::AnotherInstance
+    } else if let Some(errors) = package_errors(log) {
+        FatalCause::PackageResolution(errors)
+    } else if find(&[SCRIPT_ERRORS_MARKER]).is_some() {
+        FatalCause::ScriptErrors
+    } else if let Some(start) = find(&[ABORTED_MARKER]) {
+        // The reason is on the same line, or the ones after
+        let (_, rest) = lines[start].split_once(ABORTED_MARKER).unwrap_or_default();
+        let reason = match rest.trim() {
+            "" => details(&lines[start + 1..]).join(" "),
+            reason => reason.to_string(),
+        };
+        FatalCause::BatchmodeAborted(if reason.is_empty() { "no reason given".to_string() } else { reason })
+    } else {
+        return None;
+    };
+    Some(FatalError { cause, log_tail: log_tail.to_vec() })
+}
+
+/// Whether the line is the API updater wanting to update scripts.
+pub(crate) fn needs_api_update(line: &str) -> bool {
+    let line = line.trim_start();
+    API_UPDATE_MARKERS.iter().any(|marker| line.starts_with(marker))
+}
+
+/// The editor's reasons for not resolving the packages, if it couldn't.
+pub(crate) fn package_errors(log: &str) -> Option<Vec<String>> {
+    let lines: Vec<&str> = log.lines().collect();
+    let start = lines.iter()
+        .position(|line| PACKAGE_MARKERS.iter().any(|marker| line.contains(marker)))?;
+    Some(details(&lines[start + 1..]))
+}
+
+/// The lines up to the next blank one.
+fn details(lines: &[&str]) -> Vec<String> {
+    lines.iter()
+        .skip_while(|line| line.trim().is_empty())
+        .take_while(|line| !line.trim().is_empty())
+        .take(MAX_DETAIL_LINES)
+        .map(|line| line.trim().to_string())
+        .collect()
+}
@@ -0,0 +1,116 @@
+//! Finding flaky tests, i.e. those which both passed
+//! and failed across repeated runs of the same suite.
+
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
+use crate::{testing::{TestSummary, TestResult}, display::*};
+
+/// How a single test fared across the runs.
+#[derive(Debug, Serialize)]
+pub struct TestRecord {
+    /// The test's full name
+    pub name: String,
+
+    pub passed: usize,
+    pub failed: usize,
+
+    /// The runs it failed in, counting from 1
+    pub failed_in: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlakyReport {
+    pub runs: usize,
+
+    /// The random order seed for each run, if they were randomized
+    pub seeds: Vec<u32>,
+
+    /// Tests which passed in some runs and failed in others
+    pub flaky: Vec<TestRecord>,
+
+    /// Tests which failed in every run they ran in
+    pub failing: Vec<TestRecord>,
+}
+impl FlakyReport {
+    /// Aggregate the results of each run, in order.
+    pub fn new(runs: &[TestSummary], seeds: Vec<u32>) -> FlakyReport {
+        let mut records: BTreeMap<String, TestRecord> = BTreeMap::new();
+        for (i, run) in runs.iter().enumerate() {
+            for case in run.cases() {
+                let record = records.entry(case.key().to_string())
+                    .or_insert_with(|| TestRecord {
+                        name: case.key().to_string(),
+                        passed: 0,
+                        failed: 0,
+                        failed_in: vec![],
+                    });
+                match case.result {
+                    TestResult::Passed => record.passed += 1,
+                    TestResult::Failed => {
+                        record.failed += 1;
+                        record.failed_in.push(i + 1);
+                    },
+                    TestResult::Skipped => {},
+                }
+            }
+        }
+
+        let (mut flaky, mut failing) = (vec![], vec![]);
+        for record in records.into_values() {
+            if record.failed == 0 {
+                continue;
+            } else if record.passed > 0 {
+                flaky.push(record);
+            } else {
+                failing.push(record);
+            }
+        }
+        // Most often failing first
+        flaky.sort_by_key(|record| std::cmp::Reverse(record.failed));
+        FlakyReport { runs: runs.len(), seeds, flaky, failing }
+    }
+
+    /// Whether every test passed in every run.
+    pub fn passed(&self) -> bool {
+        self.flaky.is_empty() && self.failing.is_empty()
+    }
+}
+impl Display for FlakyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        if self.passed() {
+            lines.push(green(&format!("All tests passed in all {} runs", self.runs)).to_string());
+        }
+        if !self.flaky.is_empty() {
+            lines.push(yellow(&format!("{} flaky tests:", self.flaky.len())).to_string());
+            for record in &self.flaky {
+                lines.push(format!("  {} {}", record.name,
+                    muted(&format!("failed {}/{} ({})", record.failed,
+                        record.passed + record.failed, self.describe_runs(&record.failed_in)))));
+            }
+        }
+        if !self.failing.is_empty() {
+            lines.push(red(&format!("{} tests failed in every run:", self.failing.len())).to_string());
+            for record in &self.failing {
+                lines.push(format!("  {}", record.name));
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl FlakyReport {
+    /// e.g. `runs 2, 5` or, with seeds, `seeds 1234, 5678`,
+    /// so the failing orders can be reproduced.
+    fn describe_runs(&self, runs: &[usize]) -> String {
+        if self.seeds.is_empty() {
+            let runs = runs.iter().map(|run| run.to_string()).collect::<Vec<_>>();
+            format!("runs {}", runs.join(", "))
+        } else {
+            let seeds = runs.iter()
+                .filter_map(|run| self.seeds.get(run - 1))
+                .map(|seed| seed.to_string())
+                .collect::<Vec<_>>();
+            format!("seeds {}", seeds.join(", "))
+        }
+    }
+}
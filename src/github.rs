@@ -0,0 +1,166 @@
+//! GitHub Actions workflow commands, for annotating compile
+//! errors and test failures inline on a PR's diff. See:
+//! <https://docs.github.com/en/actions/using-workflow-commands-for-github-actions>
+//!
+//! GitHub expects file paths relative to the repository root,
+//! which is assumed to be the current directory.
+
+use std::path::{Component, Path};
+use crate::{TestSummary, compiler::{CompileError, parse_diagnostic}, log::LogException};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+impl Level {
+    fn command(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+/// Annotate an error that isn't from any file.
+pub fn error(message: &str) -> String {
+    annotation(Level::Error, &[], message)
+}
+
+/// Annotate a compile error. Its file is
+/// relative to the project at `project_path`.
+pub fn compile_error(project_path: &Path, error: &CompileError) -> String {
+    annotate_diagnostic(project_path, error, Level::Error)
+}
+
+/// Annotate a compiler warning, as a warning or
+/// (e.g. when treating warnings as errors) an error.
+pub fn compile_warning(project_path: &Path, warning: &str, level: Level) -> String {
+    let raw = warning.trim();
+    match parse_diagnostic(raw) {
+        Some(parsed) => annotate_diagnostic(project_path, &parsed, level),
+        None => annotation(level, &[], raw),
+    }
+}
+
+/// Annotate each failing test, at the line in
+/// the project's code where it failed, if known.
+pub fn test_failures(summary: &TestSummary) -> Vec<String> {
+    summary.cases().into_iter()
+        .filter(|case| case.failed())
+        .map(|case| {
+            let mut props = vec![];
+            let location = case.stack_trace().as_deref().and_then(failure_location);
+            if let Some((file, line)) = &location {
+                props.push(("file", file.clone()));
+                props.push(("line", line.to_string()));
+            }
+            let title = if case.full_name.is_empty() { &case.name } else { &case.full_name };
+            props.push(("title", title.clone()));
+            let message = case.message()
+                .map(|msg| msg.trim().to_string())
+                .unwrap_or_else(|| "Test failed".to_string());
+            annotation(Level::Error, &props, &message)
+        })
+        .collect()
+}
+
+/// Annotate an exception the editor logged, as a warning,
+/// at the first frame in the project's code, if any. Unity's
+/// frames are relative to the project at `project_path`.
+pub fn exception(project_path: &Path, exception: &LogException) -> String {
+    let mut props = vec![];
+    let location = exception.stack_trace.iter()
+        .find_map(|frame| {
+            let (_, location) = frame.rsplit_once("(at ")?;
+            let (file, line) = location.trim_end_matches(')').rsplit_once(':')?;
+            if !file.starts_with("Assets/") {
+                return None;
+            }
+            Some((workspace_path(&project_path.join(file)), line.parse().ok()?))
+        })
+        .or_else(|| failure_location(&exception.stack_trace.join("\n")));
+    if let Some((file, line)) = location {
+        props.push(("file", file));
+        props.push(("line", line.to_string()));
+    }
+    props.push(("title", exception.kind.clone()));
+    annotation(Level::Warning, &props, &exception.message)
+}
+
+fn annotate_diagnostic(project_path: &Path, diagnostic: &CompileError, level: Level) -> String {
+    let mut props = vec![];
+    if let Some(file) = &diagnostic.file {
+        props.push(("file", workspace_path(&project_path.join(file))));
+    }
+    if let Some(line) = diagnostic.line {
+        props.push(("line", line.to_string()));
+    }
+    if let Some(column) = diagnostic.column {
+        props.push(("col", column.to_string()));
+    }
+    let message = match &diagnostic.code {
+        Some(code) => format!("{}: {}", code, diagnostic.message),
+        None => diagnostic.message.clone(),
+    };
+    annotation(level, &props, &message)
+}
+
+/// Format a workflow command, e.g. `::error file=Foo.cs,line=12::Message`.
+fn annotation(level: Level, props: &[(&str, String)], message: &str) -> String {
+    let props = props.iter()
+        .map(|(key, value)| format!("{}={}", key, escape_property(value)))
+        .collect::<Vec<String>>()
+        .join(",");
+    if props.is_empty() {
+        format!("::{}::{}", level.command(), escape_data(message))
+    } else {
+        format!("::{} {}::{}", level.command(), props, escape_data(message))
+    }
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// The first frame in a stack trace that's in the project's
+/// own scripts, as `(file, line)`. Frames look like:
+/// `at Game.Tests.Foo () [0x00001] in /path/to/Assets/Tests/Foo.cs:21`
+fn failure_location(stack_trace: &str) -> Option<(String, usize)> {
+    stack_trace.lines().find_map(|frame| {
+        let (_, location) = frame.rsplit_once(" in ")?;
+        let (file, line) = location.trim().rsplit_once(':')?;
+        let file = Path::new(file);
+        let in_assets = file.components()
+            .any(|part| part == Component::Normal("Assets".as_ref()));
+        if !in_assets {
+            return None;
+        }
+        Some((workspace_path(file), line.parse().ok()?))
+    })
+}
+
+/// The path relative to the current directory,
+/// with forward slashes as GitHub expects.
+fn workspace_path(path: &Path) -> String {
+    let path = match std::env::current_dir() {
+        Ok(cwd) if path.is_absolute() => path.strip_prefix(&cwd).unwrap_or(path),
+        _ => path,
+    };
+    if path.is_absolute() {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+    path.components()
+        .filter(|part| *part != Component::CurDir)
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
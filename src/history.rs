@@ -0,0 +1,335 @@
+//! A record of past runs, for seeing how the tests have
+//! gone over time. Each compile or test run's summary (when
+//! it was, the git commit, the counts and each test's result
+//! and duration) is appended to `.unitool/history/runs.jsonl`,
+//! one JSON object per line.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display, fs, io::{ErrorKind, Write}, path::{Path, PathBuf}, process::Command, time::{Duration, SystemTime, UNIX_EPOCH}};
+use crate::{CompileErrors, TestMode, TestResult, TestSummary, display::*, json::{self, Value}, state_dir};
+
+/// How many of a test's most recent results to chart.
+const MAX_CHARTED: usize = 20;
+
+/// Duration changes smaller than this (as a
+/// fraction) aren't called out as trends.
+const TREND_THRESHOLD: f64 = 0.1;
+
+fn history_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("history/runs.jsonl")
+}
+
+/// A past compile or test run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    /// When it finished, in seconds since the Unix epoch
+    pub time: u64,
+
+    /// The project's git commit, if it's in a repository
+    pub commit: Option<String>,
+
+    /// `compile` or `test`
+    pub command: String,
+
+    /// The test mode, for test runs
+    pub mode: Option<String>,
+
+    pub compile_errors: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+
+    /// How long the run took, in seconds
+    pub duration: f64,
+
+    pub tests: Vec<TestRecord>,
+}
+
+/// A test's result in a past run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestRecord {
+    /// The test's full name
+    pub name: String,
+    pub result: TestResult,
+
+    /// In seconds
+    pub duration: f64,
+}
+
+impl RunRecord {
+    /// A record of a compile, which took `duration`.
+    pub fn compile(project_path: &Path, errors: &CompileErrors, duration: Duration) -> RunRecord {
+        RunRecord {
+            compile_errors: errors.len(),
+            ..RunRecord::new(project_path, "compile", None, duration)
+        }
+    }
+
+    /// A record of a test run, which took `duration`.
+    pub fn test(project_path: &Path, mode: &TestMode, errors: &CompileErrors, results: Option<&TestSummary>, duration: Duration) -> RunRecord {
+        let mode = match mode {
+            TestMode::EditMode => "edit-mode",
+            TestMode::PlayMode => "play-mode",
+            TestMode::All => "all",
+        };
+        let mut record = RunRecord {
+            compile_errors: errors.len(),
+            ..RunRecord::new(project_path, "test", Some(mode.to_string()), duration)
+        };
+        if let Some(results) = results {
+            record.passed = results.passed();
+            record.failed = results.failed();
+            record.skipped = results.skipped();
+            record.tests = results.cases().iter()
+                .map(|case| TestRecord {
+                    name: case.key().to_string(),
+                    result: case.result.clone(),
+                    duration: case.duration,
+                })
+                .collect();
+        }
+        record
+    }
+
+    fn new(project_path: &Path, command: &str, mode: Option<String>, duration: Duration) -> RunRecord {
+        RunRecord {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0),
+            commit: git_commit(project_path),
+            command: command.to_string(),
+            mode,
+            compile_errors: 0,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            duration: duration.as_secs_f64(),
+            tests: vec![],
+        }
+    }
+
+    fn parse(value: &Value) -> Option<RunRecord> {
+        let count = |key: &str| value.get(key).and_then(|n| n.as_f64()).unwrap_or(0.) as usize;
+        let string = |key: &str| value.get(key).and_then(|s| s.as_str()).map(|s| s.to_string());
+        let tests = match value.get("tests") {
+            Some(Value::Array(tests)) => tests.iter()
+                .filter_map(|test| Some(TestRecord {
+                    name: test.get("name")?.as_str()?.to_string(),
+                    result: match test.get("result")?.as_str()? {
+                        "Passed" => TestResult::Passed,
+                        "Failed" => TestResult::Failed,
+                        _ => TestResult::Skipped,
+                    },
+                    duration: test.get("duration").and_then(|d| d.as_f64()).unwrap_or(0.),
+                }))
+                .collect(),
+            _ => vec![],
+        };
+        Some(RunRecord {
+            time: value.get("time")?.as_f64()? as u64,
+            commit: string("commit"),
+            command: string("command")?,
+            mode: string("mode"),
+            compile_errors: count("compile_errors"),
+            passed: count("passed"),
+            failed: count("failed"),
+            skipped: count("skipped"),
+            duration: value.get("duration").and_then(|d| d.as_f64()).unwrap_or(0.),
+            tests,
+        })
+    }
+}
+
+/// The short hash of the commit checked out in the project.
+fn git_commit(project_path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(project_path)
+        .output().ok()?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !commit.is_empty()).then_some(commit)
+}
+
+/// Add a run to the project's history.
+pub fn record(project_path: &Path, record: &RunRecord) -> Result<()> {
+    let path = history_path(project_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", json::to_string(record)?)?;
+    Ok(())
+}
+
+/// The project's past runs, oldest first. Lines that
+/// can't be read (e.g. from an interrupted write) are skipped.
+pub fn load(project_path: &Path) -> Result<Vec<RunRecord>> {
+    match fs::read_to_string(history_path(project_path)) {
+        Ok(contents) => Ok(contents.lines()
+            .filter_map(|line| json::parse(line).ok())
+            .filter_map(|value| RunRecord::parse(&value))
+            .collect()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A list of past runs, for display.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct History(pub Vec<RunRecord>);
+impl Display for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "{}", muted("No runs recorded yet"));
+        }
+        let lines: Vec<String> = self.0.iter().map(|run| {
+            let outcome = if run.compile_errors > 0 {
+                red(&format!("{} compile errors", run.compile_errors)).to_string()
+            } else if run.command == "compile" {
+                green("compiled").to_string()
+            } else {
+                format!("{}, {}, {}",
+                    green(&format!("{} passed", run.passed)),
+                    red(&format!("{} failed", run.failed)),
+                    muted(&format!("{} skipped", run.skipped)))
+            };
+            let command = match &run.mode {
+                Some(mode) => format!("{} {}", run.command, mode),
+                None => run.command.clone(),
+            };
+            format!("{}  {:<9}  {:<17}  {}  {}",
+                muted(&format_time(run.time)),
+                run.commit.as_deref().unwrap_or("-"),
+                command,
+                outcome,
+                muted(&human_duration(run.duration)))
+        }).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A test's results over the recorded runs.
+#[derive(Debug, Serialize)]
+pub struct TestTrend {
+    pub name: String,
+    pub runs: usize,
+    pub passed: usize,
+    pub failed: usize,
+
+    /// Of the runs where it passed or failed
+    pub pass_rate: f64,
+
+    /// In seconds
+    pub mean_duration: f64,
+    pub last_duration: f64,
+
+    /// How much slower (or, if negative, faster) the newer half
+    /// of its runs were than the older half, as a fraction
+    pub duration_change: f64,
+
+    /// Its results, oldest first
+    #[serde(skip)]
+    results: Vec<TestResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trends {
+    /// The test runs these are from
+    pub runs: usize,
+
+    /// Least often passing first
+    pub tests: Vec<TestTrend>,
+}
+impl Trends {
+    /// The trends across the runs (oldest first),
+    /// for tests whose names contain `filter`.
+    pub fn new(runs: &[RunRecord], filter: Option<&str>) -> Trends {
+        let runs: Vec<&RunRecord> = runs.iter().filter(|run| !run.tests.is_empty()).collect();
+        let mut by_test: BTreeMap<&str, Vec<&TestRecord>> = BTreeMap::new();
+        for run in &runs {
+            for test in &run.tests {
+                if filter.is_none_or(|filter| test.name.contains(filter)) {
+                    by_test.entry(&test.name).or_default().push(test);
+                }
+            }
+        }
+
+        let mut tests: Vec<TestTrend> = by_test.into_iter().map(|(name, records)| {
+            let count = |result: TestResult| records.iter().filter(|test| test.result == result).count();
+            let (passed, failed) = (count(TestResult::Passed), count(TestResult::Failed));
+            let durations: Vec<f64> = records.iter().map(|test| test.duration).collect();
+            let (older, newer) = durations.split_at(durations.len() / 2);
+            let duration_change = match (mean(older), mean(newer)) {
+                (older, newer) if older > 0. => (newer - older) / older,
+                _ => 0.,
+            };
+            TestTrend {
+                name: name.to_string(),
+                runs: records.len(),
+                passed,
+                failed,
+                pass_rate: if passed + failed > 0 { passed as f64 / (passed + failed) as f64 } else { 1. },
+                mean_duration: mean(&durations),
+                last_duration: durations.last().copied().unwrap_or(0.),
+                duration_change,
+                results: records.iter().map(|test| test.result.clone()).collect(),
+            }
+        }).collect();
+        tests.sort_by(|a, b| a.pass_rate.total_cmp(&b.pass_rate)
+            .then(b.duration_change.total_cmp(&a.duration_change)));
+        Trends { runs: runs.len(), tests }
+    }
+}
+impl Display for Trends {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tests.is_empty() {
+            return write!(f, "{}", muted("No test runs recorded yet"));
+        }
+        let mut lines = vec![muted(&format!("Across {} test runs:", self.runs)).to_string()];
+        let width = self.tests.iter().map(|test| test.name.len()).max().unwrap_or(0);
+        for test in &self.tests {
+            let rate = format!("{:>4.0}%", test.pass_rate * 100.);
+            let rate = if test.failed > 0 { red(&rate) } else { green(&rate) };
+            // Padded by hand, as the colors throw off `format!`'s widths
+            let shown = &test.results[test.results.len().saturating_sub(MAX_CHARTED)..];
+            let chart: String = shown.iter()
+                .map(|result| result.to_string())
+                .chain(std::iter::repeat_n(" ".to_string(), MAX_CHARTED - shown.len()))
+                .collect();
+            let change = if test.duration_change > TREND_THRESHOLD {
+                red(&format!("↑{:.0}%", test.duration_change * 100.)).to_string()
+            } else if test.duration_change < -TREND_THRESHOLD {
+                green(&format!("↓{:.0}%", -test.duration_change * 100.)).to_string()
+            } else {
+                String::new()
+            };
+            let line = format!("{:<width$}  {}  {}  {} {}",
+                test.name, rate, chart,
+                muted(&format!("{:.2}s", test.mean_duration)), change,
+                width = width);
+            lines.push(line.trim_end().to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() { 0. } else { values.iter().sum::<f64>() / values.len() as f64 }
+}
+
+/// A Unix time as e.g. `2024-03-09 14:05` (UTC).
+pub(crate) fn format_time(secs: u64) -> String {
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // Days to a civil date, per Howard Hinnant's algorithm
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, secs / 3600, secs / 60 % 60)
+}
@@ -0,0 +1,112 @@
+//! Git hooks that run `unitool check` on the project,
+//! so broken code doesn't get pushed (or committed).
+
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use std::{env, fs, path::{Path, PathBuf}, process::Command};
+
+/// Marks hooks as written by unitool, so they can
+/// be replaced or uninstalled without `--force`.
+const MARKER: &str = "# Installed by `unitool hook install`";
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum HookKind {
+    PrePush,
+    PreCommit,
+}
+impl HookKind {
+    /// The hook's file name, e.g. `pre-push`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookKind::PrePush => "pre-push",
+            HookKind::PreCommit => "pre-commit",
+        }
+    }
+}
+
+/// Write a hook which checks the project, running its edit mode
+/// tests too if `tests`. An existing hook not written by unitool
+/// is only replaced if `force`. Returns the path of the hook.
+pub fn install(project_path: &Path, kind: HookKind, tests: bool, force: bool) -> Result<PathBuf> {
+    let path = hook_path(project_path, kind)?;
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(MARKER) && !force {
+            bail!("{} already exists; pass --force to replace it", path.display());
+        }
+    }
+
+    // Hooks are run from the root of the repository
+    let root = git(project_path, &["rev-parse", "--show-toplevel"])?;
+    let project = fs::canonicalize(project_path)?;
+    let relative = project.strip_prefix(fs::canonicalize(root)?).unwrap_or(&project);
+    let relative = match relative.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => relative,
+    };
+
+    let exe = env::current_exe()?;
+    let mut command = format!("exec {} check {}", quote(&exe.to_string_lossy()), quote(&relative.to_string_lossy()));
+    if tests {
+        command.push_str(" --tests");
+    }
+    let script = format!("#!/bin/sh\n{}\n{}\n", MARKER, command);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, script)?;
+    make_executable(&path)?;
+    Ok(path)
+}
+
+/// Remove the hook, if unitool wrote it. Returns its path, if it was removed.
+pub fn uninstall(project_path: &Path, kind: HookKind) -> Result<Option<PathBuf>> {
+    let path = hook_path(project_path, kind)?;
+    match fs::read_to_string(&path) {
+        Ok(existing) if existing.contains(MARKER) => {
+            fs::remove_file(&path)?;
+            Ok(Some(path))
+        },
+        Ok(_) => bail!("{} wasn't installed by unitool, so was left alone", path.display()),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Where git looks for the hook, which respects `core.hooksPath`.
+fn hook_path(project_path: &Path, kind: HookKind) -> Result<PathBuf> {
+    let hooks = PathBuf::from(git(project_path, &["rev-parse", "--git-path", "hooks"])?);
+    let hooks = match hooks.is_absolute() {
+        true => hooks,
+        false => project_path.join(hooks),
+    };
+    Ok(hooks.join(kind.name()))
+}
+
+fn git(project_path: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(project_path)
+        .output()
+        .map_err(|err| anyhow!("Couldn't run git: {}", err))?;
+    if !output.status.success() {
+        bail!("{} isn't in a git repository", project_path.display());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Single-quote for the shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn make_executable(_path: &Path) -> Result<()> {
+    // Git for Windows runs hooks through its own shell
+    Ok(())
+}
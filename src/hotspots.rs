@@ -0,0 +1,136 @@
+//! Aggregating failures across a series of test runs
+//! to find the chronically unstable parts of a project.
+
+use clap::ValueEnum;
+use colored::Colorize;
+use std::{collections::HashMap, fmt::Display};
+use crate::{testing::TestSummary, display::*};
+
+/// How to group failing tests.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    Namespace,
+    Class,
+}
+
+/// Failure stats for a single namespace or class.
+#[derive(Debug)]
+pub struct Hotspot {
+    pub name: String,
+
+    /// Total failed test cases, across all runs
+    pub failures: usize,
+
+    /// Number of distinct tests which failed at least once
+    pub tests: usize,
+
+    /// Failures in each run, in the order the runs were given
+    pub per_run: Vec<usize>,
+}
+impl Hotspot {
+    /// Number of runs this group had at least one failure in.
+    pub fn failing_runs(&self) -> usize {
+        self.per_run.iter().filter(|n| **n > 0).count()
+    }
+}
+
+#[derive(Debug)]
+pub struct HotspotReport {
+    pub runs: usize,
+    pub hotspots: Vec<Hotspot>,
+}
+impl HotspotReport {
+    /// Rank the groups in `runs` (oldest first) by how often they fail.
+    pub fn new(runs: &[TestSummary], by: GroupBy) -> HotspotReport {
+        let mut groups: HashMap<String, (Vec<usize>, Vec<&str>)> = HashMap::new();
+        for (i, run) in runs.iter().enumerate() {
+            for case in run.cases().into_iter().filter(|c| c.failed()) {
+                let class = if case.class_name.is_empty() {
+                    parent(&case.full_name)
+                } else {
+                    &case.class_name
+                };
+                let key = match by {
+                    GroupBy::Class => class,
+                    GroupBy::Namespace => parent(class),
+                };
+                let key = if key.is_empty() { "(global)" } else { key };
+                let (per_run, tests) = groups.entry(key.to_string())
+                    .or_insert_with(|| (vec![0; runs.len()], vec![]));
+                per_run[i] += 1;
+                if !tests.contains(&case.full_name.as_str()) {
+                    tests.push(&case.full_name);
+                }
+            }
+        }
+
+        let mut hotspots: Vec<Hotspot> = groups.into_iter()
+            .map(|(name, (per_run, tests))| Hotspot {
+                name,
+                failures: per_run.iter().sum(),
+                tests: tests.len(),
+                per_run,
+            })
+            .collect();
+        hotspots.sort_by(|a, b| b.failures.cmp(&a.failures)
+            .then(b.failing_runs().cmp(&a.failing_runs()))
+            .then(a.name.cmp(&b.name)));
+
+        HotspotReport {
+            runs: runs.len(),
+            hotspots,
+        }
+    }
+}
+impl Display for HotspotReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.hotspots.is_empty() {
+            return write!(f, "{}", green(&format!("No failures in {} runs", self.runs)));
+        }
+
+        let name_width = self.hotspots.iter()
+            .map(|h| h.name.chars().count())
+            .max().unwrap_or(0);
+        let hottest = self.hotspots.iter()
+            .flat_map(|h| h.per_run.iter())
+            .copied().max().unwrap_or(0);
+
+        let mut lines = vec![format!("{:name_width$}  {:>8}  {:>5}  {:>5}  {}",
+            "", "failures", "tests", "runs",
+            muted("oldest → newest"))];
+        for hotspot in &self.hotspots {
+            // Shade each run's cell by how many failures
+            // it had, relative to the worst cell overall
+            let cells: String = hotspot.per_run.iter()
+                .map(|n| heat(*n, hottest).to_string())
+                .collect();
+            lines.push(format!("{:name_width$}  {:>8}  {:>5}  {:>5}  {}",
+                hotspot.name.bold(),
+                red(&hotspot.failures.to_string()),
+                hotspot.tests,
+                format!("{}/{}", hotspot.failing_runs(), self.runs),
+                cells));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Everything before the last `.` of a dotted name,
+/// ignoring any parameters, e.g. `Foo.Bar(1.5)` -> `Foo`.
+fn parent(name: &str) -> &str {
+    let end = name.find('(').unwrap_or(name.len());
+    match name[..end].rfind('.') {
+        Some(idx) => &name[..idx],
+        None => "",
+    }
+}
+
+const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+
+fn heat(count: usize, max: usize) -> colored::ColoredString {
+    if count == 0 {
+        return muted("·");
+    }
+    let idx = ((count as f64 / max as f64) * SHADES.len() as f64).ceil() as usize;
+    red(SHADES[idx.clamp(1, SHADES.len()) - 1])
+}
@@ -0,0 +1,127 @@
+//! Rendering test results as a standalone HTML page,
+//! e.g. to publish as a CI artifact. Suites are collapsible
+//! (those with failures start expanded), and everything
+//! is inline so the file can be opened on its own.
+
+use anyhow::Result;
+use std::{fs, path::Path};
+use crate::testing::{TestSummary, TestSuite, TestCase, TestResult};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 2em; color: #1c1c1c; }
+h1 { font-size: 1.4em; }
+details { margin-left: 1.2em; }
+summary { cursor: pointer; padding: 0.15em 0; }
+.counts { color: #666; font-size: 0.9em; margin-left: 0.5em; }
+.duration { color: #888; font-size: 0.85em; margin-left: 0.5em; }
+.case { margin-left: 2.4em; padding: 0.1em 0; }
+.passed { color: #00af87; }
+.failed { color: #ff2f6d; font-weight: bold; }
+.skipped { color: #888; }
+pre { background: #f5f5f5; padding: 0.6em; margin: 0.3em 0 0.6em 1.2em; overflow-x: auto; font-size: 0.85em; }
+pre.message { color: #ff2f6d; }
+.chart { display: flex; align-items: center; gap: 1.5em; margin-bottom: 1.5em; }
+.pie { width: 6em; height: 6em; border-radius: 50%; }
+.bar { display: flex; width: 20em; height: 0.8em; border-radius: 0.4em; overflow: hidden; background: #eee; }
+.legend span { margin-right: 1em; }
+"#;
+
+/// Render the results as an HTML document.
+pub fn to_html(summary: &TestSummary) -> String {
+    let (mut passed, mut failed, mut skipped, mut duration) = (0, 0, 0, 0.);
+    for suite in summary.suites() {
+        passed += suite.passed;
+        failed += suite.failed;
+        skipped += suite.skipped;
+        duration += suite.duration;
+    }
+    let total = passed + failed + skipped;
+
+    let mut html = vec![
+        "<!DOCTYPE html>".to_string(),
+        "<html><head><meta charset=\"utf-8\"><title>Test results</title>".to_string(),
+        format!("<style>{}</style></head><body>", STYLE),
+        format!("<h1>Test results <span class=\"duration\">{:.2}s</span></h1>", duration),
+        chart(passed, failed, skipped),
+    ];
+    if total == 0 {
+        html.push("<p class=\"skipped\">No tests were run.</p>".to_string());
+    }
+    if summary.truncated() {
+        html.push("<p class=\"failed\">These results were truncated, some tests may be missing.</p>".to_string());
+    }
+    for suite in summary.suites() {
+        render_suite(suite, &mut html);
+    }
+    html.push("</body></html>".to_string());
+    html.join("\n")
+}
+
+/// Write the results to an HTML file.
+pub fn write_html(summary: &TestSummary, path: &Path) -> Result<()> {
+    fs::write(path, to_html(summary))?;
+    Ok(())
+}
+
+/// A pie chart and a bar of the pass/fail/skip proportions.
+fn chart(passed: usize, failed: usize, skipped: usize) -> String {
+    let total = (passed + failed + skipped).max(1) as f64;
+    let pct = |n: usize| n as f64 / total * 100.;
+    let (passed_pct, failed_pct, skipped_pct) = (pct(passed), pct(failed), pct(skipped));
+    let pie = format!(
+        "<div class=\"pie\" style=\"background: conic-gradient(#00af87 0 {p:.1}%, #ff2f6d {p:.1}% {f:.1}%, #ccc {f:.1}% 100%)\"></div>",
+        p = passed_pct, f = passed_pct + failed_pct);
+    let bar = format!(
+        "<div class=\"bar\"><div style=\"width: {:.1}%; background: #00af87\"></div><div style=\"width: {:.1}%; background: #ff2f6d\"></div><div style=\"width: {:.1}%; background: #ccc\"></div></div>",
+        passed_pct, failed_pct, skipped_pct);
+    let legend = format!(
+        "<div class=\"legend\"><span class=\"passed\">{} passed</span><span class=\"failed\">{} failed</span><span class=\"skipped\">{} skipped</span></div>",
+        passed, failed, skipped);
+    format!("<div class=\"chart\">{}<div>{}{}</div></div>", pie, bar, legend)
+}
+
+fn render_suite(suite: &TestSuite, html: &mut Vec<String>) {
+    let class = if suite.failed > 0 { "failed" } else if suite.passed == suite.total { "passed" } else { "skipped" };
+    html.push(format!("<details{}>", if suite.failed > 0 { " open" } else { "" }));
+    html.push(format!(
+        "<summary><span class=\"{}\">{}</span><span class=\"counts\">{} passed, {} failed, {} skipped</span><span class=\"duration\">{:.2}s</span></summary>",
+        class, escape(&suite.name), suite.passed, suite.failed, suite.skipped, suite.duration));
+    for child in suite.child_suites() {
+        render_suite(child, html);
+    }
+    for case in suite.child_cases() {
+        render_case(case, html);
+    }
+    html.push("</details>".to_string());
+}
+
+fn render_case(case: &TestCase, html: &mut Vec<String>) {
+    let (class, icon) = match case.result {
+        TestResult::Passed => ("passed", "✓"),
+        TestResult::Failed => ("failed", "✗"),
+        TestResult::Skipped => ("skipped", "-"),
+    };
+    html.push(format!(
+        "<div class=\"case\"><span class=\"{}\">{} {}</span><span class=\"duration\">{:.2}s</span>",
+        class, icon, escape(&case.name), case.duration));
+    if let Some(message) = case.message() {
+        let class = if case.failed() { "message" } else { "reason" };
+        html.push(format!("<pre class=\"{}\">{}</pre>", class, escape(message.trim())));
+    }
+    if case.failed() {
+        if let Some(trace) = case.stack_trace() {
+            html.push(format!("<pre>{}</pre>", escape(trace.trim())));
+        }
+        if let Some(output) = case.output() {
+            html.push(format!("<pre>{}</pre>", escape(output.trim())));
+        }
+    }
+    html.push("</div>".to_string());
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,41 @@
+//! Driving Unity Hub's headless CLI.
+
+use anyhow::{Result, anyhow, bail};
+use std::{env, path::PathBuf, process::Command};
+
+/// Where Unity Hub is usually installed, per platform.
+#[cfg(target_os = "linux")]
+const HUB_PATHS: &[&str] = &["/usr/bin/unityhub", "/opt/unityhub/unityhub"];
+#[cfg(target_os = "macos")]
+const HUB_PATHS: &[&str] = &["/Applications/Unity Hub.app/Contents/MacOS/Unity Hub"];
+#[cfg(target_os = "windows")]
+const HUB_PATHS: &[&str] = &["C:\\Program Files\\Unity Hub\\Unity Hub.exe"];
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+const HUB_PATHS: &[&str] = &[];
+
+/// Find the Unity Hub binary, preferring `UNITY_HUB_PATH` if set.
+fn find_hub() -> Result<PathBuf> {
+    if let Ok(path) = env::var("UNITY_HUB_PATH") {
+        return Ok(path.into());
+    }
+    HUB_PATHS.iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+        .ok_or_else(|| anyhow!(
+                "Couldn't find Unity Hub, set UNITY_HUB_PATH to its binary"))
+}
+
+/// Install build support modules (e.g. `android`, `linux-il2cpp`)
+/// for an installed editor version. Hub's progress output is
+/// passed through as-is.
+pub fn install_modules(version: &str, modules: &[String]) -> Result<()> {
+    let hub = find_hub()?;
+    let status = Command::new(hub)
+        .args(["--", "--headless", "install-modules", "--version", version, "--childModules", "-m"])
+        .args(modules)
+        .status()?;
+    if !status.success() {
+        bail!("Unity Hub failed to install modules ({})", status);
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! IL2CPP build errors and managed code stripping warnings, from the
+//! editor log. Code that compiles fine for Mono can still fail to
+//! build with IL2CPP (e.g. generics it can't compile ahead of time),
+//! or lose code the linker strips that's only reached by reflection.
+//! These come from `il2cpp`, the Bee build of its C++ output, and
+//! `UnityLinker`, rather than the C# compiler, e.g.:
+//!
+//! ```text
+//! Error: IL2CPP error for method 'System.Void Game.Foo::Bar()' in assembly 'Library/Bee/.../Game.dll'
+//! Building Library/Bee/artifacts/.../Game.o failed with output:
+//! Il2CppOutputProject/Source/il2cppOutput/Game.cpp:120:3: error: use of undeclared identifier 'x'
+//! UnityLinker: warning IL2104: Assembly 'Game' produced trim warnings.
+//! ```
+
+/// Errors and warnings from the IL2CPP side of a build.
+#[derive(Debug, Default)]
+pub(crate) struct Il2cppDiagnostics {
+    pub(crate) errors: Vec<String>,
+    pub(crate) stripping_warnings: Vec<String>,
+}
+
+/// The IL2CPP errors and stripping warnings in the log,
+/// each once, in the order they were logged.
+pub(crate) fn diagnostics(log: &str) -> Il2cppDiagnostics {
+    let mut diagnostics = Il2cppDiagnostics::default();
+    for line in log.lines() {
+        let line = line.trim();
+        let list = if is_error(line) {
+            &mut diagnostics.errors
+        } else if is_stripping_warning(line) {
+            &mut diagnostics.stripping_warnings
+        } else {
+            continue;
+        };
+        if !list.iter().any(|seen| seen == line) {
+            list.push(line.to_string());
+        }
+    }
+    diagnostics
+}
+
+fn is_error(line: &str) -> bool {
+    line.contains("IL2CPP error for ")
+        || line.contains("il2cpp.exe didn't catch exception")
+        || line.contains("Il2CppBuilderException")
+        || line.contains("BuilderFailedException")
+        || line.contains("Fatal error in Unity CIL Linker")
+        // Bee's C++ build of il2cpp's output
+        || (line.starts_with("Building Library/Bee/") && line.ends_with("failed with output:"))
+        || (is_il2cpp_output(line) && (line.contains(": error") || line.contains("): fatal error")))
+}
+
+/// Whether the line is about il2cpp's generated C++.
+fn is_il2cpp_output(line: &str) -> bool {
+    line.contains("il2cppOutput") || line.contains("Il2CppOutputProject")
+}
+
+fn is_stripping_warning(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    if !lower.contains("warning") { return false }
+    // The linker's trim analysis warnings have codes like `IL2026`
+    lower.contains("unitylinker") || line.split(|c: char| !c.is_ascii_alphanumeric()).any(is_trim_code)
+}
+
+fn is_trim_code(word: &str) -> bool {
+    word.len() == 6 && word.starts_with("IL2") && word[2..].chars().all(|c| c.is_ascii_digit())
+}
@@ -0,0 +1,101 @@
+//! Converting test results into JUnit XML, which most CI
+//! systems (GitLab, Jenkins, Azure DevOps) can ingest.
+//!
+//! Each fixture becomes a `<testsuite>`, since JUnit
+//! has no notion of nested suites.
+
+use anyhow::Result;
+use quick_xml::{Writer, events::{Event, BytesDecl, BytesStart, BytesEnd, BytesText}};
+use std::{fs, path::Path};
+use crate::testing::{TestSummary, TestCase, TestResult};
+
+/// Render the results as a JUnit XML document.
+pub fn to_junit(summary: &TestSummary) -> Result<String> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    let fixtures = summary.fixtures();
+    let (tests, failures, skipped, time) = fixtures.iter()
+        .fold((0, 0, 0, 0.), |(t, f, s, d), suite| {
+            (t + suite.total, f + suite.failed, s + suite.skipped, d + suite.duration)
+        });
+    let mut root = BytesStart::new("testsuites");
+    root.push_attribute(("tests", tests.to_string().as_str()));
+    root.push_attribute(("failures", failures.to_string().as_str()));
+    root.push_attribute(("skipped", skipped.to_string().as_str()));
+    root.push_attribute(("time", time.to_string().as_str()));
+    writer.write_event(Event::Start(root))?;
+
+    for fixture in fixtures {
+        let mut suite = BytesStart::new("testsuite");
+        suite.push_attribute(("name", fixture.name.as_str()));
+        suite.push_attribute(("tests", fixture.total.to_string().as_str()));
+        suite.push_attribute(("failures", fixture.failed.to_string().as_str()));
+        suite.push_attribute(("errors", "0"));
+        suite.push_attribute(("skipped", fixture.skipped.to_string().as_str()));
+        suite.push_attribute(("time", fixture.duration.to_string().as_str()));
+        writer.write_event(Event::Start(suite))?;
+        for case in fixture.cases() {
+            write_case(&mut writer, case)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("testsuite")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("testsuites")))?;
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Write the results to a JUnit XML file.
+pub fn write_junit(summary: &TestSummary, path: &Path) -> Result<()> {
+    fs::write(path, to_junit(summary)?)?;
+    Ok(())
+}
+
+fn write_case(writer: &mut Writer<Vec<u8>>, case: &TestCase) -> Result<()> {
+    let mut elem = BytesStart::new("testcase");
+    elem.push_attribute(("name", case.name.as_str()));
+    elem.push_attribute(("classname", case.class_name.as_str()));
+    elem.push_attribute(("time", case.duration.to_string().as_str()));
+    writer.write_event(Event::Start(elem))?;
+
+    let message = case.message().unwrap_or_default();
+    let summary = message.lines().next().unwrap_or("").trim();
+    match case.result {
+        TestResult::Failed => {
+            let mut failure = BytesStart::new("failure");
+            failure.push_attribute(("message", summary));
+            failure.push_attribute(("type", "Failure"));
+            writer.write_event(Event::Start(failure))?;
+            let body = match case.stack_trace() {
+                Some(trace) => format!("{}\n{}", message, trace),
+                None => message.clone(),
+            };
+            writer.write_event(Event::Text(BytesText::new(&body)))?;
+            writer.write_event(Event::End(BytesEnd::new("failure")))?;
+        },
+        TestResult::Skipped => {
+            let mut skipped = BytesStart::new("skipped");
+            skipped.push_attribute(("message", summary));
+            writer.write_event(Event::Empty(skipped))?;
+        },
+        TestResult::Passed => {},
+    }
+    // Attachments are listed as `[[ATTACHMENT|path]]`, which
+    // e.g. GitLab and Jenkins show with the test
+    let attachments = case.attachments().iter()
+        .map(|attachment| format!("[[ATTACHMENT|{}]]", attachment.file_path))
+        .collect::<Vec<_>>();
+    let output = match (case.output(), attachments.is_empty()) {
+        (output, true) => output,
+        (Some(output), false) => Some(format!("{}\n{}", output, attachments.join("\n"))),
+        (None, false) => Some(attachments.join("\n")),
+    };
+    if let Some(output) = output {
+        writer.write_event(Event::Start(BytesStart::new("system-out")))?;
+        writer.write_event(Event::Text(BytesText::new(&output)))?;
+        writer.write_event(Event::End(BytesEnd::new("system-out")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("testcase")))?;
+    Ok(())
+}
@@ -1,90 +1,1033 @@
 mod testing;
+mod scripts;
+mod watchdog;
+mod crash;
+mod fatal;
+mod error;
 pub mod display;
+pub mod log;
+pub mod hotspots;
+pub mod ratchet;
+pub mod hub;
+pub mod seats;
+pub mod editors;
+pub mod build;
+pub mod compiler;
+pub mod json;
+pub mod junit;
+pub mod html;
+pub mod markdown;
+pub mod tap;
+pub mod teamcity;
+pub mod watch;
+pub mod config;
+pub mod coverage;
+pub mod run;
+pub mod events;
+pub mod github;
+pub mod lock;
+pub mod rerun;
+pub mod flaky;
+pub mod license;
+pub mod cleanup;
+pub mod completions;
+pub mod assemblies;
+pub mod discovery;
+pub mod picker;
+pub mod packages;
+pub mod bundles;
+pub mod addressables;
+pub mod cache;
+pub mod server;
+pub mod mcp;
+pub mod history;
+pub mod diff;
+pub mod baseline;
+pub mod shard;
+pub mod validate;
+pub mod hooks;
+pub mod logs;
+pub mod doctor;
+pub mod docker;
+pub mod trace;
+pub mod source;
+pub mod open;
+pub mod explain;
+pub mod bisect;
+pub mod artifacts;
+mod il2cpp;
+pub mod burst;
+pub mod shaders;
+pub mod sizes;
+pub mod unitypackage;
+pub mod scaffold;
+pub mod smoke;
+pub mod workspace;
+pub mod notify;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use clap::ValueEnum;
-use std::{process::Command, str, fs, path::PathBuf, collections::HashSet};
-use testing::{TestSummary, load_test_results};
+use serde::Serialize;
+use std::{process::{Command, Stdio}, fs, env, io::{BufRead, BufReader}, path::{Path, PathBuf}, collections::HashSet, thread, time::Duration};
+pub use testing::{TestSummary, TestCase, TestResult, Attachment};
+pub use watchdog::TimeoutError;
+pub use crash::EditorCrashed;
+pub use fatal::{FatalError, FatalCause};
+pub use error::UnitoolError;
+pub use run::TestRun;
+use testing::load_test_results;
+use hotspots::{GroupBy, HotspotReport};
+use build::{BuildTarget, BuildOptions, BuildReport};
+use discovery::TestList;
+use bundles::BundleReport;
+use addressables::AnalyzeReport;
+use unitypackage::{PackageReport, Direction};
+use scaffold::{NewProject, Template};
+use smoke::SmokeReport;
+use scripts::InjectedScript;
+use compiler::CompileError;
+use events::{RunEvent, EventHandler};
 
-const UNITY_DIR: &str = "/opt/Unity/";
-const TEST_RESULTS_PATH: &str = "/tmp/unity-test-results.xml";
+/// Environment variable pointing to the editor binary to use.
+pub const UNITY_PATH_VAR: &str = "UNITY_PATH";
+
+/// The editor's flag for letting the API updater run in batch mode.
+const ACCEPT_API_UPDATE_ARG: &str = "-accept-apiupdate";
+
+/// Process exit codes, so scripts can tell
+/// what kind of failure happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitCode {
+    Success = 0,
+
+    /// The project failed to compile, or a ratchet failed
+    CompileFailed = 1,
+
+    /// At least one test failed
+    TestsFailed = 2,
+
+    /// The editor couldn't be launched, or crashed,
+    /// or a method run with [`exec`] failed
+    EditorFailed = 3,
+
+    /// The player build failed
+    BuildFailed = 4,
+
+    /// Anything else, e.g. unreadable results files
+    Error = 5,
+
+    /// `validate` found broken references, orphaned meta files or duplicate
+    /// GUIDs, `addressables analyze` or `smoke` found problems, or
+    /// a project `new` created doesn't open cleanly
+    ValidationFailed = 6,
+}
+impl ExitCode {
+    /// Exit straight away. This skips destructors, so for anything
+    /// holding e.g. a [`lock::ProjectLock`] or [`seats::Seat`], return the code
+    /// from `main` instead.
+    pub fn exit(self) -> ! {
+        std::process::exit(self as i32)
+    }
+}
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        std::process::ExitCode::from(code as u8)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, ValueEnum)]
 pub enum TestMode {
     EditMode,
     PlayMode,
+
+    /// Edit mode then play mode, with the results merged
+    All,
+}
+
+/// A graphics API to force the editor to use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphicsApi {
+    OpenGl,
+    Vulkan,
+}
+impl GraphicsApi {
+    fn arg(&self) -> &'static str {
+        match self {
+            GraphicsApi::OpenGl => "-force-glcore",
+            GraphicsApi::Vulkan => "-force-vulkan",
+        }
+    }
 }
 
-type CompileErrors = HashSet<String>;
+/// Whether there's a display for the editor to render to.
+/// Only Linux machines (e.g. CI runners) tend to go without.
+pub fn has_display() -> bool {
+    if cfg!(target_os = "linux") {
+        env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some()
+    } else {
+        true
+    }
+}
 
-/// Compile the project, returning any errors
-pub fn compile(project_path: &PathBuf) -> Result<CompileErrors> {
-    run_unity(project_path, vec!["-quit"])
+/// How to order suites in the test summary.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum SortKey {
+    Name,
+    Time,
 }
 
-/// Test the project, with optional filters.
-/// For what filters work, see:
-/// <https://docs.unity3d.com/Packages/com.unity.test-framework@1.1/manual/reference-command-line.html>
-pub fn test(project_path: &PathBuf, mode: TestMode, assemblies: &str, filters: Option<String>) -> Result<(CompileErrors, Option<TestSummary>)> {
-    let platform = match mode {
+pub type CompileErrors = HashSet<CompileError>;
+pub type CompileWarnings = HashSet<String>;
+
+/// How many lines from the end of the editor
+/// log to keep for diagnosing failures.
+const LOG_TAIL_LINES: usize = 50;
+
+/// The compiler diagnostics from a Unity run.
+#[derive(Debug, Default, Serialize)]
+pub struct CompileOutput {
+    pub errors: CompileErrors,
+    pub warnings: CompileWarnings,
+
+    /// Managed exceptions logged along the way,
+    /// e.g. from import or editor scripts
+    pub exceptions: Vec<log::LogException>,
+
+    /// Errors and warnings from compiling shaders on import
+    pub shaders: shaders::ShaderDiagnostics,
+
+    /// The editor's exit code, if it wasn't killed by a signal
+    pub exit_code: Option<i32>,
+
+    /// The last lines of the editor log
+    pub log_tail: Vec<String>,
+
+    /// How many times the editor was run,
+    /// more than once if it was retried
+    pub attempts: u32,
+
+    /// Where the full log was saved, if it was
+    pub log_path: Option<PathBuf>,
+
+    /// The full (sanitized) editor log
+    #[serde(skip)]
+    pub(crate) log: String,
+}
+
+/// A callback for editor log lines.
+pub type LogHandler<'a> = Box<dyn Fn(&str) + Send + 'a>;
+
+/// A run about to be retried, for [`RunOptions::on_retry`].
+pub struct Retry<'e> {
+    /// The attempt about to be made, from 2
+    pub attempt: u32,
+
+    /// The most attempts that will be made
+    pub max_attempts: u32,
+
+    /// How long until it's made
+    pub delay: Duration,
+
+    /// Why the last attempt failed
+    pub error: &'e anyhow::Error,
+}
+
+/// A callback for retried runs.
+pub type RetryHandler<'a> = Box<dyn Fn(&Retry) + Send + 'a>;
+
+/// A callback for where a run's full log was saved.
+pub type LogSavedHandler<'a> = Box<dyn Fn(&Path) + Send + 'a>;
+
+/// Options for how the editor is run.
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    /// Overrides which editor binary is used
+    pub unity_path: Option<PathBuf>,
+
+    /// Called with each line of the editor log as it's
+    /// output, skipping lines that are just noise
+    pub on_log: Option<LogHandler<'a>>,
+
+    /// Called with progress events parsed from the log
+    pub on_event: Option<EventHandler<'a>>,
+
+    /// Also send events for each test starting and finishing,
+    /// which needs a script injected into the project
+    pub test_events: bool,
+
+    /// Kill the editor (and anything it spawned)
+    /// if it runs longer than this
+    pub timeout: Option<Duration>,
+
+    /// Run without initializing graphics (`-nographics`),
+    /// e.g. for play mode tests on machines without a display
+    pub nographics: bool,
+
+    pub graphics_api: Option<GraphicsApi>,
+
+    /// Extra arguments to pass to the editor
+    pub extra_args: Vec<String>,
+
+    /// How many times to retry runs that fail for
+    /// transient reasons (see [`is_transient`])
+    pub retries: u32,
+
+    /// How long to wait before the first retry,
+    /// doubling for each one after
+    pub retry_backoff: Duration,
+
+    /// Called before each retry
+    pub on_retry: Option<RetryHandler<'a>>,
+
+    /// How many full editor logs to keep in the project's
+    /// `.unitool/logs` (see [`logs`]), or 0 to not save them
+    pub keep_logs: usize,
+
+    /// Called with where each run's full log was saved, as
+    /// it's saved before any error with the run is returned
+    pub on_log_saved: Option<LogSavedHandler<'a>>,
+
+    /// Run the editor in this Docker image (see [`docker`]),
+    /// rather than a locally installed one
+    pub docker: Option<docker::Image>,
+
+    /// Treat diagnostics with these codes as errors or
+    /// warnings, or ignore them (see [`compiler`])
+    pub severities: compiler::SeverityOverrides,
+
+    /// Let the API updater update scripts that use obsolete APIs
+    /// (`-accept-apiupdate`), rather than stopping the run if
+    /// it wants to
+    pub accept_api_update: bool,
+}
+
+/// Compile the project, returning any errors and warnings
+/// (recording them for [`cache::lookup`]).
+pub fn compile(project_path: &Path, options: &RunOptions) -> Result<CompileOutput> {
+    // Fingerprinted first, so changes made during
+    // the compile aren't taken as compiled
+    let fingerprint = cache::fingerprint(project_path, options);
+    let output = run_unity(project_path, options, vec!["-quit"])?;
+    let _ = cache::record(project_path, &fingerprint, &output);
+    Ok(output)
+}
+
+/// Open the project and check it does so cleanly: returning any
+/// compile errors, and what else went wrong opening it (see
+/// [`smoke`]). Unlike [`compile`], a previous run isn't reused.
+pub fn smoke(project_path: &Path, options: &RunOptions) -> Result<(CompileErrors, SmokeReport)> {
+    smoke_report(run_unity(project_path, options, vec!["-quit"]))
+}
+
+/// What went wrong opening a project, from the run that opened it.
+fn smoke_report(opened: Result<CompileOutput>) -> Result<(CompileErrors, SmokeReport)> {
+    match opened {
+        Ok(output) => {
+            let report = SmokeReport::parse(&output.log, output.exceptions);
+            Ok((output.errors, report))
+        },
+        // Reported along with the rest, rather than as the run failing
+        Err(err) => match err.downcast::<FatalError>() {
+            Ok(FatalError { cause: FatalCause::PackageResolution(errors), .. }) =>
+                Ok((CompileErrors::new(), SmokeReport { package_errors: errors, ..Default::default() })),
+            Ok(FatalError { cause: FatalCause::ApiUpdateRequired(messages), .. }) =>
+                Ok((CompileErrors::new(), SmokeReport { api_updates: messages, ..Default::default() })),
+            Ok(fatal) => Err(fatal.into()),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+/// Run a static method in the editor (e.g. `Builds.BundleAssets`),
+/// as with `-executeMethod`. `args` are passed on to the editor,
+/// for the method to read with `Environment.GetCommandLineArgs()`.
+pub fn exec(project_path: &Path, options: &RunOptions, method: &str, args: &[String]) -> Result<CompileOutput> {
+    let mut unity_args = vec!["-quit", "-executeMethod", method];
+    unity_args.extend(args.iter().map(|arg| arg.as_str()));
+    run_unity(project_path, options, unity_args)
+}
+
+/// Export the assets at `paths` (relative to the project, e.g.
+/// `Assets/Plugins/Foo`, with what's under them) as a `.unitypackage`
+/// at `output_path`, returning any compile errors, or the report
+/// of what's in it if compilation succeeded.
+pub fn export_package(project_path: &Path, options: &RunOptions, paths: &[PathBuf], output_path: &Path) -> Result<(CompileErrors, Option<PackageReport>)> {
+    for path in paths {
+        if !project_path.join(path).exists() {
+            bail!("There's nothing at {} in the project", path.display());
+        }
+    }
+    let output_path = std::path::absolute(output_path)?;
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // So a stale one isn't taken as exported
+    if output_path.exists() {
+        fs::remove_file(&output_path)?;
+    }
+
+    let mut args = vec!["-quit", "-exportPackage"];
+    for path in paths {
+        args.push(path.to_str().ok_or_else(|| anyhow!("{} isn't valid UTF-8", path.display()))?);
+    }
+    args.push(output_path.to_str().ok_or_else(|| anyhow!("The output path isn't valid UTF-8"))?);
+    let output = run_unity(project_path, options, args)?;
+    if !output.errors.is_empty() {
+        return Ok((output.errors, None));
+    }
+
+    let errors = unitypackage::errors(&output.log);
+    let assets = if output_path.exists() {
+        unitypackage::contents(&output_path)?
+    } else if errors.is_empty() {
+        bail!("The package wasn't exported. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n")));
+    } else {
+        vec![]
+    };
+    Ok((output.errors, Some(PackageReport {
+        direction: Direction::Export,
+        file: output_path,
+        assets,
+        missing: vec![],
+        errors,
+    })))
+}
+
+/// Import a `.unitypackage` into the project, returning the
+/// report of what was imported, along with any compile errors
+/// (e.g. in the scripts it imported).
+pub fn import_package(project_path: &Path, options: &RunOptions, file: &Path) -> Result<(CompileErrors, PackageReport)> {
+    let file = std::path::absolute(file)?;
+    if !file.is_file() {
+        bail!("There's no package at {}", file.display());
+    }
+    let contents = unitypackage::contents(&file)?;
+    let output = run_unity(project_path, options, vec![
+        "-quit", "-importPackage",
+        file.to_str().ok_or_else(|| anyhow!("The package path isn't valid UTF-8"))?,
+    ])?;
+    let (assets, missing) = contents.into_iter()
+        .partition(|asset| project_path.join(asset).exists());
+    Ok((output.errors, PackageReport {
+        direction: Direction::Import,
+        errors: unitypackage::errors(&output.log),
+        file,
+        assets,
+        missing,
+    }))
+}
+
+/// List the tests for the mode without running them,
+/// returning any compile errors, or the tests if
+/// compilation succeeded.
+pub fn list_tests(project_path: &Path, options: &RunOptions, mode: TestMode) -> Result<(CompileErrors, Option<TestList>)> {
+    let _script = InjectedScript::inject(project_path, scripts::LIST_TESTS)?;
+    let mode = match mode {
         TestMode::EditMode => "EditMode",
         TestMode::PlayMode => "PlayMode",
+        TestMode::All => "All",
     };
+    let output = run_unity(project_path, options, vec![
+        "-executeMethod", "Unitool.ListTests.Run",
+        "-unitoolTestMode", mode,
+    ])?;
+    if !output.errors.is_empty() {
+        return Ok((output.errors, None));
+    }
+    match TestList::parse(&output.log) {
+        Some(tests) => Ok((output.errors, Some(tests))),
+        None => bail!("The tests weren't listed. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n"))),
+    }
+}
+
+/// Options for how tests are run.
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Record code coverage, to be loaded
+    /// after with [`coverage::load_coverage`]
+    pub coverage: bool,
+
+    /// Keep the raw results XML at this path, rather than
+    /// in a temp file that's removed after the run
+    pub results_out: Option<PathBuf>,
+
+    /// Run play mode tests in a player built for this
+    /// platform, rather than in the editor
+    pub platform: Option<BuildTarget>,
+
+    /// Run the tests in a random order, from this seed
+    pub random_order_seed: Option<u32>,
+
+    /// Run the tests in the order listed in this file, one full
+    /// test name per line (see [`rerun::save_failures_order`])
+    pub order_file: Option<PathBuf>,
+}
+
+/// Test the project, with optional filters and NUnit categories.
+/// For what filters work, see:
+/// <https://docs.unity3d.com/Packages/com.unity.test-framework@1.1/manual/reference-command-line.html>
+/// See [`TestRun`] for more options.
+pub fn test(project_path: &Path, options: &RunOptions, mode: TestMode, assemblies: Option<&str>, filters: Option<String>, categories: Option<String>, test_options: &TestOptions) -> Result<(CompileErrors, Option<TestSummary>)> {
+    let mut run = TestRun::new(project_path)
+        .mode(mode)
+        .coverage(test_options.coverage);
+    if let Some(assemblies) = assemblies {
+        run = run.assemblies(assemblies);
+    }
+    if let Some(path) = &test_options.results_out {
+        run = run.results_path(path);
+    }
+    if let Some(filters) = filters {
+        run = run.filters(filters);
+    }
+    if let Some(categories) = categories {
+        run = run.categories(categories);
+    }
+    if let Some(platform) = &test_options.platform {
+        run = run.platform(platform.clone());
+    }
+    if let Some(seed) = test_options.random_order_seed {
+        run = run.random_order_seed(seed);
+    }
+    if let Some(path) = &test_options.order_file {
+        run = run.order_file(path);
+    }
+    run.run(options)
+}
+
+/// Build a player for the target, to the output path.
+/// Returns any compile errors, or the build's report.
+pub fn build(project_path: &Path, options: &RunOptions, target: BuildTarget, output_path: &Path, build_options: &BuildOptions) -> Result<(CompileErrors, Option<BuildReport>)> {
+    let editor = find_build_editor(project_path, options, &target)?;
+
+    let output_path = std::path::absolute(output_path)?;
+    let output_path = output_path.to_str()
+        .ok_or_else(|| anyhow!("The output path isn't valid UTF-8"))?;
+    let _script = InjectedScript::inject(project_path, scripts::BUILD)?;
 
-    let filters = filters.unwrap_or("".to_string());
     let mut args = vec![
-      "-runTests",
-      "-testPlatform", platform,
-      "-testResults", TEST_RESULTS_PATH,
-      "-testFilter", &filters,
-      "-assemblyNames", assemblies,
+        "-buildTarget", target.arg(),
+        "-executeMethod", "Unitool.Build.Run",
+        "-unitoolOutput", output_path,
     ];
+    if build_options.development {
+        args.push("-unitoolDevelopment");
+    }
+    if let Some(backend) = &build_options.scripting_backend {
+        args.extend(["-unitoolScriptingBackend", backend.arg()]);
+    }
+    if let Some(level) = &build_options.strip_level {
+        args.extend(["-unitoolStripLevel", level.arg()]);
+    }
 
-    // Edit mode tests need to run synchronously
-    // or they lock up Unity in batchmode
-    if mode == TestMode::EditMode {
-        args.push("-runSynchronously");
+    let output = run_editor(&editor, Some(project_path), options, args)?;
+    if !output.errors.is_empty() {
+        return Ok((output.errors, None));
+    }
+    match BuildReport::parse(&output.log) {
+        Some(report) => Ok((output.errors, Some(report))),
+        None => bail!("The build didn't report a result. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n"))),
     }
+}
 
-    let errs = run_unity(project_path, args)?;
+/// Check the project's jobs compile with Burst ahead of time, which
+/// only happens when building a player, by building one for the
+/// target into a temp directory (removed after). Returns any compile
+/// errors, or the build's report, with any Burst errors.
+pub fn burst_check(project_path: &Path, options: &RunOptions, target: BuildTarget) -> Result<(CompileErrors, Option<BuildReport>)> {
+    let uses_burst = ["Packages/manifest.json", "Packages/packages-lock.json"].iter()
+        .any(|file| fs::read_to_string(project_path.join(file))
+            .is_ok_and(|contents| contents.contains("\"com.unity.burst\"")));
+    if !uses_burst {
+        bail!("The project doesn't use Burst (the `com.unity.burst` package)");
+    }
+    let dir = std::env::temp_dir().join(format!("unitool-burst-{}", std::process::id()));
+    let result = build(project_path, options, target, &dir.join("player"), &BuildOptions::default());
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
 
-    if errs.is_empty() {
-        let results = load_test_results(&TEST_RESULTS_PATH.into());
-        Ok((errs, Some(results)))
-    } else {
-        Ok((errs, None))
+/// Build the project's AssetBundles for the target into `output_path`,
+/// returning any compile errors, or the report if compilation succeeded.
+pub fn build_bundles(project_path: &Path, options: &RunOptions, target: BuildTarget, output_path: &Path) -> Result<(CompileErrors, Option<BundleReport>)> {
+    let editor = find_build_editor(project_path, options, &target)?;
+    let output_path = std::path::absolute(output_path)?;
+    let output_path = output_path.to_str()
+        .ok_or_else(|| anyhow!("The output path isn't valid UTF-8"))?;
+    let _script = InjectedScript::inject(project_path, scripts::BUILD_BUNDLES)?;
+    let output = run_editor(&editor, Some(project_path), options, vec![
+        "-buildTarget", target.arg(),
+        "-executeMethod", "Unitool.BuildBundles.Run",
+        "-unitoolOutput", output_path,
+    ])?;
+    bundle_report(output)
+}
+
+/// Build the project's Addressables content for the target (to
+/// where its Addressables settings say), as `build_bundles` does.
+pub fn build_addressables(project_path: &Path, options: &RunOptions, target: BuildTarget) -> Result<(CompileErrors, Option<BundleReport>)> {
+    let editor = find_build_editor(project_path, options, &target)?;
+    let _script = InjectedScript::inject(project_path, scripts::BUILD_ADDRESSABLES)?;
+    let output = run_editor(&editor, Some(project_path), options, vec![
+        "-buildTarget", target.arg(),
+        "-executeMethod", "Unitool.BuildAddressables.Run",
+    ])?;
+    bundle_report(output)
+}
+
+/// Build an update of the project's Addressables content for the
+/// target, of only what's changed since the build the content state
+/// file is from (by default, where the Addressables settings put it).
+pub fn update_addressables(project_path: &Path, options: &RunOptions, target: BuildTarget, content_state: Option<&Path>) -> Result<(CompileErrors, Option<BundleReport>)> {
+    let editor = find_build_editor(project_path, options, &target)?;
+    let content_state = content_state.map(std::path::absolute).transpose()?;
+    let mut args = vec![
+        "-buildTarget", target.arg(),
+        "-executeMethod", "Unitool.BuildAddressables.Update",
+    ];
+    if let Some(path) = &content_state {
+        args.push("-unitoolContentState");
+        args.push(path.to_str().ok_or_else(|| anyhow!("The content state path isn't valid UTF-8"))?);
+    }
+    let _script = InjectedScript::inject(project_path, scripts::BUILD_ADDRESSABLES)?;
+    let output = run_editor(&editor, Some(project_path), options, args)?;
+    bundle_report(output)
+}
+
+/// Run the project's Addressables analyze rules (for the target,
+/// otherwise the project's active one) without fixing anything,
+/// returning any compile errors, or the report if compilation
+/// succeeded.
+pub fn analyze_addressables(project_path: &Path, options: &RunOptions, target: Option<BuildTarget>) -> Result<(CompileErrors, Option<AnalyzeReport>)> {
+    let _script = InjectedScript::inject(project_path, scripts::ANALYZE_ADDRESSABLES)?;
+    let output = match target {
+        Some(target) => {
+            let editor = find_build_editor(project_path, options, &target)?;
+            run_editor(&editor, Some(project_path), options, vec![
+                "-buildTarget", target.arg(),
+                "-executeMethod", "Unitool.AnalyzeAddressables.Run",
+            ])?
+        },
+        None => run_unity(project_path, options, vec!["-executeMethod", "Unitool.AnalyzeAddressables.Run"])?,
+    };
+    if !output.errors.is_empty() {
+        return Ok((output.errors, None));
+    }
+    match AnalyzeReport::parse(&output.log) {
+        Some(report) => Ok((output.errors, Some(report))),
+        None => bail!("The analysis didn't report a result. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n"))),
+    }
+}
+
+fn bundle_report(output: CompileOutput) -> Result<(CompileErrors, Option<BundleReport>)> {
+    if !output.errors.is_empty() {
+        return Ok((output.errors, None));
+    }
+    match BundleReport::parse(&output.log) {
+        Some(report) => Ok((output.errors, Some(report))),
+        None => bail!("The bundle build didn't report a result. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n"))),
+    }
+}
+
+/// The editor to build with, if it can build for the target.
+pub(crate) fn find_build_editor(project_path: &Path, options: &RunOptions, target: &BuildTarget) -> Result<PathBuf> {
+    match &options.docker {
+        // Which modules an image has can't be checked without pulling it
+        Some(docker::Image::Named(_)) => return Ok(docker::EDITOR.into()),
+        Some(docker::Image::ForProject) if *target != BuildTarget::Linux64 =>
+            bail!("The default Docker image can only build for {}; pass one with the `{}` module, e.g. `--docker unityci/editor:ubuntu-<version>-{}-3`",
+                BuildTarget::Linux64.arg(), target.module().1, target.module().1),
+        Some(docker::Image::ForProject) => return Ok(docker::EDITOR.into()),
+        None => {},
+    }
+    let editor = find_unity_path(project_path, options.unity_path.as_deref())?;
+    if let Some(module) = build::missing_module(&editor, target) {
+        let version = project_version(project_path).unwrap_or("<version>".to_string());
+        bail!("The editor at {} can't build for {} without the `{}` module. Install it with:\n  unitool modules install --version {} {}",
+            editor.display(), target.arg(), module, version, module);
+    }
+    Ok(editor)
+}
+
+/// Open the project in the editor's GUI, optionally with a scene
+/// (e.g. `Assets/Scenes/Main.unity`). The editor is detached,
+/// so it keeps running after unitool exits.
+pub fn open(project_path: &Path, unity_path: Option<&Path>, scene: Option<&str>) -> Result<PathBuf> {
+    let editor = find_unity_path(project_path, unity_path)?;
+    let mut cmd = Command::new(&editor);
+    cmd.arg("-projectPath").arg(project_path);
+    if let Some(scene) = scene {
+        cmd.args(["-openScene", scene]);
+    }
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Its own process group, so that e.g. Ctrl-C
+    // in the terminal doesn't close it too
+    watchdog::isolate(&mut cmd);
+    cmd.spawn().map_err(|err| error::launch_failed(&editor, err))?;
+    Ok(editor)
+}
+
+/// Load a test results file, e.g. one kept from a CI run.
+pub fn load_results(results_path: &Path) -> Result<TestSummary> {
+    if !results_path.is_file() {
+        return Err(UnitoolError::ResultsMissing(results_path.to_path_buf()).into());
+    }
+    Ok(load_test_results(results_path)?)
+}
+
+/// Load several test results files (e.g. from shards,
+/// test modes, or devices) and merge them into one summary.
+pub fn merge_results(results_paths: &[PathBuf]) -> Result<TestSummary> {
+    let summaries = results_paths.iter()
+        .map(|path| load_results(path))
+        .collect::<Result<Vec<TestSummary>>>()?;
+    Ok(TestSummary::merge(summaries))
+}
+
+/// Aggregate the failures from a series of test results files
+/// (oldest first) into a ranked report.
+pub fn hotspots(results_paths: &[PathBuf], by: GroupBy) -> Result<HotspotReport> {
+    let runs = results_paths.iter()
+        .map(|path| load_results(path))
+        .collect::<Result<Vec<TestSummary>>>()?;
+    Ok(HotspotReport::new(&runs, by))
+}
+
+/// Find the Unity Editor binary to use. In order of precedence:
+/// the provided override, the `UNITY_PATH` environment variable,
+/// or the installed editor matching the project's version
+/// (see [`editors`] for where editors are looked for).
+/// If the project doesn't specify a version, the most recent
+/// installed editor is used.
+pub fn find_unity_path(project_path: &Path, unity_path: Option<&Path>) -> Result<PathBuf> {
+    find_editor(Some(project_path), unity_path)
+}
+
+/// As [`find_unity_path`], for commands which may not be for
+/// a project, in which case the most recent editor is used.
+pub(crate) fn find_editor(project_path: Option<&Path>, unity_path: Option<&Path>) -> Result<PathBuf> {
+    if let Some(path) = unity_path {
+        return Ok(path.to_path_buf());
     }
+    if let Ok(path) = env::var(UNITY_PATH_VAR) {
+        return Ok(path.into());
+    }
+
+    let editors = editors::installed_editors();
+    let editor = match project_path.and_then(project_version) {
+        Some(version) => {
+            match editors.iter().find(|editor| editor.version == version) {
+                Some(editor) => editor,
+                None => {
+                    let installed = editors.iter()
+                        .map(|editor| editor.version.clone())
+                        .collect();
+                    return Err(UnitoolError::VersionNotInstalled { version, installed }.into());
+                }
+            }
+        },
+        None => match editors.last() {
+            Some(editor) => editor,
+            None => return Err(UnitoolError::NoEditorsInstalled.into()),
+        },
+    };
+    Ok(editor.path.clone())
 }
 
-/// Find the path to the most recent Unity Editor binary.
-fn find_unity_path() -> Result<PathBuf> {
-    let mut cands = fs::read_dir(UNITY_DIR)?
-        .map(|dir| dir.unwrap().path())
-        .collect::<Vec<PathBuf>>();
-    cands.sort();
+/// Create a project at `project_path` (which mustn't exist, or
+/// must be empty) from one of the editor's templates or the user's
+/// own (see [`scaffold`]), with the newest installed editor matching
+/// `version` (e.g. `2022.3` or `2022.3.x`), optionally seeding it
+/// with edit and play mode test assemblies. The project's then opened
+/// (importing its assets) to check it does so cleanly, as with
+/// [`smoke`], returning any compile errors along with what was
+/// created.
+pub fn create_project(project_path: &Path, options: &RunOptions, version: Option<&str>, template: Template, seed_tests: bool) -> Result<(CompileErrors, NewProject)> {
+    if options.docker.is_some() {
+        bail!("Projects can't be created in Docker; create it with a local editor");
+    }
+    if fs::read_dir(project_path).is_ok_and(|mut entries| entries.next().is_some()) {
+        bail!("There's already something at {}", project_path.display());
+    }
+    let editor = match version {
+        Some(version) if options.unity_path.is_none() && env::var(UNITY_PATH_VAR).is_err() => {
+            let editors = editors::installed_editors();
+            let prefix = format!("{}.", version.trim_end_matches(['x', '*']).trim_end_matches('.'));
+            match editors.iter().rev().find(|editor| editor.version == version || editor.version.starts_with(&prefix)) {
+                Some(editor) => editor.path.clone(),
+                None => {
+                    let installed: Vec<String> = editors.iter().map(|editor| editor.version.clone()).collect();
+                    bail!("No installed editor matches {}. Installed versions: {}", version, installed.join(", "));
+                },
+            }
+        },
+        _ => find_editor(None, options.unity_path.as_deref())?,
+    };
+    // The plain project `-createProject` makes is 3D, for
+    // editors without their templates
+    let template_file = match scaffold::template_file(&editor, &template) {
+        Ok(file) => file,
+        Err(_) if template == Template::ThreeD => None,
+        Err(err) => return Err(err),
+    };
 
-    let dir = &cands[0];
-    let path = PathBuf::from(dir).join("Editor/Unity");
-    Ok(path)
+    let path = std::path::absolute(project_path)?;
+    let path_arg = path.to_str().ok_or_else(|| anyhow!("The project path isn't valid UTF-8"))?;
+    let mut args = vec!["-quit", "-createProject", path_arg];
+    if let Some(file) = &template_file {
+        args.push("-cloneFromTemplate");
+        args.push(file.to_str().ok_or_else(|| anyhow!("The template path isn't valid UTF-8"))?);
+    }
+    let output = run_editor(&editor, None, options, args)?;
+    if project_version(&path).is_none() {
+        bail!("The project wasn't created. Last lines of the editor log:\n{}",
+            display::indent(&output.log_tail.join("\n")));
+    }
+
+    if let Template::Custom(template) = &template {
+        scaffold::apply_template(&path, template)?;
+    }
+
+    let tests = if seed_tests { scaffold::seed_tests(&path)? } else { vec![] };
+    let (errors, import) = smoke_report(run_editor(&editor, Some(&path), options, vec!["-quit"]))?;
+    Ok((errors, NewProject {
+        version: project_version(&path),
+        path,
+        template,
+        tests,
+        import,
+    }))
+}
+
+/// The editor version the project was last opened with,
+/// from `ProjectSettings/ProjectVersion.txt`.
+fn project_version(project_path: &Path) -> Option<String> {
+    let path = project_path.join("ProjectSettings/ProjectVersion.txt");
+    let contents = fs::read_to_string(path).ok()?;
+    contents.lines()
+        .find_map(|line| line.strip_prefix("m_EditorVersion:"))
+        .map(|version| version.trim().to_string())
+}
+
+/// Where unitool keeps its per-project state.
+pub(crate) fn state_dir(project_path: &Path) -> PathBuf {
+    project_path.join(".unitool")
 }
 
 /// Run Unity in headless mode with the provided commands.
-fn run_unity(project_path: &PathBuf, args: Vec<&str>) -> Result<HashSet<String>> {
-    let path = find_unity_path()?;
-    let mut cmd = Command::new(path);
-
-    cmd.args([vec![
-        "-batchmode",       // run headless
-        "-logfile", "-",    // log to stdout
-        "-projectPath", project_path.to_str().unwrap(),
-    ], args].concat());
-
-    let output = cmd.output()?;
-    let output = str::from_utf8(&output.stdout)?;
-    let errors: HashSet<String> = output.lines()
-        .filter(|line| line.contains("error CS"))
-        .map(|line| line.into())
+fn run_unity(project_path: &Path, options: &RunOptions, args: Vec<&str>) -> Result<CompileOutput> {
+    if options.docker.is_some() {
+        return run_editor(Path::new(docker::EDITOR), Some(project_path), options, args);
+    }
+    let path = find_unity_path(project_path, options.unity_path.as_deref())?;
+    run_editor(&path, Some(project_path), options, args)
+}
+
+/// Whether a run failed for a reason that often goes away on
+/// its own: a licensing hiccup, another editor briefly having
+/// the project open, or the package registry timing out.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<license::NoLicense>().is_some_and(|unlicensed| unlicensed.transient)
+        || err.downcast_ref::<FatalError>().is_some_and(|fatal| fatal.cause.is_transient())
+}
+
+/// Run a specific editor, retrying transient failures
+/// as many times as `options.retries` allows.
+fn run_editor(editor: &Path, project_path: Option<&Path>, options: &RunOptions, args: Vec<&str>) -> Result<CompileOutput> {
+    let mut attempt = 1;
+    loop {
+        match run_editor_once(editor, project_path, options, args.clone()) {
+            Ok(mut output) => {
+                output.attempts = attempt;
+                return Ok(output);
+            },
+            Err(error) if attempt <= options.retries && is_transient(&error) => {
+                let delay = options.retry_backoff * 2u32.saturating_pow(attempt - 1);
+                if let Some(on_retry) = &options.on_retry {
+                    on_retry(&Retry { attempt: attempt + 1, max_attempts: options.retries + 1, delay, error: &error });
+                }
+                thread::sleep(delay);
+                attempt += 1;
+            },
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Run a specific editor in headless mode with the provided commands.
+/// The log is read as it's output, rather than all at the end,
+/// so that it can be passed on to `options.on_log`.
+/// If the editor outlives `options.timeout`, it's killed
+/// and a [`TimeoutError`] is returned; if it crashes,
+/// an [`EditorCrashed`] is, and if it has no license,
+/// a [`license::NoLicense`]. Other failures the editor
+/// explains in its log are returned as a [`FatalError`].
+fn run_editor_once(editor: &Path, project_path: Option<&Path>, options: &RunOptions, args: Vec<&str>) -> Result<CompileOutput> {
+    // Mounted at the same path in a container, so needs to be absolute
+    let project = match (project_path, &options.docker) {
+        (Some(project_path), Some(_)) => Some(fs::canonicalize(project_path)?),
+        (project_path, _) => project_path.map(Path::to_path_buf),
+    };
+    let mut editor_args: Vec<std::ffi::OsString> = vec![
+        "-batchmode".into(),        // run headless
+        "-logfile".into(), "-".into(),  // log to stdout
+    ];
+    if let Some(project) = &project {
+        editor_args.push("-projectPath".into());
+        editor_args.push(project.into());
+    }
+    editor_args.extend(args.iter().map(|arg| arg.into()));
+    if options.nographics {
+        editor_args.push("-nographics".into());
+    }
+    if let Some(api) = options.graphics_api {
+        editor_args.push(api.arg().into());
+    }
+    // Also if it's passed on to the editor directly
+    let accept_api_update = options.accept_api_update
+        || options.extra_args.iter().any(|arg| arg == ACCEPT_API_UPDATE_ARG);
+    if options.accept_api_update {
+        editor_args.push(ACCEPT_API_UPDATE_ARG.into());
+    }
+    editor_args.extend(options.extra_args.iter().map(|arg| arg.into()));
+
+    let (mut cmd, container) = match &options.docker {
+        Some(image) => {
+            let (cmd, name) = docker::command(image, project.as_deref(), &editor_args)?;
+            (cmd, Some(name))
+        },
+        None => {
+            let mut cmd = Command::new(editor);
+            cmd.args(&editor_args);
+            (cmd, None)
+        },
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        // Docker explains failing to start the container there
+        .stderr(if container.is_some() { Stdio::piped() } else { Stdio::null() });
+    watchdog::isolate(&mut cmd);
+
+    let mut child = match &container {
+        Some(_) => cmd.spawn().map_err(|err| anyhow!("Couldn't run docker: {}", err))?,
+        None => cmd.spawn().map_err(|err| error::launch_failed(editor, err))?,
+    };
+    let watchdog = options.timeout
+        .map(|timeout| watchdog::Watchdog::start(child.id(), timeout));
+    let emit = |event: RunEvent| {
+        if let Some(on_event) = &options.on_event {
+            on_event(event);
+        }
+    };
+    emit(RunEvent::EditorStarted { pid: child.id() });
+    let docker_stderr = child.stderr.take().map(|mut stderr| thread::spawn(move || {
+        let mut out = String::new();
+        let _ = std::io::Read::read_to_string(&mut stderr, &mut out);
+        out
+    }));
+    let mut reader = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut output = String::new();
+    let mut classifier = log::Classifier::default();
+    let mut api_update: Option<String> = None;
+    // What the run was in if it hangs
+    let mut last_test: Option<String> = None;
+    let mut buf = vec![];
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        let line = log::sanitize(&String::from_utf8_lossy(&buf));
+        let line = line.trim_end_matches('\n');
+        // Stopped straight away, rather than left to stall
+        if !accept_api_update && api_update.is_none() && fatal::needs_api_update(line) {
+            api_update = Some(line.trim().to_string());
+            match &container {
+                Some(container) => docker::kill(container),
+                None => watchdog::kill_tree(child.id()),
+            }
+        }
+        if let Some(on_log) = &options.on_log {
+            if classifier.classify(line) != log::LineKind::Noise {
+                on_log(line);
+            }
+        }
+        if let Some(event) = events::parse(line) {
+            match &event {
+                RunEvent::TestStarted(name) => last_test = Some(name.clone()),
+                RunEvent::TestFinished { name, .. } if last_test.as_ref() == Some(name) => last_test = None,
+                _ => {},
+            }
+            emit(event);
+        }
+        output.push_str(line);
+        output.push('\n');
+        buf.clear();
+    }
+    // Stop the watchdog before reaping the child,
+    // so its pid can't have been reused when killed
+    let timed_out = watchdog.is_some_and(|watchdog| watchdog.stop());
+    let status = child.wait()?;
+    if let (true, Some(container)) = (timed_out, &container) {
+        docker::kill(container);
+    }
+    if let Some(stderr) = docker_stderr {
+        let stderr = stderr.join().unwrap_or_default();
+        if let Some(err) = docker::failed(&status, &stderr) {
+            return Err(err);
+        }
+    }
+    emit(RunEvent::Finished { exit_code: status.code() });
+
+    let (errors, warnings) = compiler::diagnostics(&output, &options.severities);
+
+    let log_path = match project_path {
+        Some(project_path) if options.keep_logs > 0 =>
+            logs::save(project_path, &output, options.keep_logs).ok(),
+        _ => None,
+    };
+    if let (Some(path), Some(on_log_saved)) = (&log_path, &options.on_log_saved) {
+        on_log_saved(path);
+    }
+
+    let lines: Vec<&str> = output.lines().collect();
+    let log_tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].iter()
+        .map(|line| line.to_string())
         .collect();
 
-    Ok(errors)
+    if timed_out {
+        return Err(TimeoutError {
+            timeout: options.timeout.unwrap_or_default(),
+            last_test,
+            log_tail,
+        }.into());
+    }
+    if let Some(message) = api_update {
+        return Err(FatalError { cause: FatalCause::ApiUpdateRequired(vec![message]), log_tail }.into());
+    }
+    if let Some(no_license) = license::detect(&status, &output, &log_tail) {
+        return Err(no_license.into());
+    }
+    if let Some(fatal) = fatal::detect(&status, &output, !errors.is_empty(), &log_tail) {
+        return Err(fatal.into());
+    }
+    if let Some(crashed) = crash::detect(&status, &output, &log_tail) {
+        return Err(crashed.into());
+    }
+
+    Ok(CompileOutput {
+        errors,
+        warnings,
+        exceptions: log::exceptions(&output),
+        shaders: shaders::diagnostics(&output),
+        exit_code: status.code(),
+        log_tail,
+        attempts: 1,
+        log_path,
+        log: output,
+    })
 }
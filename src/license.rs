@@ -0,0 +1,215 @@
+//! Activating and returning Unity licenses, e.g. on headless
+//! CI machines, and recognizing runs that failed for want of one.
+//! See: <https://docs.unity3d.com/Manual/ManagingYourUnityLicense.html>
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::{env, fs, fmt::Display, path::{Path, PathBuf}, process::ExitStatus, time::SystemTime};
+use crate::{CompileOutput, RunOptions, find_editor, run_editor, display::*};
+
+/// Log lines that mean the editor had no license to run with.
+const NO_LICENSE_MARKERS: &[&str] = &[
+    "No valid Unity Editor license found",
+    "No valid license",
+    "No ULF license found",
+    "License is not active",
+    "Failed to update license",
+    "Failed to activate/update license",
+];
+
+/// Log lines that mean the editor couldn't reach the licensing
+/// client (or its server), rather than that there's no license,
+/// which may pass on a retry.
+const HICCUP_MARKERS: &[&str] = &[
+    "Timed out waiting for the Licensing Client",
+    "Failed to connect to the Licensing Client",
+    "Licensing Client connection timed out",
+    "LicensingClient has failed validation",
+    "Failed to connect to local IPC",
+    "Unable to connect to the license server",
+];
+
+/// Where the editor keeps its activated license.
+#[cfg(target_os = "macos")]
+fn license_path() -> Option<PathBuf> {
+    Some("/Library/Application Support/Unity/Unity_lic.ulf".into())
+}
+#[cfg(target_os = "windows")]
+fn license_path() -> Option<PathBuf> {
+    Some("C:\\ProgramData\\Unity\\Unity_lic.ulf".into())
+}
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn license_path() -> Option<PathBuf> {
+    let home = PathBuf::from(env::var_os("HOME")?);
+    Some(home.join(".local/share/unity3d/Unity/Unity_lic.ulf"))
+}
+
+/// The editor couldn't run for want of a license.
+#[derive(Debug)]
+pub struct NoLicense {
+    /// Whether it's because of a licensing hiccup,
+    /// e.g. the licensing client timing out
+    pub transient: bool,
+
+    /// The last lines of the editor log
+    pub log_tail: Vec<String>,
+}
+impl Display for NoLicense {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = if self.transient {
+            vec![
+                red("Unity couldn't check its license with the licensing client.").to_string(),
+                "This is often temporary; retry with `--retries`.".to_string(),
+            ]
+        } else {
+            vec![
+                red("Unity couldn't find a valid license.").to_string(),
+                "Activate one with `unitool license activate`, or check `unitool license status`.".to_string(),
+            ]
+        };
+        if !self.log_tail.is_empty() {
+            lines.push(format!("Last {} lines of the editor log:", self.log_tail.len()));
+            lines.push(muted(&indent(&self.log_tail.join("\n"))).to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for NoLicense {}
+
+/// Check whether the editor failed because it had no license,
+/// or couldn't check it had one.
+pub(crate) fn detect(status: &ExitStatus, log: &str, log_tail: &[String]) -> Option<NoLicense> {
+    if status.success() {
+        return None;
+    }
+    let logged = |markers: &[&str]| log.lines()
+        .any(|line| markers.iter().any(|marker| line.contains(marker)));
+    let transient = logged(HICCUP_MARKERS);
+    if !transient && !logged(NO_LICENSE_MARKERS) {
+        return None;
+    }
+    Some(NoLicense {
+        transient,
+        log_tail: log_tail.to_vec(),
+    })
+}
+
+/// A Unity account, for serial activation and returning licenses.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Whether a license is activated on this machine.
+#[derive(Debug, Serialize)]
+pub struct LicenseStatus {
+    /// Where the license file is expected
+    pub path: Option<PathBuf>,
+
+    pub activated: bool,
+}
+impl Display for LicenseStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or("(unknown location)".to_string());
+        if self.activated {
+            write!(f, "{}\n{}", green("A license is activated"), muted(&path))
+        } else {
+            write!(f, "{}\n{}", red("No license is activated"), muted(&format!("No license file at {}", path)))
+        }
+    }
+}
+
+/// Check for an activated license. Only the license file is
+/// checked; it may still have expired or been revoked.
+pub fn status() -> LicenseStatus {
+    let path = license_path();
+    let activated = path.as_ref().is_some_and(|path| path.is_file());
+    LicenseStatus { path, activated }
+}
+
+/// Create a manual activation request (`.alf`) file, to upload
+/// at <https://license.unity3d.com/manual> for a license file.
+/// Unity writes it to the current directory; its path is returned.
+pub fn create_activation_file(project_path: Option<&Path>, options: &RunOptions) -> Result<PathBuf> {
+    let started = SystemTime::now();
+    let output = run(project_path, options, vec!["-createManualActivationFile"]);
+    check(output, "create an activation file")?;
+
+    let written = fs::read_dir(env::current_dir()?)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "alf"))
+        .find(|path| fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified >= started));
+    match written {
+        Some(path) => Ok(path),
+        None => bail!("Unity didn't write an activation file"),
+    }
+}
+
+/// Activate with a license file from manual activation.
+pub fn activate_with_file(project_path: Option<&Path>, options: &RunOptions, license_file: &Path) -> Result<()> {
+    let license_file = std::path::absolute(license_file)?;
+    let output = run(project_path, options, vec![
+        "-manualLicenseFile", &license_file.to_string_lossy(),
+        "-quit",
+    ]);
+    check(output, "activate the license")
+}
+
+/// Activate a Plus or Pro license by its serial.
+pub fn activate_with_serial(project_path: Option<&Path>, options: &RunOptions, serial: &str, credentials: &Credentials) -> Result<()> {
+    let output = run(project_path, options, vec![
+        "-serial", serial,
+        "-username", &credentials.username,
+        "-password", &credentials.password,
+        "-quit",
+    ]);
+    check(output, "activate the license")
+}
+
+/// Return a Plus or Pro license, freeing up its seat.
+pub fn return_license(project_path: Option<&Path>, options: &RunOptions, credentials: &Credentials) -> Result<()> {
+    let output = run(project_path, options, vec![
+        "-returnlicense",
+        "-username", &credentials.username,
+        "-password", &credentials.password,
+        "-quit",
+    ]);
+    check(output, "return the license")
+}
+
+/// Run the editor, outside of any project. The project's
+/// only used to pick the editor version, if given.
+fn run(project_path: Option<&Path>, options: &RunOptions, args: Vec<&str>) -> Result<CompileOutput> {
+    let editor = find_editor(project_path, options.unity_path.as_deref())?;
+    run_editor(&editor, None, options, args)
+}
+
+/// Fail if the editor didn't manage the licensing `action`.
+fn check(output: Result<CompileOutput>, action: &str) -> Result<()> {
+    let (exit_code, log_tail) = match output {
+        Ok(output) if output.exit_code == Some(0) => return Ok(()),
+        Ok(output) => (output.exit_code, output.log_tail),
+
+        // Expected when activation fails, but not worth
+        // suggesting activation for
+        Err(err) => match err.downcast::<NoLicense>() {
+            Ok(no_license) => (None, no_license.log_tail),
+            Err(err) => return Err(err),
+        },
+    };
+    let mut lines = vec![match exit_code {
+        Some(code) => format!("Unity failed to {} (exit code {}).", action, code),
+        None => format!("Unity failed to {}.", action),
+    }];
+    if !log_tail.is_empty() {
+        lines.push(format!("Last {} lines of the editor log:", log_tail.len()));
+        lines.push(indent(&log_tail.join("\n")));
+    }
+    bail!("{}", lines.join("\n"))
+}
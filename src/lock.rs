@@ -0,0 +1,138 @@
+//! Preventing overlapping runs on the same project, which
+//! corrupt its `Library/` and fail confusingly as Unity
+//! refuses to open a project that's already open.
+//!
+//! unitool's own runs are coordinated with a lock file in the
+//! project's state directory, holding the PID of its holder
+//! (as with license seats). Unity's own `Temp/UnityLockfile`
+//! is also respected, for when the editor has the project open.
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use std::{fs, path::{Path, PathBuf}, thread, time::Duration};
+use crate::{state_dir, seats::{claim, is_stale}};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What to do if the project is already in use.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum LockMode {
+    /// Wait for the other run to finish
+    Wait,
+
+    /// Fail straight away
+    #[default]
+    Fail,
+
+    /// Ignore the lock
+    Force,
+}
+
+/// What's holding the project.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Holder {
+    /// Another unitool run, by PID
+    Unitool(u32),
+
+    /// An editor, most likely with the project open in the GUI
+    Editor,
+}
+impl std::fmt::Display for Holder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Holder::Unitool(pid) => write!(f, "another unitool run (pid {})", pid),
+            Holder::Editor => write!(f, "an open Unity editor"),
+        }
+    }
+}
+
+/// A held project lock, released on drop.
+#[derive(Debug)]
+pub struct ProjectLock {
+    path: PathBuf,
+}
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Lock the project for a run. `on_wait` is called with
+/// what's holding it each time it's found to be in use.
+pub fn lock_project(project_path: &Path, mode: LockMode, mut on_wait: impl FnMut(&Holder)) -> Result<ProjectLock> {
+    let path = state_dir(project_path).join("run.lock");
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if mode == LockMode::Force {
+        let _ = fs::remove_file(&path);
+    }
+
+    loop {
+        let holder = if claim(&path)? {
+            let lock = ProjectLock { path: path.clone() };
+
+            // Checked after taking the lock, as another unitool
+            // run's editor would also hold Unity's lock file
+            if mode == LockMode::Force || !editor_has_project(project_path) {
+                return Ok(lock);
+            }
+            Holder::Editor
+        } else if is_stale(&path) {
+            // Being replaced by another run
+            continue;
+        } else {
+            let pid = fs::read_to_string(&path).ok()
+                .and_then(|pid| pid.trim().parse().ok())
+                .unwrap_or(0);
+            Holder::Unitool(pid)
+        };
+
+        match mode {
+            LockMode::Fail => match holder {
+                Holder::Editor => bail!("The project is in use by {}. Close it, or pass `--lock wait` to wait for it.", holder),
+                Holder::Unitool(_) => bail!("The project is in use by {}. Pass `--lock wait` to wait for it to finish.", holder),
+            },
+            _ => {
+                on_wait(&holder);
+                thread::sleep(POLL_INTERVAL);
+            },
+        }
+    }
+}
+
+/// Whether Unity has the project open, i.e. some process has
+/// its lock file open. Unity deletes the file on exit, but one
+/// can be left over from a crash, so its existence isn't enough.
+pub(crate) fn editor_has_project(project_path: &Path) -> bool {
+    let path = project_path.join("Temp/UnityLockfile");
+    match path.canonicalize() {
+        Ok(path) => is_open(&path),
+        Err(_) => false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_open(path: &Path) -> bool {
+    let Ok(procs) = fs::read_dir("/proc") else { return true };
+    procs.flatten()
+        .filter_map(|proc| fs::read_dir(proc.path().join("fd")).ok())
+        .flat_map(|fds| fds.flatten())
+        .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == path))
+}
+
+#[cfg(target_os = "macos")]
+fn is_open(path: &Path) -> bool {
+    std::process::Command::new("lsof")
+        .arg("-t")
+        .arg(path)
+        .output()
+        .map(|out| !out.stdout.is_empty())
+        .unwrap_or(true)
+}
+
+/// Unity holds the file open without sharing.
+#[cfg(windows)]
+fn is_open(path: &Path) -> bool {
+    fs::OpenOptions::new().write(true).open(path).is_err()
+}
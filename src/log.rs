@@ -0,0 +1,235 @@
+//! Handling the editor log: cleaning it up, and classifying
+//! its lines so the noise can be skipped, and errors and
+//! exceptions picked out.
+
+use serde::Serialize;
+use crate::{compiler, shaders};
+
+/// Clean up raw log output so it can be matched line-by-line:
+/// ANSI escape sequences are removed, and carriage-return
+/// progress redraws are collapsed into their final state.
+pub fn sanitize(text: &str) -> String {
+    let text = strip_escapes(text);
+    text.split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+
+            // Each `\r` returns to the start of the line, so only
+            // the last (non-empty) redraw would remain on screen
+            line.rsplit('\r')
+                .find(|redraw| !redraw.is_empty())
+                .unwrap_or("")
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Log lines which are rarely of interest, by prefix.
+const NOISE: &[&str] = &[
+    "[Licensing::",
+    "[Package Manager]",
+    "[Subsystems]",
+    "[Physics::Module]",
+    "Licensing::",
+    "Start importing ",
+    "Refreshing native plugins",
+    "Preloading ",
+    "Registering precompiled",
+    "Registered in ",
+    "UnloadTime:",
+    "Compiling shader ",
+    "Shader warmup",
+    "Unloading ",
+    "Total: ",
+    "System memory in use",
+    "Mono path[",
+    "Mono config path",
+    "[MODES]",
+    "[Worker",
+    "Asset Pipeline Refresh (id=",
+    "AcceleratorClientConnectionCallback",
+];
+
+/// Log lines marking the editor's progress, by prefix.
+const PROGRESS: &[&str] = &[
+    "Refresh completed in ",
+    "Reloading assemblies",
+    "Begin MonoManager ReloadAssembly",
+    "- Finished resetting",
+    "Starting: ",
+    "Executing ",
+    "Building ",
+    "Compilation ",
+    "Resolving packages",
+    "Exiting batchmode",
+];
+
+/// How many frames of an exception's stack trace to keep.
+const MAX_FRAMES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Error,
+    Warning,
+
+    /// A managed exception, e.g. `NullReferenceException: ...`
+    Exception,
+
+    /// A frame of the stack trace after an error or exception
+    StackTrace,
+
+    /// The editor getting on with things, e.g. reloading assemblies
+    Progress,
+
+    /// Not worth showing, e.g. licensing chatter or blank lines
+    Noise,
+
+    Info,
+}
+
+/// Classifies log lines in order, as stack traces
+/// can only be told apart by what they follow.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    in_trace: bool,
+}
+impl Classifier {
+    pub fn classify(&mut self, line: &str) -> LineKind {
+        let trimmed = line.trim();
+        if self.in_trace && is_frame(trimmed) {
+            return LineKind::StackTrace;
+        }
+        let diagnostic = compiler::diagnostic(line).map(|(severity, _)| severity)
+            .or_else(|| shaders::severity(line));
+        let kind = if is_noise(line) {
+            LineKind::Noise
+        } else if exception_type(trimmed).is_some() {
+            LineKind::Exception
+        } else if diagnostic == Some("error") || starts_with_any(trimmed, &["Error:", "[Error]", "error:"]) {
+            LineKind::Error
+        } else if diagnostic == Some("warning") || starts_with_any(trimmed, &["Warning:", "[Warning]", "warning:"]) {
+            LineKind::Warning
+        } else if starts_with_any(trimmed, PROGRESS) {
+            LineKind::Progress
+        } else {
+            LineKind::Info
+        };
+        self.in_trace = matches!(kind, LineKind::Exception | LineKind::Error);
+        kind
+    }
+}
+
+/// Whether a log line is noise, e.g. licensing chatter
+/// or blank lines, and not worth showing as-is.
+pub fn is_noise(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || starts_with_any(line, NOISE)
+}
+
+fn starts_with_any(line: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// The exception's type, if the line starts one,
+/// e.g. `NullReferenceException: Object reference...`
+fn exception_type(line: &str) -> Option<&str> {
+    let (name, _) = line.split_once(':')?;
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '_');
+    (is_name && name.ends_with("Exception")).then_some(name)
+}
+
+/// Whether a line is a stack frame, either .NET's
+/// `at Foo.Bar () [0x00001] in ...` or Unity's
+/// `Foo.Bar:Baz () (at Assets/Foo.cs:12)`.
+fn is_frame(line: &str) -> bool {
+    if line.starts_with("at ") || line.contains("(at ") || line.starts_with("--- End of") {
+        return true;
+    }
+    // `Namespace.Class:Method (args)`, with no spaces before the args
+    let head = line.split('(').next().unwrap_or(line).trim_end();
+    line.contains('(') && head.contains(':') && !head.contains(' ')
+}
+
+/// A managed exception logged by the editor,
+/// e.g. thrown by an import or editor script.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogException {
+    /// e.g. `NullReferenceException`
+    pub kind: String,
+
+    pub message: String,
+    pub stack_trace: Vec<String>,
+}
+
+/// The exceptions in the log, without repeats
+/// (the editor often logs them more than once).
+pub fn exceptions(log: &str) -> Vec<LogException> {
+    let mut classifier = Classifier::default();
+    let mut exceptions: Vec<LogException> = vec![];
+    let mut current: Option<LogException> = None;
+    for line in log.lines() {
+        let kind = classifier.classify(line);
+        if kind == LineKind::StackTrace {
+            if let Some(exception) = &mut current {
+                if exception.stack_trace.len() < MAX_FRAMES {
+                    exception.stack_trace.push(line.trim().to_string());
+                }
+            }
+            continue;
+        }
+        exceptions.extend(current.take());
+        if kind == LineKind::Exception {
+            let line = line.trim();
+            let kind = exception_type(line).unwrap_or_default();
+            current = Some(LogException {
+                kind: kind.to_string(),
+                message: line[kind.len() + 1..].trim().to_string(),
+                stack_trace: vec![],
+            });
+        }
+    }
+    exceptions.extend(current);
+
+    let mut unique: Vec<LogException> = vec![];
+    for exception in exceptions {
+        if !unique.contains(&exception) {
+            unique.push(exception);
+        }
+    }
+    unique
+}
+
+/// Remove ANSI escape sequences (CSI, OSC, and two-character escapes).
+fn strip_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters until a final byte in `@`..=`~`
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) { break }
+                }
+            },
+
+            // OSC: until BEL or the string terminator `ESC \`
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' { break }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            },
+
+            // Everything else is a single character
+            _ => continue,
+        }
+    }
+    out
+}
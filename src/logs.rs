@@ -0,0 +1,105 @@
+//! The full editor log of each run, kept in `.unitool/logs`,
+//! for digging into failures that the tail of the log (all
+//! that's shown by default) doesn't explain.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt::Display, fs, io::ErrorKind, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use crate::{state_dir, build::format_size, display::*, history::format_time};
+
+/// How many logs are kept by default.
+pub const DEFAULT_KEEP: usize = 20;
+
+fn logs_dir(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("logs")
+}
+
+/// Save a run's log, named by when it was saved
+/// (e.g. `2024-03-09_14-05-21.123.log`), removing
+/// the oldest so only `keep` are left.
+pub(crate) fn save(project_path: &Path, log: &str, keep: usize) -> Result<PathBuf> {
+    let dir = logs_dir(project_path);
+    fs::create_dir_all(&dir)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let stamp = format!("{}-{:02}.{:03}",
+        format_time(now.as_secs()).replace(' ', "_").replace(':', "-"),
+        now.as_secs() % 60, now.subsec_millis());
+
+    // Runs in parallel could finish in the same millisecond
+    let mut path = dir.join(format!("{}.log", stamp));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = dir.join(format!("{}-{}.log", stamp, n));
+    }
+    fs::write(&path, log)?;
+
+    let logs = log_paths(project_path)?;
+    for old in &logs[..logs.len().saturating_sub(keep)] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// The saved logs' paths, oldest first.
+fn log_paths(project_path: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(logs_dir(project_path)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedLog {
+    pub path: PathBuf,
+
+    /// In bytes
+    pub size: u64,
+
+    /// When it was written, as a Unix time
+    pub time: u64,
+}
+
+/// The project's saved logs, oldest first.
+pub fn list(project_path: &Path) -> Result<Vec<SavedLog>> {
+    log_paths(project_path)?.into_iter()
+        .map(|path| {
+            let meta = fs::metadata(&path)?;
+            let time = meta.modified()?.duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0);
+            Ok(SavedLog { path, size: meta.len(), time })
+        })
+        .collect()
+}
+
+/// The last `lines` lines of a log.
+pub fn tail(path: &Path, lines: usize) -> Result<Vec<String>> {
+    let log = fs::read_to_string(path)?;
+    let all: Vec<&str> = log.lines().collect();
+    Ok(all[all.len().saturating_sub(lines)..].iter().map(|line| line.to_string()).collect())
+}
+
+/// A list of saved logs, for display.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct SavedLogs(pub Vec<SavedLog>);
+impl Display for SavedLogs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "{}", muted("No logs saved yet"));
+        }
+        let lines: Vec<String> = self.0.iter().map(|log| {
+            format!("{}  {:>8}  {}",
+                muted(&format_time(log.time)),
+                format_size(log.size),
+                log.path.display())
+        }).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
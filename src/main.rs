@@ -1,12 +1,265 @@
-use indicatif::{ProgressBar, ProgressStyle};
-use std::{path::PathBuf, time::Duration};
-use clap::{Parser, Subcommand, ValueHint};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex, OnceLock}, time::{Duration, Instant}};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use unitool::{ExitCode, config::{Config, TestConfig}, display::{ColorMode, set_color_mode, green, red, yellow, muted, human_duration}};
+
+/// In seconds.
+const DEFAULT_RETRY_BACKOFF: u64 = 10;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about)]
+#[clap(author, version, about,
+    after_help = "Arguments after `--` are passed on to the editor, e.g.\n  unitool compile path/to/project -- -nographics -disable-assembly-updater")]
 struct Args {
     #[clap(subcommand)]
     cmd: SubCommand,
+
+    #[clap(flatten)]
+    global: GlobalArgs,
+}
+
+/// Options shared by all subcommands.
+/// Those left unset fall back to the config file.
+#[derive(clap::Args, Debug, Clone)]
+struct GlobalArgs {
+    /// The Unity editor binary to use
+    /// (also settable with `UNITY_PATH`)
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    unity_path: Option<PathBuf>,
+
+    /// Show the editor log as it's output
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// How to print results [default: text]
+    #[arg(long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// When to color output [default: auto]
+    #[arg(long, global = true, value_enum)]
+    color: Option<ColorMode>,
+
+    /// Kill the editor if it runs longer than this many seconds
+    #[arg(long, global = true)]
+    timeout: Option<u64>,
+
+    /// Run the editor without graphics
+    /// [default: when there's no display]
+    #[arg(long, global = true, conflicts_with = "graphics")]
+    nographics: bool,
+
+    /// Run the editor with graphics, even without a display
+    #[arg(long, global = true)]
+    graphics: bool,
+
+    /// Force the editor to render with OpenGL (core)
+    #[arg(long, global = true, conflicts_with_all = ["nographics", "force_vulkan"])]
+    force_opengl: bool,
+
+    /// Force the editor to render with Vulkan
+    #[arg(long, global = true, conflicts_with = "nographics")]
+    force_vulkan: bool,
+
+    /// Retry runs that fail for transient reasons (licensing hiccups,
+    /// package registry timeouts, another editor closing) this many times
+    #[arg(long, global = true)]
+    retries: Option<u32>,
+
+    /// Seconds to wait before the first retry, doubling
+    /// for each one after [default: 10]
+    #[arg(long, global = true)]
+    retry_backoff: Option<u64>,
+
+    /// Let Unity's API updater update scripts that use obsolete APIs
+    /// (`-accept-apiupdate`), rather than stopping the run if it wants to
+    #[arg(long, global = true)]
+    accept_api_update: bool,
+
+    /// How many full editor logs to keep in the project's
+    /// `.unitool/logs`, 0 to not save them [default: 20]
+    #[arg(long, global = true)]
+    keep_logs: Option<usize>,
+
+    /// Run the editor in a Docker container, from the image
+    /// [default: GameCI's image for the project's version]
+    #[arg(long, global = true, value_name = "IMAGE", num_args = 0..=1, default_missing_value = "")]
+    docker: Option<String>,
+
+    /// Post the outcome of `compile` and `test` runs to this
+    /// webhook (as a Slack message for Slack webhooks)
+    #[arg(long, global = true, value_name = "URL")]
+    notify: Option<String>,
+
+    /// Show the outcome of `compile` and `test`
+    /// runs as a desktop notification
+    #[arg(long, global = true)]
+    notify_desktop: bool,
+
+    /// Lines of source to show either side of compile
+    /// errors and test failures, 0 for none [default: 2]
+    #[arg(long, global = true, value_name = "N")]
+    context: Option<usize>,
+
+    /// Explain common compile errors, with hints for fixing them
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Open the first compile error or failed test in your
+    /// editor after a failed run, or ask to [default: never]
+    #[arg(long, global = true, value_enum, value_name = "WHEN", num_args = 0..=1, default_missing_value = "always")]
+    open_editor: Option<unitool::open::OpenEditor>,
+
+    /// What to do if another run (or an open editor)
+    /// is using the project [default: fail]
+    #[arg(long, global = true, value_enum)]
+    lock: Option<unitool::lock::LockMode>,
+
+    /// The config file to use, instead of
+    /// the project's `unitool.toml`
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    config: Option<PathBuf>,
+
+    /// From `UNITOOL_LICENSE_SEATS` or the config
+    #[arg(skip)]
+    license_seats: Option<usize>,
+
+    /// The command to open failures with, from the config
+    #[arg(skip)]
+    editor_command: Option<String>,
+
+    /// Severity overrides by diagnostic code, from the config
+    #[arg(skip)]
+    severities: unitool::compiler::SeverityOverrides,
+
+    /// Anything after `--`, passed on to the editor
+    #[arg(skip)]
+    editor_args: Vec<String>,
+
+    /// Where this command's last run saved its full log
+    #[arg(skip)]
+    saved_log: Arc<Mutex<Option<PathBuf>>>,
+}
+impl GlobalArgs {
+    /// Fill in anything not given on the command line
+    /// (or by environment variables) from the config.
+    fn apply(&mut self, config: &Config) -> anyhow::Result<()> {
+        if self.unity_path.is_none() && std::env::var_os(unitool::UNITY_PATH_VAR).is_none() {
+            self.unity_path = config.unity_path.clone();
+        }
+        if self.output.is_none() {
+            if let Some(name) = &config.output {
+                self.output = Some(<OutputFormat as clap::ValueEnum>::from_str(name, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown output format `{}` in the config", name))?);
+            }
+        }
+        if self.color.is_none() {
+            if let Some(name) = &config.color {
+                self.color = Some(<ColorMode as clap::ValueEnum>::from_str(name, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown color mode `{}` in the config", name))?);
+            }
+        }
+        if self.open_editor.is_none() {
+            if let Some(name) = &config.open_editor {
+                self.open_editor = Some(<unitool::open::OpenEditor as clap::ValueEnum>::from_str(name, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown `open_editor` value `{}` in the config", name))?);
+            }
+        }
+        if self.lock.is_none() {
+            if let Some(name) = &config.lock {
+                self.lock = Some(<unitool::lock::LockMode as clap::ValueEnum>::from_str(name, true)
+                    .map_err(|_| anyhow::anyhow!("Unknown lock mode `{}` in the config", name))?);
+            }
+        }
+        self.verbose |= config.verbose.unwrap_or(false);
+        self.notify_desktop |= config.notify_desktop.unwrap_or(false);
+        self.explain |= config.explain.unwrap_or(false);
+        self.accept_api_update |= config.accept_api_update.unwrap_or(false);
+        self.timeout = self.timeout.or(config.timeout);
+        self.retries = self.retries.or(config.retries);
+        self.retry_backoff = self.retry_backoff.or(config.retry_backoff);
+        self.keep_logs = self.keep_logs.or(config.keep_logs);
+        self.context = self.context.or(config.context);
+        if self.docker.is_none() {
+            self.docker = config.docker.clone();
+        }
+        if self.notify.is_none() {
+            self.notify = config.notify.clone();
+        }
+        self.license_seats = unitool::seats::seats_from_env().or(config.license_seats);
+        self.editor_command = config.editor_command.clone();
+        self.severities = config.diagnostics.clone();
+        Ok(())
+    }
+
+    fn format(&self) -> OutputFormat {
+        self.output.unwrap_or(OutputFormat::Text)
+    }
+
+    fn docker(&self) -> Option<unitool::docker::Image> {
+        use unitool::docker::Image;
+        self.docker.as_ref().map(|image| match image.as_str() {
+            "" => Image::ForProject,
+            name => Image::Named(name.to_string()),
+        })
+    }
+
+    fn context(&self) -> usize {
+        self.context.unwrap_or(unitool::source::DEFAULT_CONTEXT)
+    }
+
+    fn retry_backoff(&self) -> Duration {
+        Duration::from_secs(self.retry_backoff.unwrap_or(DEFAULT_RETRY_BACKOFF))
+    }
+
+    fn graphics_api(&self) -> Option<unitool::GraphicsApi> {
+        match (self.force_opengl, self.force_vulkan) {
+            (true, _) => Some(unitool::GraphicsApi::OpenGl),
+            (_, true) => Some(unitool::GraphicsApi::Vulkan),
+            _ => None,
+        }
+    }
+
+    /// Forcing graphics on (or a graphics API)
+    /// overrides the default for headless machines.
+    /// GameCI images run the editor under `xvfb-run`, so have a display.
+    fn nographics(&self) -> bool {
+        self.nographics
+            || (!self.graphics && self.graphics_api().is_none() && self.docker.is_none() && !unitool::has_display())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Colored, human-readable output
+    Text,
+
+    /// A single JSON object, for scripts
+    Json,
+
+    /// Text, plus GitHub Actions annotations
+    /// for compile errors and failing tests
+    Github,
+
+    /// A Markdown summary, e.g. for PR comments
+    /// (for `compile`, `test` and `report`)
+    Markdown,
+
+    /// TAP 13, for TAP consumers like `prove`
+    /// (for `test` and `report`)
+    Tap,
+
+    /// Text, plus TeamCity service messages for
+    /// tests, compile errors and the run's progress
+    #[value(name = "teamcity")]
+    TeamCity,
+}
+impl OutputFormat {
+    /// Whether the CI's annotations are printed along with the text.
+    fn annotates(&self) -> bool {
+        matches!(self, OutputFormat::Github | OutputFormat::TeamCity)
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -16,6 +269,25 @@ enum SubCommand {
         /// The root path of the Unity project
         #[clap(value_hint = ValueHint::FilePath)]
         project_path: PathBuf,
+
+        /// Fail if any count goes up relative to
+        /// the recorded baseline (recording one if needed)
+        #[arg(long, value_enum)]
+        ratchet: Option<unitool::ratchet::Ratchet>,
+
+        /// Display compiler warnings
+        #[arg(long)]
+        warnings: bool,
+
+        /// Fail if there are any compiler warnings
+        #[arg(long)]
+        warnings_as_errors: bool,
+
+        /// Return the last compile's results straight away if no
+        /// scripts, assembly definitions, packages or settings have
+        /// changed since, rather than launching the editor
+        #[arg(long)]
+        cached: bool,
     },
 
     /// Compile the project and run tests
@@ -25,56 +297,3031 @@ enum SubCommand {
         project_path: PathBuf,
 
         /// Which set of tests to run
+        /// (required unless set in the config)
+        #[arg(short, value_enum)]
+        mode: Option<unitool::TestMode>,
+
+        #[clap(flatten)]
+        selection: TestSelection,
+
+        #[clap(flatten)]
+        output: ResultsOutput,
+
+        /// Record code coverage and print a summary per assembly
+        /// (requires the Code Coverage package)
+        #[arg(long)]
+        coverage: bool,
+
+        /// Keep the raw results XML at this path
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        results_out: Option<PathBuf>,
+
+        /// Run play mode tests in a player built for this
+        /// platform, rather than in the editor
+        #[arg(long, value_enum)]
+        platform: Option<unitool::build::BuildTarget>,
+
+        /// Only run the tests that failed in the last run
+        #[arg(long, conflicts_with = "filters")]
+        failed: bool,
+
+        /// Pick the tests to run from a list, fuzzy-finding by name
+        #[arg(short, long, conflicts_with_all = ["filters", "failed"])]
+        interactive: bool,
+
+        /// Run just one shard of the tests, e.g. `2/5` for the second
+        /// of five, to split the suite across CI machines
+        #[arg(long, conflicts_with_all = ["filters", "failed", "interactive"])]
+        shard: Option<unitool::shard::Shard>,
+
+        /// Run the tests N times and report any flaky tests
+        /// (just those, so not with options for a run's results)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..),
+            conflicts_with_all = ["coverage", "results_out", "junit", "html", "artifacts_dir", "sort", "slowest",
+                "quiet", "show_skipped", "full_trace", "order_file", "failed_first", "update_baseline"])]
+        repeat: Option<u32>,
+
+        /// With `--repeat`, run the tests in a different
+        /// random order each time
+        #[arg(long, requires = "repeat")]
+        random_order: bool,
+
+        /// Run the tests in the order listed in the file,
+        /// one full test name per line
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "shuffle")]
+        order_file: Option<PathBuf>,
+
+        /// Run the tests that failed in the last run first,
+        /// then the rest
+        #[arg(long, conflicts_with_all = ["failed", "order_file", "shuffle"])]
+        failed_first: bool,
+
+        /// Run the tests in a random order, from the seed if
+        /// given (to repeat an order) [default: a random seed]
+        #[arg(long, value_name = "SEED", conflicts_with = "repeat")]
+        shuffle: Option<Option<u32>>,
+
+        /// With `--shuffle`, if a test fails that passes on its own,
+        /// bisect the tests that ran before it for the one it fails after
+        #[arg(long, requires = "shuffle", conflicts_with_all = ["coverage", "update_baseline"])]
+        bisect_order: bool,
+
+        /// Add this run's failures to the known failures in
+        /// `.unitool/baseline.toml`, so they don't fail later runs,
+        /// and drop those that passed (tests that didn't run are kept)
+        #[arg(long, conflicts_with = "repeat")]
+        update_baseline: bool,
+    },
+
+    /// Build a player
+    Build {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to build for
+        #[arg(short, long, value_enum)]
+        target: unitool::build::BuildTarget,
+
+        /// Where to write the build (`--output <path>`
+        /// also works, as it did before output formats)
+        #[arg(short = 'o', long = "out", value_hint = ValueHint::FilePath)]
+        out: PathBuf,
+
+        /// Make a development build
+        #[arg(long)]
+        development: bool,
+
+        /// Build with this scripting backend rather than the
+        /// project's, e.g. to check the code builds with IL2CPP
+        #[arg(long, value_enum)]
+        scripting_backend: Option<unitool::build::ScriptingBackend>,
+
+        /// Strip managed code at this level rather than the project's
+        #[arg(long, value_enum)]
+        strip_level: Option<unitool::build::StripLevel>,
+
+        /// List the N largest assets in the build
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        largest: usize,
+
+        /// Fail if the whole build is larger than this many megabytes
+        #[arg(long, value_name = "MB")]
+        size_budget: Option<f64>,
+    },
+
+    /// Check the project's jobs compile with Burst, which only
+    /// happens ahead of time when building a player; a throwaway
+    /// player is built to find out (needs the Burst package)
+    BurstCheck {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to build for [default: this one]
+        #[arg(short, long, value_enum)]
+        target: Option<unitool::build::BuildTarget>,
+    },
+
+    /// Build the project's AssetBundles
+    Bundles {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to build for
+        #[arg(short, long, value_enum)]
+        target: unitool::build::BuildTarget,
+
+        /// Where to write the bundles
+        /// [default: <project>/AssetBundles/<target>]
+        #[arg(short = 'o', long = "out", value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+
+    /// Build Addressables content (needs the Addressables package)
+    Addressables {
+        #[clap(subcommand)]
+        cmd: AddressablesCommand,
+    },
+
+    /// Export or import `.unitypackage` files
+    Package {
+        #[clap(subcommand)]
+        cmd: PackageCommand,
+    },
+
+    /// Recompile (or re-run tests) whenever scripts or packages change
+    Watch {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Run the tests rather than just compiling
+        #[arg(long)]
+        test: bool,
+
+        /// Which set of tests to run, with `--test`
+        /// [default: edit-mode]
         #[arg(short, value_enum)]
+        mode: Option<unitool::TestMode>,
+
+        #[clap(flatten)]
+        selection: TestSelection,
+    },
+
+    /// Serve compile and test requests as line-delimited
+    /// JSON over a local socket, e.g. for editor plugins
+    Serve {
+        /// The port to listen on (on localhost)
+        #[arg(long, default_value_t = unitool::server::DEFAULT_PORT)]
+        port: u16,
+    },
+
+    /// Serve compile, test and report tools to coding
+    /// assistants, as a Model Context Protocol server on stdio
+    Mcp,
+
+    /// Run a static method in the editor, e.g. a custom build step
+    Exec {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The method to run, e.g. `Builds.BundleAssets`
+        method: String,
+
+        /// Arguments to pass on to the method
+        /// (everything after `--args`)
+        #[arg(long, num_args = 1.., allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// List the tests without running them
+    ListTests {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Which set of tests to list
+        #[arg(short, value_enum, default_value = "all")]
         mode: unitool::TestMode,
+    },
+
+    /// Create a project, and check it opens cleanly
+    New {
+        /// Where to create the project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The editor version, e.g. `2022.3.10f1`, or `2022.3.x`
+        /// for the newest installed 2022.3 [default: the newest installed]
+        #[arg(long)]
+        version: Option<String>,
+
+        /// The template to create it from: `3d`, `2d`, `urp`, `hdrp`
+        /// or `vr`, or the path to your own (a template package's `.tgz`
+        /// or directory, or a project), to copy its `Packages/manifest.json`
+        /// and `ProjectSettings` from
+        #[arg(long, default_value = "3d")]
+        template: unitool::scaffold::Template,
+
+        /// Add `EditTests` and `PlayTests` test assemblies, under `Assets/Tests`
+        #[arg(long)]
+        tests: bool,
+    },
+
+    /// Open the project in the editor
+    Open {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// A scene to open, e.g. `Assets/Scenes/Main.unity`
+        #[arg(long)]
+        scene: Option<String>,
+    },
+
+    /// Stop editors left running with the project open (e.g. by an
+    /// interrupted run) and remove stale lock files
+    Cleanup {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+    },
+
+    /// Compile (reusing the last compile if nothing's changed) and
+    /// optionally run edit mode tests, quietly, for git hooks: prints
+    /// only what failed and a one-line summary
+    Check {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Also run the edit mode tests
+        #[arg(long)]
+        tests: bool,
+    },
+
+    /// Open the project and check it does so cleanly: that it compiles,
+    /// imports without exceptions, resolves its packages, and needs
+    /// neither the API updater nor serialized data upgraded
+    Smoke {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+    },
+
+    /// Compile or test every project in a workspace
+    /// (listed in a `unitool.workspace.toml`)
+    Workspace {
+        #[clap(subcommand)]
+        cmd: WorkspaceCommand,
+    },
+
+    /// Install or remove a git hook which runs `unitool check`
+    Hook {
+        #[clap(subcommand)]
+        cmd: HookCommand,
+    },
+
+    /// Check scenes, prefabs and other assets for missing scripts and
+    /// broken references, and find orphaned meta files and duplicate
+    /// GUIDs, without the editor
+    Validate {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Give each duplicate `.meta` file (all but the oldest
+        /// with the GUID) a new GUID
+        #[arg(long)]
+        regenerate_guids: bool,
+    },
+
+    /// List the project's assemblies, and which hold
+    /// edit mode and play mode tests
+    ListAssemblies {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+    },
+
+    /// Check the editors, license, build support, disk space, display
+    /// and lock files, with hints for fixing anything that's wrong
+    Doctor {
+        /// The project to check the editor, disk space and locks for
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: Option<PathBuf>,
+
+        /// Check the project's editor can build for these platforms
+        #[arg(short, long, value_enum)]
+        target: Vec<unitool::build::BuildTarget>,
+    },
+
+    /// Explain a compile error code (e.g. `CS0246`),
+    /// with hints for fixing it in a Unity project
+    Explain {
+        code: String,
+    },
+
+    /// List the installed editors, marking the one a project would use
+    ListEditors {
+        /// The project to show the selected editor for
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: Option<PathBuf>,
+    },
+
+    /// Rank namespaces or classes by how often their tests fail
+    Hotspots {
+        /// Test results files from past runs, oldest first
+        #[clap(value_hint = ValueHint::FilePath, required = true)]
+        results: Vec<PathBuf>,
 
-        /// Optional `;`-delimited filters
-        #[arg(short)]
-        filters: Option<String>,
+        /// How to group the failing tests
+        #[arg(long, value_enum, default_value="namespace")]
+        by: unitool::hotspots::GroupBy,
+    },
+
+    /// List the project's past compile and test runs
+    History {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// How many of the most recent runs to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// List the full editor logs saved from recent runs,
+    /// or show the end of the latest
+    Logs {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// How many of the most recent logs to list
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+
+        /// Print the last lines of the latest log instead
+        #[arg(long, value_name = "LINES", num_args = 0..=1, default_missing_value = "50")]
+        tail: Option<usize>,
+    },
+
+    /// Show each test's pass rate and duration trend over past runs
+    Trends {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// How many of the most recent test runs to look at
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+
+        /// Only tests whose full names contain this
+        #[arg(short, long)]
+        filter: Option<String>,
+    },
+
+    /// Compare two test runs: which tests newly fail,
+    /// newly pass, or were added or removed
+    Diff {
+        /// The old and new results files, or with `--against-last`
+        /// just the new one (or none, to compare the last two runs)
+        #[clap(value_hint = ValueHint::FilePath, num_args = 0..=2)]
+        results: Vec<PathBuf>,
+
+        /// Compare against the project's last recorded test run
+        #[arg(long, value_name = "PROJECT_PATH", value_hint = ValueHint::FilePath)]
+        against_last: Option<PathBuf>,
+    },
+
+    /// Show an existing test results file, or work with several
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Report {
+        /// The test results file to show
+        #[clap(value_hint = ValueHint::FilePath, required = true)]
+        results: Option<PathBuf>,
+
+        #[clap(flatten)]
+        output: ResultsOutput,
+
+        #[clap(subcommand)]
+        cmd: Option<ReportCommand>,
+    },
+
+    /// Merge results files, e.g. from `test --shard` runs, into one
+    /// summary (the same as `report merge`)
+    MergeResults {
+        /// The results files to merge
+        #[clap(value_hint = ValueHint::FilePath, required = true)]
+        results: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        output: ResultsOutput,
+    },
+
+    /// Manage the project's packages, without opening the editor
+    Pkg {
+        #[clap(subcommand)]
+        cmd: PkgCommand,
+    },
+
+    /// Manage editor build support modules via Unity Hub
+    Modules {
+        #[clap(subcommand)]
+        cmd: ModulesCommand,
+    },
+
+    /// Activate or return the editor's license, e.g. on CI machines
+    License {
+        #[clap(subcommand)]
+        cmd: LicenseCommand,
 
-        /// The assemblies to include
-        #[arg(short, default_value="EditTests;PlayTests")]
-        assemblies: String,
+        /// A project whose editor version to use
+        /// [default: the most recent installed]
+        #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+        project: Option<PathBuf>,
     },
+
+    /// Print a shell completion script, e.g.
+    /// `unitool completions bash > /etc/bash_completion.d/unitool`
+    Completions {
+        #[arg(value_enum)]
+        shell: unitool::completions::Shell,
+    },
+
+    /// List the installed editor versions, for completions
+    #[command(name = unitool::completions::VERSIONS_COMMAND, hide = true)]
+    Versions,
 }
 
-fn main() {
-    let args = Args::parse();
-    match args.cmd {
-        SubCommand::Compile { project_path } => {
-            let spinner = ProgressBar::new_spinner();
-            spinner.set_style(ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}").unwrap());
-            spinner.enable_steady_tick(Duration::from_millis(120));
-            spinner.set_message("Compiling...");
+/// Which tests to run.
+#[derive(clap::Args, Debug, Clone)]
+struct TestSelection {
+    /// Optional `;`-delimited filters
+    #[arg(short)]
+    filters: Option<String>,
 
-            let errs = unitool::compile(&project_path).unwrap();
-            if errs.is_empty() {
-                spinner.finish_with_message(
-                    format!("{}",
-                            unitool::display::green("Compilation succeeded")));
-            } else {
-                spinner.finish_with_message(
-                    format!("{}",
-                            unitool::display::red("Compilation failed")));
-                for err in &errs {
-                    println!("  {}", err);
-                }
-            }
-        },
-        SubCommand::Test { project_path, mode, assemblies, filters } => {
-            let spinner = ProgressBar::new_spinner();
-            spinner.set_style(ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}").unwrap());
-            spinner.enable_steady_tick(Duration::from_millis(120));
-            spinner.set_message("Compiling and running tests...");
+    /// Optional `;`-delimited NUnit categories
+    #[arg(short = 'c', long = "category")]
+    categories: Option<String>,
 
-            let (errs, results) = unitool::test(&project_path, mode, &assemblies, filters).unwrap();
-            if let Some(results) = results {
-                println!("{}", results);
-            } else {
-                for err in &errs {
-                    println!("  {}", err);
-                }
-            }
+    /// The assemblies to include
+    /// [default: found from the project's assembly definitions]
+    #[arg(short)]
+    assemblies: Option<String>,
+}
+impl TestSelection {
+    fn apply(&mut self, config: &TestConfig) {
+        self.filters = self.filters.take().or(config.filters.clone());
+        self.categories = self.categories.take().or(config.categories.clone());
+        self.assemblies = self.assemblies.take().or(config.assemblies.clone());
+    }
+}
+
+/// How to present test results.
+#[derive(clap::Args, Debug, Default)]
+struct ResultsOutput {
+    /// How to order the suites in the summary
+    #[arg(long, value_enum)]
+    sort: Option<unitool::SortKey>,
+
+    /// Also list the N slowest tests
+    #[arg(long, value_name = "N")]
+    slowest: Option<usize>,
+
+    /// Only show the failing tests, and the counts
+    #[arg(short, long, visible_alias = "failures-only")]
+    quiet: bool,
+
+    /// Also list the skipped tests, with why they were skipped
+    #[arg(long)]
+    show_skipped: bool,
+
+    /// Show failures' full stack traces, including the
+    /// engine's and test framework's frames
+    #[arg(long)]
+    full_trace: bool,
+
+    /// Also write the results as JUnit XML to this path
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    junit: Option<PathBuf>,
+
+    /// Also write an HTML report of the results to this path
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    html: Option<PathBuf>,
+
+    /// Copy the files failed tests attached (e.g. graphics
+    /// tests' screenshots) into this directory
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    artifacts_dir: Option<PathBuf>,
+}
+impl ResultsOutput {
+    /// Sort the results, copy their attachments (relative to
+    /// `base`), and write them as JUnit XML or HTML if requested.
+    fn prepare(&self, results: &mut unitool::TestSummary, base: &Path) -> anyhow::Result<()> {
+        if let Some(key) = &self.sort {
+            results.sort(key.clone());
+        }
+        if let Some(dir) = &self.artifacts_dir {
+            unitool::artifacts::collect(results, base, dir)?;
+        }
+        if let Some(path) = &self.junit {
+            unitool::junit::write_junit(results, path)?;
         }
+        if let Some(path) = &self.html {
+            unitool::html::write_html(results, path)?;
+        }
+        Ok(())
     }
 }
+
+impl SubCommand {
+    /// The project the command is for, if any.
+    fn project_path(&self) -> Option<&Path> {
+        match self {
+            SubCommand::Compile { project_path, .. }
+                | SubCommand::Test { project_path, .. }
+                | SubCommand::Build { project_path, .. }
+                | SubCommand::Bundles { project_path, .. }
+                | SubCommand::BurstCheck { project_path, .. }
+                | SubCommand::Watch { project_path, .. }
+                | SubCommand::Exec { project_path, .. }
+                | SubCommand::Cleanup { project_path }
+                | SubCommand::Validate { project_path, .. }
+                | SubCommand::Check { project_path, .. }
+                | SubCommand::Smoke { project_path }
+                | SubCommand::ListAssemblies { project_path }
+                | SubCommand::History { project_path, .. }
+                | SubCommand::Logs { project_path, .. }
+                | SubCommand::Trends { project_path, .. }
+                | SubCommand::ListTests { project_path, .. }
+                | SubCommand::Open { project_path, .. } => Some(project_path),
+            SubCommand::Pkg { cmd } => Some(cmd.project_path()),
+            SubCommand::Addressables { cmd } => Some(cmd.project_path()),
+            SubCommand::Package { cmd } => Some(cmd.project_path()),
+            SubCommand::ListEditors { project_path } => project_path.as_deref(),
+            SubCommand::Doctor { project_path, .. } => project_path.as_deref(),
+            SubCommand::License { project, .. } => project.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ReportCommand {
+    /// Merge several results files into one summary
+    Merge {
+        /// The results files to merge
+        #[clap(value_hint = ValueHint::FilePath, required = true)]
+        results: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        output: ResultsOutput,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AddressablesCommand {
+    /// Build the content, to where the Addressables settings say
+    Build {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to build for
+        #[arg(short, long, value_enum)]
+        target: unitool::build::BuildTarget,
+    },
+
+    /// Build only the content that's changed since a previous build
+    Update {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to build for
+        #[arg(short, long, value_enum)]
+        target: unitool::build::BuildTarget,
+
+        /// The previous build's content state file
+        /// [default: where the Addressables settings put it]
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        state: Option<PathBuf>,
+    },
+
+    /// Run the analyze rules (e.g. for duplicated dependencies) and
+    /// report what they find, without fixing anything
+    Analyze {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The platform to analyze for [default: the project's active one]
+        #[arg(short, long, value_enum)]
+        target: Option<unitool::build::BuildTarget>,
+    },
+}
+impl AddressablesCommand {
+    fn project_path(&self) -> &Path {
+        match self {
+            AddressablesCommand::Build { project_path, .. }
+                | AddressablesCommand::Update { project_path, .. }
+                | AddressablesCommand::Analyze { project_path, .. } => project_path,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum PackageCommand {
+    /// Export assets, with what's under them, as a `.unitypackage`
+    Export {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The assets or folders to export, relative
+        /// to the project, e.g. `Assets/Plugins/Foo`
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Where to write the package
+        #[arg(short = 'o', long = "out", value_hint = ValueHint::FilePath)]
+        out: PathBuf,
+    },
+
+    /// Import a `.unitypackage` into the project
+    Import {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// The package to import
+        #[clap(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+    },
+}
+impl PackageCommand {
+    fn project_path(&self) -> &Path {
+        match self {
+            PackageCommand::Export { project_path, .. }
+                | PackageCommand::Import { project_path, .. } => project_path,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum PkgCommand {
+    /// List the project's packages and their locked versions
+    List {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+    },
+
+    /// Add packages, as `name` (for the latest version) or
+    /// `name@version`, where the version may be a git URL
+    Add {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        #[clap(required = true)]
+        packages: Vec<String>,
+
+        #[clap(flatten)]
+        resolve: ResolveArgs,
+    },
+
+    /// Remove packages
+    Remove {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        #[clap(required = true)]
+        packages: Vec<String>,
+
+        #[clap(flatten)]
+        resolve: ResolveArgs,
+    },
+
+    /// Update packages to their latest versions (or `name@version`),
+    /// or all registry packages if none are given
+    Update {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        packages: Vec<String>,
+
+        #[clap(flatten)]
+        resolve: ResolveArgs,
+    },
+}
+impl PkgCommand {
+    fn project_path(&self) -> &Path {
+        match self {
+            PkgCommand::List { project_path }
+                | PkgCommand::Add { project_path, .. }
+                | PkgCommand::Remove { project_path, .. }
+                | PkgCommand::Update { project_path, .. } => project_path,
+        }
+    }
+}
+
+/// Whether to resolve the packages after changing them.
+#[derive(clap::Args, Debug)]
+struct ResolveArgs {
+    /// Then open the project headlessly, so the editor resolves
+    /// the packages and we can check everything still compiles
+    #[arg(long)]
+    resolve: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum ModulesCommand {
+    /// Install modules for an editor version
+    Install {
+        /// The editor version, e.g. `2022.3.10f1`
+        #[arg(long)]
+        version: String,
+
+        /// Module IDs, e.g. `android`, `linux-il2cpp`
+        #[clap(required = true)]
+        modules: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WorkspaceCommand {
+    /// Compile each project
+    Compile {
+        #[clap(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Compile each project and run its tests
+    Test {
+        #[clap(flatten)]
+        workspace: WorkspaceArgs,
+
+        /// Which set of tests to run
+        /// (required unless set in each project's config)
+        #[arg(short, value_enum)]
+        mode: Option<unitool::TestMode>,
+
+        #[clap(flatten)]
+        selection: TestSelection,
+    },
+}
+
+/// Which workspace to run, and how.
+#[derive(clap::Args, Debug)]
+struct WorkspaceArgs {
+    /// The workspace file, or a directory in the workspace
+    #[clap(value_hint = ValueHint::AnyPath, default_value = ".")]
+    path: PathBuf,
+
+    /// How many projects to run at once; with limited license
+    /// seats, runs beyond those wait for one to free up
+    /// [default: `jobs` in the workspace file, or 1]
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    jobs: Option<u32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum HookCommand {
+    /// Write the hook, to run `unitool check` on the project
+    Install {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Which hook to install
+        #[arg(long, value_enum, default_value = "pre-push")]
+        hook: unitool::hooks::HookKind,
+
+        /// Also run the edit mode tests
+        #[arg(long)]
+        tests: bool,
+
+        /// Replace an existing hook not installed by unitool
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove the hook, if unitool installed it
+    Uninstall {
+        /// The root path of the Unity project
+        #[clap(value_hint = ValueHint::FilePath)]
+        project_path: PathBuf,
+
+        /// Which hook to remove
+        #[arg(long, value_enum, default_value = "pre-push")]
+        hook: unitool::hooks::HookKind,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum LicenseCommand {
+    /// Activate a license, by serial (for Plus or Pro)
+    /// or with a license file from manual activation
+    Activate {
+        /// The license serial [default: $UNITY_SERIAL]
+        #[arg(long)]
+        serial: Option<String>,
+
+        /// A license file from https://license.unity3d.com/manual
+        #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "serial")]
+        license_file: Option<PathBuf>,
+
+        /// Instead create a request file to upload there for one
+        #[arg(long, conflicts_with_all = ["serial", "license_file"])]
+        manual: bool,
+
+        #[clap(flatten)]
+        account: AccountArgs,
+    },
+
+    /// Return a Plus or Pro license, freeing up its seat
+    Return {
+        #[clap(flatten)]
+        account: AccountArgs,
+    },
+
+    /// Show whether a license is activated
+    Status,
+}
+
+/// A Unity account; best passed through the environment,
+/// so the password doesn't show up in the process list.
+#[derive(clap::Args, Debug)]
+struct AccountArgs {
+    /// Unity account email [default: $UNITY_USERNAME]
+    #[arg(long)]
+    username: Option<String>,
+
+    /// Unity account password [default: $UNITY_PASSWORD]
+    #[arg(long)]
+    password: Option<String>,
+}
+impl AccountArgs {
+    fn credentials(self) -> anyhow::Result<unitool::license::Credentials> {
+        let username = self.username.or_else(|| std::env::var("UNITY_USERNAME").ok());
+        let password = self.password.or_else(|| std::env::var("UNITY_PASSWORD").ok());
+        match (username, password) {
+            (Some(username), Some(password)) => Ok(unitool::license::Credentials { username, password }),
+            _ => anyhow::bail!("A Unity account is needed; pass `--username` and `--password` or set UNITY_USERNAME and UNITY_PASSWORD"),
+        }
+    }
+}
+
+/// The spinner and, under it, what the editor's doing.
+/// Output printed while they're shown goes through
+/// its `suspend`, so it isn't drawn over.
+fn progress() -> &'static MultiProgress {
+    static PROGRESS: OnceLock<MultiProgress> = OnceLock::new();
+    PROGRESS.get_or_init(MultiProgress::new)
+}
+
+fn spinner(msg: &'static str) -> ProgressBar {
+    let spinner = progress().add(ProgressBar::new_spinner());
+    spinner.set_style(ProgressStyle::with_template("{spinner} [{elapsed_precise}] {msg}").unwrap());
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    spinner.set_message(msg);
+    spinner
+}
+
+/// Lock the project for this run, waiting if configured to.
+fn lock_project(spinner: &ProgressBar, project_path: &Path, mode: Option<unitool::lock::LockMode>) -> anyhow::Result<unitool::lock::ProjectLock> {
+    let msg = spinner.message();
+    let lock = unitool::lock::lock_project(project_path, mode.unwrap_or_default(), |holder| {
+        spinner.set_message(format!("Waiting for {} to finish with the project...", holder));
+    })?;
+    spinner.set_message(msg);
+    Ok(lock)
+}
+
+/// If license seats are limited, wait for one to free up.
+fn wait_for_seat(spinner: &ProgressBar, seats: Option<usize>) -> anyhow::Result<Option<unitool::seats::Seat>> {
+    let Some(seats) = seats else { return Ok(None) };
+    let msg = spinner.message();
+    let seat = unitool::seats::acquire(seats, |in_use| {
+        spinner.set_message(format!("Waiting for a license seat ({}/{} in use)...", in_use, seats));
+    })?;
+    spinner.set_message(msg);
+    Ok(Some(seat))
+}
+
+/// Warn about assembly definitions that'll be skipped
+/// when finding the test assemblies.
+fn warn_invalid_asmdefs(project_path: &Path) {
+    let Ok(found) = unitool::assemblies::discover(project_path) else { return };
+    for invalid in &found.invalid {
+        progress().suspend(|| eprintln!("{} {}",
+            yellow(&format!("Skipping {}:", invalid.path.display())), invalid.error));
+    }
+}
+
+/// Editor options from the global args; in verbose mode log
+/// lines are printed above the spinner, as are retries, and
+/// the run's phases are shown under it (with each test as it
+/// runs, in verbose mode).
+fn run_options<'a>(args: &GlobalArgs, spinner: &ProgressBar) -> unitool::RunOptions<'a> {
+    let on_log = if args.verbose { Some(print_log()) } else { None };
+    let on_retry: unitool::RetryHandler = Box::new(move |retry: &unitool::Retry| {
+        let reason = retry.error.to_string();
+        let reason = reason.lines().next().unwrap_or_default();
+        progress().suspend(|| eprintln!("{} {}",
+            yellow(&format!("Retrying in {}s (attempt {} of {}):", retry.delay.as_secs(), retry.attempt, retry.max_attempts)),
+            reason));
+    });
+    // Without a terminal, there's nothing to show them on
+    let on_event: Option<unitool::events::EventHandler> = if args.format() == OutputFormat::TeamCity {
+        Some(Box::new(|event| if let Some(message) = unitool::teamcity::progress(&event) {
+            progress().suspend(|| println!("{}", message));
+        }))
+    } else if progress().is_hidden() || spinner.is_hidden() {
+        None
+    } else {
+        Some(show_phases())
+    };
+    let saved_log = args.saved_log.clone();
+    unitool::RunOptions {
+        unity_path: args.unity_path.clone(),
+        on_log,
+        on_event,
+        // Just for showing each test under the spinner
+        test_events: args.verbose && args.format() != OutputFormat::TeamCity,
+        timeout: args.timeout.map(Duration::from_secs),
+        nographics: args.nographics(),
+        graphics_api: args.graphics_api(),
+        extra_args: args.editor_args.clone(),
+        retries: args.retries.unwrap_or(0),
+        retry_backoff: args.retry_backoff(),
+        on_retry: Some(on_retry),
+        keep_logs: args.keep_logs.unwrap_or(unitool::logs::DEFAULT_KEEP),
+        on_log_saved: Some(Box::new(move |path| *saved_log.lock().unwrap() = Some(path.to_path_buf()))),
+        docker: args.docker(),
+        severities: args.severities.clone(),
+        accept_api_update: args.accept_api_update,
+    }
+}
+
+/// Merge results files and show the summary.
+fn merge_results(results: &[PathBuf], output: &ResultsOutput, global: &GlobalArgs) -> Outcome {
+    let results = unitool::merge_results(results)
+        .map_err(|err| (err, ExitCode::Error))?;
+    report(results, output, global)
+}
+
+/// Show results loaded from files, as `report` does.
+fn report(mut results: unitool::TestSummary, output: &ResultsOutput, global: &GlobalArgs) -> Outcome {
+    // The editor runs in the project, which is
+    // likely where this is run from too
+    output.prepare(&mut results, Path::new("."))
+        .map_err(|err| (err, ExitCode::Error))?;
+    if global.format() == OutputFormat::Json {
+        print_json(&results);
+    } else if global.format() == OutputFormat::Markdown {
+        println!("{}", unitool::markdown::test_results(&results));
+    } else if global.format() == OutputFormat::Tap {
+        println!("{}", unitool::tap::to_tap(&results));
+    } else {
+        print_results(&results, None, output, global);
+    }
+    if global.format().annotates() {
+        annotate_failures(&results, &global.format());
+    }
+    match results.has_failures() {
+        true => Ok(ExitCode::TestsFailed),
+        false => Ok(ExitCode::Success),
+    }
+}
+
+/// Diff two results files, or runs from the project's history.
+fn diff_runs(results: &[PathBuf], against_last: Option<&Path>) -> anyhow::Result<unitool::diff::TestDiff> {
+    use unitool::diff::{Results, TestDiff};
+    let load = |path: &PathBuf| unitool::load_results(path).map(|summary| Results::from(&summary));
+    let Some(project_path) = against_last else {
+        let [old, new] = results else {
+            anyhow::bail!("Pass the old and new results files, or `--against-last` with a project");
+        };
+        return Ok(TestDiff::new(&load(old)?, &load(new)?));
+    };
+
+    let mut runs = unitool::history::load(project_path)?;
+    runs.retain(|run| !run.tests.is_empty());
+    let mut last = || runs.pop().map(|run| Results::from(&run))
+        .ok_or_else(|| anyhow::anyhow!("Not enough test runs recorded for `{}` to compare", project_path.display()));
+    match results {
+        [] => {
+            let new = last()?;
+            Ok(TestDiff::new(&last()?, &new))
+        },
+        [new] => Ok(TestDiff::new(&last()?, &load(new)?)),
+        _ => anyhow::bail!("With `--against-last`, pass just the new results file"),
+    }
+}
+
+/// Editor options for `serve` and `mcp`, from the global args.
+fn serve_options(args: &GlobalArgs, port: u16) -> unitool::server::ServeOptions {
+    unitool::server::ServeOptions {
+        port,
+        unity_path: args.unity_path.clone(),
+        timeout: args.timeout.map(Duration::from_secs),
+        nographics: args.nographics(),
+        graphics_api: args.graphics_api(),
+        extra_args: args.editor_args.clone(),
+        retries: args.retries.unwrap_or(0),
+        retry_backoff: args.retry_backoff(),
+        keep_logs: args.keep_logs.unwrap_or(unitool::logs::DEFAULT_KEEP),
+        docker: args.docker(),
+        severities: args.severities.clone(),
+        accept_api_update: args.accept_api_update,
+    }
+}
+
+/// Print editor log lines, around the progress bars.
+fn print_log<'a>() -> unitool::LogHandler<'a> {
+    use unitool::log::{Classifier, LineKind};
+    let classifier = std::cell::RefCell::new(Classifier::default());
+    Box::new(move |line: &str| {
+        let line = match classifier.borrow_mut().classify(line) {
+            LineKind::Error | LineKind::Exception => red(line),
+            LineKind::Warning => yellow(line),
+            LineKind::StackTrace | LineKind::Progress => muted(line),
+            LineKind::Noise | LineKind::Info => line.normal(),
+        };
+        progress().suspend(|| eprintln!("{}", line))
+    })
+}
+
+/// A phase of a run, as shown under the spinner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Packages,
+    Import,
+    Compile,
+    Reload,
+    Tests,
+}
+
+/// A bar per phase, in the order they were first entered, with the
+/// current one's spinning. As phases interleave (e.g. importing
+/// after compiling), a phase returned to picks up its bar again.
+struct Phases {
+    bars: Vec<(Phase, ProgressBar, Instant)>,
+    current: Option<Phase>,
+    imported: usize,
+    compiled: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+}
+impl Phases {
+    /// Move on to the phase, finishing the last one.
+    fn enter(&mut self, phase: Phase) -> &ProgressBar {
+        if self.current != Some(phase) {
+            self.finish_current();
+            let idx = match self.bars.iter().position(|(found, _, _)| *found == phase) {
+                Some(idx) => idx,
+                None => {
+                    self.bars.push((phase, progress().add(ProgressBar::new_spinner()), Instant::now()));
+                    self.bars.len() - 1
+                },
+            };
+            let bar = &self.bars[idx].1;
+            bar.set_style(ProgressStyle::with_template("  {spinner} {msg}").unwrap());
+            bar.enable_steady_tick(Duration::from_millis(120));
+            self.current = Some(phase);
+        }
+        self.bar(phase).expect("a phase was entered")
+    }
+
+    fn bar(&self, phase: Phase) -> Option<&ProgressBar> {
+        self.bars.iter().find(|(found, _, _)| *found == phase).map(|(_, bar, _)| bar)
+    }
+
+    /// Show the current phase as done, with its totals so far.
+    fn finish_current(&mut self) {
+        let Some(phase) = self.current.take() else { return };
+        let Some((_, bar, started)) = self.bars.iter().find(|(found, _, _)| *found == phase) else { return };
+        let done = match phase {
+            Phase::Packages => "Resolved packages".to_string(),
+            Phase::Import => format!("Imported {} assets", self.imported),
+            Phase::Compile => format!("Compiled {} assemblies", self.compiled),
+            Phase::Reload => "Reloaded scripts".to_string(),
+            Phase::Tests => format!("Ran {} tests", self.passed + self.failed + self.skipped),
+        };
+        bar.disable_steady_tick();
+        bar.set_style(ProgressStyle::with_template("  {msg}").unwrap());
+        bar.set_message(format!("{} {} {}",
+            green("✓"), done, muted(&unitool::display::human_duration(started.elapsed().as_secs_f64()))));
+    }
+}
+
+/// Show the run's phases under the spinner as its events come in:
+/// packages resolving, assets importing, each assembly compiling,
+/// scripts reloading, and each test running. All are cleared
+/// when the editor exits.
+fn show_phases<'a>() -> unitool::events::EventHandler<'a> {
+    use unitool::{TestResult, events::RunEvent};
+    let phases = Mutex::new(Phases {
+        bars: vec![], current: None,
+        imported: 0, compiled: 0, passed: 0, failed: 0, skipped: 0,
+    });
+    Box::new(move |event: RunEvent| {
+        let mut phases = phases.lock().unwrap();
+        match event {
+            RunEvent::ResolvingPackages => phases.enter(Phase::Packages).set_message("Resolving packages..."),
+            RunEvent::PackagesResolved => phases.finish_current(),
+            RunEvent::ImportingAsset(path) => {
+                let count = phases.imported + 1;
+                phases.enter(Phase::Import).set_message(format!("Importing assets ({}) {}", count, muted(&path)));
+                phases.imported = count;
+            },
+            RunEvent::CompilationStarted => phases.enter(Phase::Compile).set_message("Compiling scripts..."),
+            RunEvent::CompilingAssembly { name, progress } => {
+                let count = phases.compiled + 1;
+                let progress = match progress {
+                    Some((done, total)) => format!(" ({}/{})", done, total),
+                    None => String::new(),
+                };
+                phases.enter(Phase::Compile).set_message(format!("Compiling {}{}...", name, progress));
+                phases.compiled = count;
+            },
+            RunEvent::ReloadingDomain => phases.enter(Phase::Reload).set_message("Reloading scripts..."),
+            RunEvent::TestStarted(name) => {
+                let counts = format!("{} passed, {} failed", phases.passed, phases.failed);
+                phases.enter(Phase::Tests).set_message(format!("Running tests ({}) {}", counts, muted(&name)));
+            },
+            RunEvent::TestFinished { result, .. } => match result {
+                TestResult::Passed => phases.passed += 1,
+                TestResult::Failed => phases.failed += 1,
+                TestResult::Skipped => phases.skipped += 1,
+            },
+            RunEvent::Finished { .. } => {
+                phases.current = None;
+                for (_, bar, _) in phases.bars.drain(..) {
+                    bar.finish_and_clear();
+                }
+            },
+            RunEvent::EditorStarted { .. } | RunEvent::CompileError(_) => {},
+        }
+    })
+}
+
+/// Print compile errors grouped by file, in line order.
+fn print_compile_errors(project_path: &Path, errs: &unitool::CompileErrors, global: &GlobalArgs) {
+    let groups = unitool::compiler::group_errors(errs);
+    let mut hinted = HashSet::new();
+    let mut by_file: BTreeMap<Option<&str>, Vec<&unitool::compiler::ErrorGroup>> = BTreeMap::new();
+    for group in &groups {
+        by_file.entry(group.error.file.as_deref()).or_default().push(group);
+    }
+    for (file, groups) in by_file {
+        println!("  {}", file.unwrap_or("(no file)").bold());
+        for group in groups {
+            let err = group.error;
+            let location = match (err.line, err.column) {
+                (Some(line), Some(col)) => format!("{}:{}", line, col),
+                _ => "".to_string(),
+            };
+            let seen_in = match group.assemblies.len() {
+                0 | 1 => String::new(),
+                n => format!(" {}", muted(&format!("(seen in {} assemblies)", n))),
+            };
+            println!("    {:>7} {}{} {}{}",
+                muted(&location),
+                red(err.code.as_deref().unwrap_or("error")),
+                analyzer_note(err.code.as_deref()),
+                err.message,
+                seen_in);
+            // The same hint under each of many errors is just noise
+            if let Some(hint) = global.explain.then(|| unitool::explain::hint(err)).flatten() {
+                if hinted.insert(hint.clone()) {
+                    println!("            {}", yellow(&format!("hint: {}", hint)));
+                }
+            }
+            let snippet = match (file, err.line, global.context()) {
+                (Some(file), Some(line), context) if context > 0 =>
+                    unitool::source::snippet(&project_path.join(file), line, err.column, context),
+                _ => None,
+            };
+            if let Some(snippet) = snippet {
+                println!("{}\n", snippet.lines().map(|line| format!("            {}", line)).collect::<Vec<_>>().join("\n"));
+            }
+        }
+    }
+    let counts = unitool::compiler::count_codes(&groups);
+    if groups.len() > 1 {
+        let counts: Vec<String> = counts.iter().map(|(code, count)| format!("{} ×{}", code, count)).collect();
+        println!("  {}", muted(&counts.join(", ")));
+    }
+}
+
+/// Marks diagnostics from analyzers, rather than the compiler.
+fn analyzer_note(code: Option<&str>) -> String {
+    match code.map(unitool::compiler::DiagnosticSource::of) {
+        Some(unitool::compiler::DiagnosticSource::Analyzer) => format!(" {}", muted("(analyzer)")),
+        _ => String::new(),
+    }
+}
+
+/// Print shader errors (and warnings, if asked for)
+/// in their own section, after the scripts'.
+fn print_shader_diagnostics(shaders: &unitool::shaders::ShaderDiagnostics, show_warnings: bool) {
+    if !shaders.errors.is_empty() {
+        println!("\n{}", red(&format!("Shader errors ({}):", shaders.errors.len())));
+        for err in &shaders.errors {
+            println!("  {}", err);
+        }
+    }
+    if show_warnings && !shaders.warnings.is_empty() {
+        println!("\n{}", yellow(&format!("Shader warnings ({}):", shaders.warnings.len())));
+        for warning in &shaders.warnings {
+            println!("  {}", warning);
+        }
+    }
+}
+
+/// Print warnings grouped by code and message, so a
+/// warning repeated across the project is listed once.
+fn print_warnings(warnings: &unitool::CompileWarnings) {
+    const MAX_LOCATIONS: usize = 3;
+    for group in unitool::compiler::group_warnings(warnings) {
+        let count = if group.count > 1 { format!(" ×{}", group.count) } else { "".to_string() };
+        println!("  {}{} {}{}",
+            yellow(group.code.as_deref().unwrap_or("warning")),
+            analyzer_note(group.code.as_deref()),
+            group.message,
+            muted(&count));
+        let mut locations = group.locations.iter().take(MAX_LOCATIONS)
+            .map(|location| location.to_string())
+            .collect::<Vec<_>>();
+        if group.locations.len() > MAX_LOCATIONS {
+            locations.push(format!("+{} more", group.locations.len() - MAX_LOCATIONS));
+        }
+        if !locations.is_empty() {
+            println!("    {}", muted(&locations.join(", ")));
+        }
+    }
+}
+
+/// Print exceptions the editor logged, with
+/// the top of their stack traces.
+fn print_exceptions(exceptions: &[unitool::log::LogException]) {
+    const MAX_FRAMES: usize = 3;
+    println!("\n{}", yellow(&format!("The editor logged {} exceptions:", exceptions.len())));
+    for exception in exceptions {
+        println!("  {} {}", red(&exception.kind), exception.message);
+        for frame in exception.stack_trace.iter().take(MAX_FRAMES) {
+            println!("    {}", muted(frame));
+        }
+    }
+}
+
+/// Print GitHub annotations (or TeamCity build problems) for compile errors.
+fn annotate_errors(project_path: &Path, errs: &unitool::CompileErrors, format: &OutputFormat) {
+    if *format == OutputFormat::TeamCity {
+        for problem in unitool::teamcity::compile_errors(errs) {
+            println!("{}", problem);
+        }
+        return;
+    }
+    for err in sorted_errors(errs) {
+        println!("{}", unitool::github::compile_error(project_path, err));
+    }
+}
+
+/// Print GitHub annotations for compiler warnings.
+fn annotate_warnings(project_path: &Path, warnings: &unitool::CompileWarnings, level: unitool::github::Level) {
+    let mut warnings: Vec<_> = warnings.iter().collect();
+    warnings.sort();
+    for warning in warnings {
+        println!("{}", unitool::github::compile_warning(project_path, warning, level));
+    }
+}
+
+/// Print GitHub annotations for failing tests, or
+/// for TeamCity, service messages for all of them.
+fn annotate_failures(results: &unitool::TestSummary, format: &OutputFormat) {
+    let annotations = match format {
+        OutputFormat::TeamCity => unitool::teamcity::test_results(results),
+        _ => unitool::github::test_failures(results),
+    };
+    for annotation in annotations {
+        println!("{}", annotation);
+    }
+}
+
+/// Print the installed editors, marking the selected one.
+fn print_editors(editors: &[unitool::editors::Editor], selected: Option<&Path>) {
+    let width = editors.iter().map(|editor| editor.version.len()).max().unwrap_or(0);
+    for editor in editors {
+        let line = format!("{:width$}  {}", editor.version, editor.path.display(), width = width);
+        if selected == Some(editor.path.as_path()) {
+            println!("{} {}", green("*"), green(&line));
+        } else {
+            println!("  {}", line);
+        }
+        if !editor.modules.is_empty() {
+            println!("  {:width$}  {}", "", muted(&editor.modules.join(", ")), width = width);
+        }
+    }
+    if editors.is_empty() {
+        println!("{}", muted("No editors are installed"));
+    }
+    if let Some(path) = selected {
+        if !editors.iter().any(|editor| editor.path == path) {
+            println!("{}", green(&format!("* {} (from --unity-path, UNITY_PATH or the config)", path.display())));
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    println!("{}", unitool::json::to_string(value).unwrap());
+}
+
+/// Report an error in the requested format.
+fn report_error(err: anyhow::Error, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            #[derive(Serialize)]
+            struct Failure {
+                error: String,
+
+                /// For timeouts, the test that hung
+                #[serde(skip_serializing_if = "Option::is_none")]
+                hung_in: Option<String>,
+            }
+            let hung_in = err.downcast_ref::<unitool::TimeoutError>()
+                .and_then(|timeout| timeout.last_test.clone());
+            print_json(&Failure { error: err.to_string(), hung_in });
+        },
+        OutputFormat::Text | OutputFormat::Markdown => eprintln!("{}", red(&err.to_string())),
+        OutputFormat::Github => println!("{}", unitool::github::error(&err.to_string())),
+        OutputFormat::TeamCity => println!("{}", unitool::teamcity::build_problem(&err.to_string())),
+        OutputFormat::Tap => println!("Bail out! {}", err.to_string().lines().next().unwrap_or("")),
+    }
+}
+
+/// Report an error in the requested format and exit.
+fn fail(err: anyhow::Error, global: &GlobalArgs, code: ExitCode) -> ! {
+    report_error(err, &global.format());
+    print_log_path(global);
+    code.exit();
+}
+
+/// After a failed run, point to its full editor log.
+fn print_log_path(global: &GlobalArgs) {
+    let saved_log = global.saved_log.lock().unwrap();
+    if let (Some(path), false) = (saved_log.as_ref(), global.format() == OutputFormat::Json) {
+        eprintln!("{}", muted(&format!("Full editor log: {}", path.display())));
+    }
+}
+
+/// How a command finished; errors carry
+/// the exit code they should produce.
+type Outcome = Result<ExitCode, (anyhow::Error, ExitCode)>;
+
+/// The code to exit with for the outcome, reporting its error if
+/// any. Returned from `main`, rather than exiting there and then,
+/// so the lock and seat guards the command held are dropped first.
+fn finish(outcome: Outcome, global: &GlobalArgs) -> std::process::ExitCode {
+    let code = match outcome {
+        Ok(ExitCode::Success) => ExitCode::Success,
+        Ok(code) => {
+            print_log_path(global);
+            code
+        },
+        Err((err, code)) => {
+            report_error(err, &global.format());
+            print_log_path(global);
+            code
+        },
+    };
+    code.into()
+}
+
+/// With `--notify`, post the run's outcome to the webhook, and with
+/// `--notify-desktop`, show it on the desktop. Failing to doesn't
+/// fail the run.
+fn notify(project_path: &Path, command: &str, outcome: &Outcome, record: Option<&unitool::history::RunRecord>, global: &GlobalArgs) {
+    if global.notify.is_none() && !global.notify_desktop { return }
+    let (code, error) = match outcome {
+        Ok(code) => (*code, None),
+        Err((err, code)) => (*code, Some(err)),
+    };
+    let notification = unitool::notify::Notification::new(project_path, command, record, code, error);
+    if let Some(url) = &global.notify {
+        if let Err(err) = unitool::notify::send(url, &notification) {
+            eprintln!("{} {}", yellow("Couldn't send the notification:"), err);
+        }
+    }
+    if global.notify_desktop {
+        if let Err(err) = unitool::notify::desktop(&notification) {
+            eprintln!("{} {}", yellow("Couldn't show the desktop notification:"), err);
+        }
+    }
+}
+
+/// Open the failure in the editor (per `--open-editor`), asking
+/// first if so. Failing to open it only warns.
+fn open_failure(location: Option<unitool::open::Location>, global: &GlobalArgs) {
+    use unitool::open::OpenEditor;
+    let Some(location) = location else { return };
+    let when = global.open_editor.unwrap_or_default();
+    if when == OpenEditor::Never || global.format() == OutputFormat::Json { return }
+    if when == OpenEditor::Ask {
+        let term = console::Term::stderr();
+        if !term.is_term() || !console::user_attended() { return }
+        let relative = std::env::current_dir().ok()
+            .and_then(|cwd| location.path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+            .unwrap_or(location.path.clone());
+        eprint!("Open {}:{} in your editor? [y/N] ", relative.display(), location.line);
+        let answer = term.read_char().unwrap_or('n');
+        eprintln!();
+        if !answer.eq_ignore_ascii_case(&'y') { return }
+    }
+    if let Err(err) = unitool::open::open(global.editor_command.as_deref(), &location) {
+        eprintln!("{} {}", yellow(&format!("Couldn't open {}:", location)), err);
+    }
+}
+
+/// Add the run to the project's history, returning it. Failing to
+/// only warns, as the history shouldn't decide the run's outcome.
+fn record_run(project_path: &Path, record: unitool::history::RunRecord) -> unitool::history::RunRecord {
+    if let Err(err) = unitool::history::record(project_path, &record) {
+        eprintln!("{} {}", yellow("Couldn't record the run in the history:"), err);
+    }
+    record
+}
+
+/// Record the run's failures for `--failed`, only warning if they
+/// can't be, as the results have been (or will be) shown regardless.
+fn save_failures(project_path: &Path, results: &unitool::TestSummary) {
+    if let Err(err) = unitool::rerun::save_failures(project_path, results) {
+        eprintln!("{} {}", yellow("Couldn't record the failures for `--failed`:"), err);
+    }
+}
+
+/// How to treat compiler warnings.
+#[derive(Debug, Clone, Copy, Default)]
+struct WarningOptions {
+    show: bool,
+    as_errors: bool,
+}
+
+/// Compile the project and print the results, setting `recorded`
+/// to the run's record in the history, if it was run.
+fn compile(project_path: &Path, ratchet: Option<unitool::ratchet::Ratchet>, warning_options: WarningOptions, cached: bool, recorded: &mut Option<unitool::history::RunRecord>, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Compiling...");
+    let options = run_options(global, &spinner);
+    let (output, cached) = match cached.then(|| unitool::cache::lookup(project_path, &options)).flatten() {
+        Some(output) => (output, true),
+        None => {
+            let _lock = lock_project(&spinner, project_path, global.lock)
+                .map_err(|err| (err, ExitCode::Error))?;
+            let _seat = wait_for_seat(&spinner, global.license_seats)
+                .map_err(|err| (err, ExitCode::Error))?;
+            let started = Instant::now();
+            let output = unitool::compile(project_path, &options)
+                .map_err(|err| (err, ExitCode::EditorFailed))?;
+            let record = unitool::history::RunRecord::compile(project_path, &output.errors, started.elapsed());
+            *recorded = Some(record_run(project_path, record));
+            (output, false)
+        },
+    };
+    let cached_note = match (cached, output.attempts) {
+        (true, _) => muted(" (cached)").to_string(),
+        (false, attempts) if attempts > 1 => muted(&format!(" (after {} attempts)", attempts)).to_string(),
+        _ => String::new(),
+    };
+    let outcome = match ratchet {
+        Some(unitool::ratchet::Ratchet::Warnings) if output.errors.is_empty() =>
+            Some(unitool::ratchet::ratchet_warnings(project_path, &output.warnings)
+                .map_err(|err| (err, ExitCode::Error))?),
+        _ => None,
+    };
+    let ratchet_failed = outcome.as_ref().is_some_and(|outcome| outcome.failed());
+    let warnings_failed = warning_options.as_errors && !output.warnings.is_empty();
+    let shaders_failed = !output.shaders.errors.is_empty();
+
+    if global.format() == OutputFormat::Json {
+        spinner.finish_and_clear();
+        let mut warnings: Vec<_> = output.warnings.iter().collect();
+        warnings.sort();
+        print_json(&CompileJson {
+            errors: sorted_errors(&output.errors),
+            warnings,
+            exceptions: &output.exceptions,
+            shaders: &output.shaders,
+            ratchet: outcome.as_ref(),
+            cached,
+            attempts: output.attempts,
+        });
+    } else if global.format() == OutputFormat::Markdown {
+        spinner.finish_and_clear();
+        if warnings_failed && output.errors.is_empty() {
+            println!("### ❌ Compilation failed ({} warnings treated as errors)", output.warnings.len());
+        } else {
+            println!("{}", unitool::markdown::compile_errors(&output.errors));
+        }
+        if warning_options.show || warnings_failed {
+            println!("\n{}", unitool::markdown::warnings(&output.warnings));
+        }
+    } else if warnings_failed && output.errors.is_empty() {
+        spinner.finish_with_message(
+            format!("{}{}",
+                    red(&format!("Compilation failed ({} warnings treated as errors)", output.warnings.len())), cached_note));
+        print_warnings(&output.warnings);
+    } else if shaders_failed && output.errors.is_empty() {
+        let count = output.shaders.errors.len();
+        spinner.finish_with_message(
+            format!("{}{}",
+                    red(&format!("Shader compilation failed ({} {})", count, if count == 1 { "error" } else { "errors" })), cached_note));
+        if warning_options.show {
+            print_warnings(&output.warnings);
+        }
+    } else if output.errors.is_empty() {
+        spinner.finish_with_message(
+            format!("{}{}",
+                    green("Compilation succeeded"), cached_note));
+        if warning_options.show {
+            print_warnings(&output.warnings);
+        }
+    } else {
+        spinner.finish_with_message(
+            format!("{}{}",
+                    red("Compilation failed"), cached_note));
+        print_compile_errors(project_path, &output.errors, global);
+    }
+    if !matches!(global.format(), OutputFormat::Json | OutputFormat::Markdown) {
+        // Whichever way it failed, if it got as far as the ratchet
+        if let Some(outcome) = &outcome {
+            println!("{}", outcome);
+        }
+        print_shader_diagnostics(&output.shaders, warning_options.show);
+    }
+    if !output.exceptions.is_empty() && !matches!(global.format(), OutputFormat::Json | OutputFormat::Markdown) {
+        print_exceptions(&output.exceptions);
+    }
+    if global.format() == OutputFormat::TeamCity {
+        annotate_errors(project_path, &output.errors, &global.format());
+        if warnings_failed {
+            println!("{}", unitool::teamcity::build_problem(
+                &format!("Compilation failed ({} warnings treated as errors)", output.warnings.len())));
+        }
+        if warnings_failed || warning_options.show {
+            let mut warnings: Vec<_> = output.warnings.iter().collect();
+            warnings.sort();
+            for warning in warnings {
+                println!("{}", unitool::teamcity::compile_warning(warning));
+            }
+        }
+    } else if global.format() == OutputFormat::Github {
+        annotate_errors(project_path, &output.errors, &global.format());
+        for exception in &output.exceptions {
+            println!("{}", unitool::github::exception(project_path, exception));
+        }
+        if warnings_failed {
+            annotate_warnings(project_path, &output.warnings, unitool::github::Level::Error);
+        } else if warning_options.show {
+            annotate_warnings(project_path, &output.warnings, unitool::github::Level::Warning);
+        }
+    }
+    open_failure(unitool::open::first_compile_error(project_path, &output.errors), global);
+    if !output.errors.is_empty() || ratchet_failed || warnings_failed || shaders_failed {
+        Ok(ExitCode::CompileFailed)
+    } else {
+        Ok(ExitCode::Success)
+    }
+}
+
+/// Compile the project, run the tests and print the results,
+/// setting `recorded` to the run's record in the history.
+#[allow(clippy::too_many_arguments)]
+fn test(project_path: &Path, mode: unitool::TestMode, selection: &TestSelection, output: &ResultsOutput, test_options: &unitool::TestOptions, update_baseline: bool, recorded: &mut Option<unitool::history::RunRecord>, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Compiling and running tests...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    if selection.assemblies.is_none() {
+        warn_invalid_asmdefs(project_path);
+    }
+
+    let started = Instant::now();
+    let result = unitool::test(project_path, &run_options(global, &spinner), mode.clone(),
+        selection.assemblies.as_deref(),
+        selection.filters.clone(), selection.categories.clone(), test_options);
+    spinner.finish_and_clear();
+    let (errs, mut results) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    let record = unitool::history::RunRecord::test(project_path, &mode, &errs, results.as_ref(), started.elapsed());
+    *recorded = Some(record_run(project_path, record));
+    let coverage = match &results {
+        Some(_) if test_options.coverage => Some(unitool::coverage::load_coverage(project_path)
+            .map_err(|err| (err, ExitCode::Error))?),
+        _ => None,
+    };
+    if let Some(results) = &mut results {
+        output.prepare(results, project_path).map_err(|err| (err, ExitCode::Error))?;
+    }
+
+    let mut baseline = unitool::baseline::Baseline::load(project_path)
+        .map_err(|err| (err, ExitCode::Error))?;
+    if let (true, Some(results)) = (update_baseline, &results) {
+        baseline = baseline.updated(results);
+        baseline.save(project_path).map_err(|err| (err, ExitCode::Error))?;
+    }
+    let checked = match &results {
+        Some(results) if !baseline.is_empty() => Some(baseline.check(results)),
+        _ => None,
+    };
+
+    if global.format() == OutputFormat::Json {
+        print_json(&TestJson {
+            errors: sorted_errors(&errs),
+            results: results.as_ref(),
+            coverage: coverage.as_ref(),
+            baseline: checked.as_ref(),
+        });
+    } else if global.format() == OutputFormat::Markdown {
+        match &results {
+            Some(results) => println!("{}", unitool::markdown::test_results(results)),
+            None => println!("{}", unitool::markdown::compile_errors(&errs)),
+        }
+    } else if global.format() == OutputFormat::Tap {
+        match &results {
+            Some(results) => println!("{}", unitool::tap::to_tap(results)),
+            None => println!("{}", unitool::tap::bail_out(&errs)),
+        }
+    } else if let Some(results) = &results {
+        print_results(results, Some(project_path), output, global);
+        if let Some(seed) = test_options.random_order_seed {
+            println!("{}", muted(&format!("Ran in random order with seed {}; pass `--shuffle {}` to run in the same order", seed, seed)));
+        }
+        if let Some(coverage) = &coverage {
+            println!("\n{}", coverage);
+        }
+        match &checked {
+            Some(checked) if !checked.is_empty() => println!("\n{}", checked),
+            _ => {},
+        }
+    } else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+        if let Some(results) = &results {
+            annotate_failures(results, &global.format());
+        }
+    }
+    if let Some(results) = &results {
+        save_failures(project_path, results);
+    }
+    let failed_at = match (&results, &checked) {
+        (None, _) => unitool::open::first_compile_error(project_path, &errs),
+        (Some(_), Some(checked)) if !checked.failed() => None,
+        (Some(results), _) => unitool::open::first_test_failure(project_path, results),
+    };
+    open_failure(failed_at, global);
+    Ok(match (results, checked) {
+        (None, _) => ExitCode::CompileFailed,
+        (Some(_), Some(checked)) if checked.failed() => ExitCode::TestsFailed,
+        (Some(results), None) if results.has_failures() => ExitCode::TestsFailed,
+        _ => ExitCode::Success,
+    })
+}
+
+/// Compile and optionally run edit mode tests for a git hook,
+/// printing only failures and a summary line.
+fn check(project_path: &Path, tests: bool, global: &GlobalArgs) -> Outcome {
+    let quiet = ProgressBar::hidden();
+    let options = run_options(global, &quiet);
+    let started = Instant::now();
+    let cached = unitool::cache::lookup(project_path, &options);
+    let summary = |msg: String, note: &str| println!("unitool: {} {}", msg,
+        muted(&format!("({}{})", note, human_duration(started.elapsed().as_secs_f64()))));
+    let compile_failed = |errs: &unitool::CompileErrors| {
+        print_compile_errors(project_path, errs, global);
+        summary(red(&format!("compilation failed with {} errors", errs.len())).to_string(), "");
+        Ok(ExitCode::CompileFailed)
+    };
+
+    // Without tests, or if nothing's changed since a failed compile,
+    // compiling is all there is to do (running tests compiles anyway)
+    match cached {
+        Some(output) if !output.errors.is_empty() => return compile_failed(&output.errors),
+        Some(_) if !tests => {
+            summary(green("compiled").to_string(), "cached, ");
+            return Ok(ExitCode::Success);
+        },
+        _ => {},
+    }
+
+    let _lock = lock_project(&quiet, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&quiet, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+    if !tests {
+        let output = unitool::compile(project_path, &options)
+            .map_err(|err| (err, ExitCode::EditorFailed))?;
+        let record = unitool::history::RunRecord::compile(project_path, &output.errors, started.elapsed());
+        record_run(project_path, record);
+        if !output.errors.is_empty() {
+            return compile_failed(&output.errors);
+        }
+        summary(green("compiled").to_string(), "");
+        return Ok(ExitCode::Success);
+    }
+
+    let mode = unitool::TestMode::EditMode;
+    let (errs, results) = unitool::test(project_path, &options, mode.clone(), None, None, None, &Default::default())
+        .map_err(|err| (err, ExitCode::EditorFailed))?;
+    let record = unitool::history::RunRecord::test(project_path, &mode, &errs, results.as_ref(), started.elapsed());
+    record_run(project_path, record);
+    let Some(results) = results else { return compile_failed(&errs) };
+    let baseline = unitool::baseline::Baseline::load(project_path)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let failures: Vec<_> = results.failures().into_iter()
+        .filter(|case| !baseline.known_failing.contains_key(case.key()) && !baseline.quarantined.contains_key(case.key()))
+        .collect();
+    for case in &failures {
+        println!("  {} {}", red("failed"), case.key());
+    }
+    if failures.is_empty() {
+        summary(green(&format!("compiled, {} tests passed", results.passed())).to_string(), "");
+        Ok(ExitCode::Success)
+    } else {
+        summary(red(&format!("{} of {} tests failed", failures.len(), results.total())).to_string(), "");
+        Ok(ExitCode::TestsFailed)
+    }
+}
+
+/// Compile (or, with a test mode, test) each project in the
+/// workspace, printing one report with a section per project.
+fn workspace(args: &WorkspaceArgs, tests: Option<(Option<unitool::TestMode>, &TestSelection)>, global: &GlobalArgs) -> Outcome {
+    use unitool::workspace::{Workspace, WorkspaceReport, ProjectReport, ProjectOutcome, run_all};
+    let workspace = Workspace::find(&args.path)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let jobs = args.jobs.map(|jobs| jobs as usize).or(workspace.jobs).unwrap_or(1);
+    let projects = run_all(&workspace.projects, jobs, |project| {
+        let spinner = spinner("Compiling...");
+        spinner.set_message(format!("{}: {}", project.name,
+            if tests.is_some() { "Compiling and running tests..." } else { "Compiling..." }));
+        let started = Instant::now();
+        let outcome = run_workspace_project(&project.path, tests.as_ref(), &spinner, global)
+            .unwrap_or_else(|(err, exit_code)| ProjectOutcome::Failed { error: err.to_string(), exit_code });
+        spinner.finish_and_clear();
+        ProjectReport { project: project.clone(), outcome, time: started.elapsed().as_secs_f64() }
+    });
+    let report = WorkspaceReport { projects };
+    if global.format() == OutputFormat::Json {
+        print_json(&report);
+    } else {
+        println!("{}", report);
+    }
+    Ok(report.exit_code())
+}
+
+/// Run one of a workspace's projects, with its own config
+/// filling in what the global args and `selection` leave unset.
+fn run_workspace_project(project_path: &Path, tests: Option<&(Option<unitool::TestMode>, &TestSelection)>, spinner: &ProgressBar, global: &GlobalArgs) -> Result<unitool::workspace::ProjectOutcome, (anyhow::Error, ExitCode)> {
+    use unitool::workspace::ProjectOutcome;
+    let config = Config::for_project(project_path).map_err(|err| (err, ExitCode::Error))?;
+    let mut global = global.clone();
+    global.apply(&config).map_err(|err| (err, ExitCode::Error))?;
+    // Its own, rather than shared with the projects run alongside it
+    global.saved_log = Default::default();
+
+    let _lock = lock_project(spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    // Phases from several runs at once would be a jumble
+    let mut options = run_options(&global, spinner);
+    options.on_event = None;
+    let started = Instant::now();
+    Ok(match tests {
+        Some((mode, selection)) => {
+            let mut selection = (*selection).clone();
+            selection.apply(&config.test);
+            let Some(mode) = mode.clone().or(config.test.mode.clone()) else {
+                return Err((anyhow::anyhow!("No test mode given; pass `-m` or set `mode` under `[test]` in its {}",
+                    unitool::config::CONFIG_FILE), ExitCode::Error));
+            };
+            let (errs, results) = unitool::test(project_path, &options, mode.clone(),
+                selection.assemblies.as_deref(), selection.filters.clone(), selection.categories.clone(), &Default::default())
+                .map_err(|err| (err, ExitCode::EditorFailed))?;
+            let record = unitool::history::RunRecord::test(project_path, &mode, &errs, results.as_ref(), started.elapsed());
+            record_run(project_path, record);
+            match results {
+                Some(results) => {
+                    save_failures(project_path, &results);
+                    let baseline = unitool::baseline::Baseline::load(project_path)
+                        .map_err(|err| (err, ExitCode::Error))?;
+                    let failures = results.failures().into_iter()
+                        .map(|case| case.key())
+                        .filter(|name| !baseline.known_failing.contains_key(*name) && !baseline.quarantined.contains_key(*name))
+                        .map(|name| name.to_string())
+                        .collect();
+                    ProjectOutcome::Tested { results, failures }
+                },
+                None => ProjectOutcome::CompileFailed { errors: sorted_errors(&errs).into_iter().cloned().collect() },
+            }
+        },
+        None => {
+            let output = unitool::compile(project_path, &options)
+                .map_err(|err| (err, ExitCode::EditorFailed))?;
+            let record = unitool::history::RunRecord::compile(project_path, &output.errors, started.elapsed());
+            record_run(project_path, record);
+            match output.errors.is_empty() {
+                true => ProjectOutcome::Compiled { warnings: output.warnings.len() },
+                false => ProjectOutcome::CompileFailed { errors: sorted_errors(&output.errors).into_iter().cloned().collect() },
+            }
+        },
+    })
+}
+
+/// Print test results, with their timings
+/// and optionally the `slowest` tests.
+fn print_results(results: &unitool::TestSummary, project_path: Option<&Path>, output: &ResultsOutput, global: &GlobalArgs) {
+    unitool::trace::set_trace_options(project_path, output.full_trace, global.context());
+    if results.truncated() {
+        eprintln!("{}", red(
+                "Test results were truncated, some tests may be missing"));
+    }
+    if !output.quiet {
+        println!("{}", results);
+        if let Some(bars) = results.timing_bars() {
+            println!("\n{}", bars);
+        }
+    } else if results.has_failures() {
+        println!("{}", results.failures_only());
+    }
+    if let Some(n) = output.slowest {
+        println!("\n{}", results.slowest(n));
+    }
+    if output.show_skipped && results.skipped() > 0 {
+        println!("\n{}", results.skipped_tests());
+    }
+    println!("\n{}", results.totals());
+}
+
+/// Run a static method in the editor, streaming its log.
+fn exec(project_path: &Path, method: &str, args: &[String], global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Running...");
+    spinner.set_message(format!("Running {}...", method));
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let mut options = run_options(global, &spinner);
+    options.on_log.get_or_insert_with(print_log);
+    let result = unitool::exec(project_path, &options, method, args);
+    spinner.finish_and_clear();
+    let output = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+
+    let method_failed = output.exit_code != Some(0);
+    if global.format() == OutputFormat::Json {
+        print_json(&ExecJson {
+            errors: sorted_errors(&output.errors),
+            exit_code: output.exit_code,
+        });
+    } else if !output.errors.is_empty() {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &output.errors, global);
+    } else if method_failed {
+        let code = output.exit_code.map(|code| code.to_string()).unwrap_or("unknown".to_string());
+        println!("{}", red(&format!("{} failed (Unity exited with code {})", method, code)));
+    } else {
+        println!("{}", green(&format!("{} finished", method)));
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &output.errors, &global.format());
+    }
+    Ok(if !output.errors.is_empty() {
+        ExitCode::CompileFailed
+    } else if method_failed {
+        ExitCode::EditorFailed
+    } else {
+        ExitCode::Success
+    })
+}
+
+/// List the tests, or the compile errors stopping them being listed.
+fn list_tests(project_path: &Path, mode: unitool::TestMode, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Listing tests...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::list_tests(project_path, &run_options(global, &spinner), mode);
+    spinner.finish_and_clear();
+    let (errs, tests) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct ListTestsJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            tests: Option<&'a [unitool::discovery::DiscoveredTest]>,
+        }
+        print_json(&ListTestsJson {
+            errors: sorted_errors(&errs),
+            tests: tests.as_ref().map(|list| list.tests.as_slice()),
+        });
+    } else if let Some(tests) = &tests {
+        println!("{}", tests);
+    } else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(if tests.is_some() { ExitCode::Success } else { ExitCode::CompileFailed })
+}
+
+/// What `bundles` builds.
+enum BundleBuild<'a> {
+    /// AssetBundles, into the directory
+    AssetBundles(&'a Path),
+
+    /// Addressables content
+    Addressables,
+
+    /// An update of Addressables content, from the content state file
+    AddressablesUpdate(Option<&'a Path>),
+}
+
+fn bundles(project_path: &Path, target: unitool::build::BuildTarget, build: BundleBuild, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Building bundles...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let options = run_options(global, &spinner);
+    let result = match build {
+        BundleBuild::AssetBundles(out) => unitool::build_bundles(project_path, &options, target, out),
+        BundleBuild::Addressables => unitool::build_addressables(project_path, &options, target),
+        BundleBuild::AddressablesUpdate(state) => unitool::update_addressables(project_path, &options, target, state),
+    };
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct BundlesJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            report: Option<&'a unitool::bundles::BundleReport>,
+        }
+        print_json(&BundlesJson {
+            errors: sorted_errors(&errs),
+            report: report.as_ref(),
+        });
+    } else if let Some(report) = &report {
+        println!("{}", report);
+    } else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(match report {
+        None => ExitCode::CompileFailed,
+        Some(report) if !report.succeeded() => ExitCode::BuildFailed,
+        Some(_) => ExitCode::Success,
+    })
+}
+
+fn analyze_addressables(project_path: &Path, target: Option<unitool::build::BuildTarget>, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Analyzing Addressables...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::analyze_addressables(project_path, &run_options(global, &spinner), target);
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct AnalyzeJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            report: Option<&'a unitool::addressables::AnalyzeReport>,
+        }
+        print_json(&AnalyzeJson {
+            errors: sorted_errors(&errs),
+            report: report.as_ref(),
+        });
+    } else if let Some(report) = &report {
+        println!("{}", report);
+    } else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(match report {
+        None => ExitCode::CompileFailed,
+        Some(report) if !report.succeeded() => ExitCode::EditorFailed,
+        Some(report) if report.issues().next().is_some() => ExitCode::ValidationFailed,
+        Some(_) => ExitCode::Success,
+    })
+}
+
+fn smoke(project_path: &Path, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Opening project...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::smoke(project_path, &run_options(global, &spinner));
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct SmokeJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            report: &'a unitool::smoke::SmokeReport,
+        }
+        print_json(&SmokeJson {
+            errors: sorted_errors(&errs),
+            report: &report,
+        });
+    } else {
+        if !errs.is_empty() {
+            println!("{}", red(&format!("Compilation failed ({} errors)", errs.len())));
+            print_compile_errors(project_path, &errs, global);
+        }
+        if !report.passed() {
+            println!("{}", report);
+        }
+        let problems: Vec<String> = [("compile errors", errs.len())].into_iter()
+            .chain(report.categories())
+            .filter(|(_, count)| *count > 0)
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect();
+        if problems.is_empty() {
+            println!("{}", green("The project opens cleanly"));
+        } else {
+            println!("{}", red(&format!("The project doesn't open cleanly: {}", problems.join(", "))));
+        }
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(if !errs.is_empty() {
+        ExitCode::CompileFailed
+    } else if !report.passed() {
+        ExitCode::ValidationFailed
+    } else {
+        ExitCode::Success
+    })
+}
+
+fn new_project(project_path: &Path, version: Option<&str>, template: unitool::scaffold::Template, tests: bool, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Creating project...");
+    // Not locked, as the lock file would be in the
+    // project's directory, which must be empty
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::create_project(project_path, &run_options(global, &spinner), version, template, tests);
+    spinner.finish_and_clear();
+    let (errs, project) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct NewJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            project: &'a unitool::scaffold::NewProject,
+        }
+        print_json(&NewJson {
+            errors: sorted_errors(&errs),
+            project: &project,
+        });
+    } else {
+        let version = project.version.as_deref().unwrap_or("unknown version");
+        println!("{}", green(&format!("Created {} ({})", project.path.display(), version)));
+        for file in &project.tests {
+            let file = file.strip_prefix(&project.path).unwrap_or(file);
+            println!("  {}", muted(&file.display().to_string()));
+        }
+        if !errs.is_empty() {
+            println!("{}", red("It doesn't open cleanly; compilation failed"));
+            print_compile_errors(&project.path, &errs, global);
+        } else if !project.import.passed() {
+            println!("{}", red("It doesn't open cleanly"));
+            println!("{}", project.import);
+        }
+    }
+    if global.format().annotates() {
+        annotate_errors(&project.path, &errs, &global.format());
+    }
+    Ok(if !errs.is_empty() {
+        ExitCode::CompileFailed
+    } else if !project.import.passed() {
+        ExitCode::ValidationFailed
+    } else {
+        ExitCode::Success
+    })
+}
+
+fn package(project_path: &Path, cmd: PackageCommand, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner(match cmd {
+        PackageCommand::Export { .. } => "Exporting package...",
+        PackageCommand::Import { .. } => "Importing package...",
+    });
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let options = run_options(global, &spinner);
+    let result = match &cmd {
+        PackageCommand::Export { paths, out, .. } =>
+            unitool::export_package(project_path, &options, paths, out),
+        PackageCommand::Import { file, .. } =>
+            unitool::import_package(project_path, &options, file)
+                .map(|(errs, report)| (errs, Some(report))),
+    };
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct PackageJson<'a> {
+            errors: Vec<&'a unitool::compiler::CompileError>,
+            report: Option<&'a unitool::unitypackage::PackageReport>,
+        }
+        print_json(&PackageJson {
+            errors: sorted_errors(&errs),
+            report: report.as_ref(),
+        });
+    } else {
+        if let Some(report) = &report {
+            println!("{}", report);
+        }
+        if !errs.is_empty() {
+            println!("{}", red("Compilation failed"));
+            print_compile_errors(project_path, &errs, global);
+        }
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(match report {
+        _ if !errs.is_empty() => ExitCode::CompileFailed,
+        Some(report) if report.succeeded() => ExitCode::Success,
+        _ => ExitCode::EditorFailed,
+    })
+}
+
+fn build(project_path: &Path, target: unitool::build::BuildTarget, out: &Path, options: &unitool::build::BuildOptions, largest: usize, size_budget: Option<f64>, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Building...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::build(project_path, &run_options(global, &spinner), target, out, options);
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    let budget = size_budget.map(|mb| (mb * 1024. * 1024.) as u64);
+    let over_budget = report.as_ref()
+        .zip(budget)
+        .filter(|(report, budget)| report.succeeded() && report.total_size() > *budget);
+    if global.format() == OutputFormat::Json {
+        print_json(&BuildJson {
+            errors: sorted_errors(&errs),
+            report: report.as_ref(),
+        });
+    } else if let Some(report) = &report {
+        println!("{}", report);
+        match &report.sizes {
+            Some(sizes) if largest > 0 && !sizes.assets.is_empty() => println!("\n{}", sizes.largest(largest)),
+            _ => {},
+        }
+        if let Some((report, budget)) = over_budget {
+            println!("\n{}", red(&format!("The build is {} over its budget of {}",
+                unitool::build::format_size(report.total_size() - budget),
+                unitool::build::format_size(budget))));
+        }
+    } else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(match report {
+        _ if over_budget.is_some() => ExitCode::BuildFailed,
+        None => ExitCode::CompileFailed,
+        Some(report) if !report.succeeded() => ExitCode::BuildFailed,
+        Some(_) => ExitCode::Success,
+    })
+}
+
+fn burst_check(project_path: &Path, target: Option<unitool::build::BuildTarget>, global: &GlobalArgs) -> Outcome {
+    let target = target.or_else(unitool::build::BuildTarget::host)
+        .ok_or_else(|| (anyhow::anyhow!("No target for this platform; pass `-t`"), ExitCode::Error))?;
+    let spinner = spinner("Compiling with Burst...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let result = unitool::burst_check(project_path, &run_options(global, &spinner), target);
+    spinner.finish_and_clear();
+    let (errs, report) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    if global.format() == OutputFormat::Json {
+        print_json(&BuildJson {
+            errors: sorted_errors(&errs),
+            report: report.as_ref(),
+        });
+    } else {
+        match &report {
+            None => {
+                println!("{}", red("Compilation failed"));
+                print_compile_errors(project_path, &errs, global);
+            },
+            Some(report) if !report.burst_errors.is_empty() => {
+                let count = report.burst_errors.len();
+                println!("{}", red(&format!("{} Burst {}", count, if count == 1 { "error" } else { "errors" })));
+                for err in &report.burst_errors {
+                    println!("{}", err);
+                }
+            },
+            Some(report) if report.succeeded() => println!("{}", green("No Burst errors")),
+            // Failed for some other reason
+            Some(report) => println!("{}", report),
+        }
+    }
+    if global.format().annotates() {
+        annotate_errors(project_path, &errs, &global.format());
+    }
+    Ok(match report {
+        None => ExitCode::CompileFailed,
+        Some(report) if !report.succeeded() || !report.burst_errors.is_empty() => ExitCode::BuildFailed,
+        Some(_) => ExitCode::Success,
+    })
+}
+
+/// List the tests and prompt for which to run,
+/// returning a `-testFilter` for them.
+fn pick_tests(project_path: &Path, mode: unitool::TestMode, global: &GlobalArgs) -> Result<String, (anyhow::Error, ExitCode)> {
+    let names = list_test_names(project_path, mode, global)?;
+    let picked = unitool::picker::pick("Tests to run:", &names)
+        .map_err(|err| (err, ExitCode::Error))?;
+    Ok(picked.join(";"))
+}
+
+/// The full names of the mode's tests, exiting if compilation fails.
+fn list_test_names(project_path: &Path, mode: unitool::TestMode, global: &GlobalArgs) -> Result<Vec<String>, (anyhow::Error, ExitCode)> {
+    let spinner = spinner("Listing tests...");
+    let result = {
+        let _lock = lock_project(&spinner, project_path, global.lock)
+            .map_err(|err| (err, ExitCode::Error))?;
+        let _seat = wait_for_seat(&spinner, global.license_seats)
+            .map_err(|err| (err, ExitCode::Error))?;
+        unitool::list_tests(project_path, &run_options(global, &spinner), mode)
+    };
+    spinner.finish_and_clear();
+    let (errs, tests) = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    let Some(tests) = tests else {
+        println!("{}", red("Compilation failed"));
+        print_compile_errors(project_path, &errs, global);
+        if global.format().annotates() {
+            annotate_errors(project_path, &errs, &global.format());
+        }
+        ExitCode::CompileFailed.exit();
+    };
+    if tests.tests.is_empty() {
+        return Err((anyhow::anyhow!("No tests were found"), ExitCode::Error));
+    }
+
+    Ok(tests.tests.iter().map(|test| test.full_name.clone()).collect())
+}
+
+/// List or change the project's packages,
+/// optionally resolving them in the editor after.
+fn pkg(cmd: PkgCommand, global: &GlobalArgs) -> Outcome {
+    use unitool::packages;
+    let project_path = cmd.project_path().to_path_buf();
+    let (changes, resolve) = match &cmd {
+        PkgCommand::List { .. } => {
+            let packages = packages::list(&project_path).map_err(|err| (err, ExitCode::Error))?;
+            if global.format() == OutputFormat::Json {
+                print_json(&packages);
+            } else {
+                println!("{}", packages);
+            }
+            return Ok(ExitCode::Success);
+        },
+        PkgCommand::Add { packages: specs, resolve, .. } => (packages::add(&project_path, specs), resolve.resolve),
+        PkgCommand::Remove { packages: names, resolve, .. } => (packages::remove(&project_path, names), resolve.resolve),
+        PkgCommand::Update { packages: specs, resolve, .. } => (packages::update(&project_path, specs), resolve.resolve),
+    };
+    let changes = changes.map_err(|err| (err, ExitCode::Error))?;
+
+    let errs = if resolve {
+        let spinner = spinner("Resolving packages...");
+        let _lock = lock_project(&spinner, &project_path, global.lock)
+            .map_err(|err| (err, ExitCode::Error))?;
+        let _seat = wait_for_seat(&spinner, global.license_seats)
+            .map_err(|err| (err, ExitCode::Error))?;
+        let result = unitool::compile(&project_path, &run_options(global, &spinner));
+        spinner.finish_and_clear();
+        Some(result.map_err(|err| (err, ExitCode::EditorFailed))?.errors)
+    } else {
+        None
+    };
+
+    if global.format() == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct PkgJson<'a> {
+            changes: &'a [packages::PackageChange],
+            errors: Option<Vec<&'a unitool::compiler::CompileError>>,
+        }
+        print_json(&PkgJson {
+            changes: &changes,
+            errors: errs.as_ref().map(sorted_errors),
+        });
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+        match &errs {
+            Some(errs) if errs.is_empty() => println!("{}", green("Packages resolved")),
+            Some(errs) => {
+                println!("{}", red("Compilation failed"));
+                print_compile_errors(&project_path, errs, global);
+            },
+            None => {},
+        }
+    }
+    if let Some(errs) = errs.as_ref().filter(|_| global.format().annotates()) {
+        annotate_errors(&project_path, errs, &global.format());
+    }
+    Ok(match errs {
+        Some(errs) if !errs.is_empty() => ExitCode::CompileFailed,
+        _ => ExitCode::Success,
+    })
+}
+
+/// Activate or return a license, or show its status.
+fn license(cmd: LicenseCommand, project_path: Option<&Path>, global: &GlobalArgs) -> Outcome {
+    use unitool::license;
+    if let LicenseCommand::Status = cmd {
+        let status = license::status();
+        if global.format() == OutputFormat::Json {
+            print_json(&status);
+        } else {
+            println!("{}", status);
+        }
+        return Ok(if status.activated { ExitCode::Success } else { ExitCode::Error });
+    }
+
+    let spinner = spinner("Running...");
+    let options = run_options(global, &spinner);
+    let result = match cmd {
+        LicenseCommand::Status => unreachable!("handled above"),
+        LicenseCommand::Activate { manual: true, .. } => {
+            spinner.set_message("Creating an activation file...");
+            license::create_activation_file(project_path, &options)
+                .map(|path| format!("Wrote {}; upload it at https://license.unity3d.com/manual, then run:\n  unitool license activate --license-file <file.ulf>",
+                    path.display()))
+        },
+        LicenseCommand::Activate { license_file: Some(file), .. } => {
+            spinner.set_message("Activating...");
+            license::activate_with_file(project_path, &options, &file)
+                .map(|_| "License activated".to_string())
+        },
+        LicenseCommand::Activate { serial, account, .. } => {
+            let serial = serial.or_else(|| std::env::var("UNITY_SERIAL").ok())
+                .ok_or_else(|| anyhow::anyhow!("Pass `--serial` (or set UNITY_SERIAL), `--license-file` or `--manual`"))
+                .map_err(|err| (err, ExitCode::Error))?;
+            let credentials = account.credentials().map_err(|err| (err, ExitCode::Error))?;
+            spinner.set_message("Activating...");
+            license::activate_with_serial(project_path, &options, &serial, &credentials)
+                .map(|_| "License activated".to_string())
+        },
+        LicenseCommand::Return { account } => {
+            let credentials = account.credentials().map_err(|err| (err, ExitCode::Error))?;
+            spinner.set_message("Returning the license...");
+            license::return_license(project_path, &options, &credentials)
+                .map(|_| "License returned".to_string())
+        },
+    };
+    spinner.finish_and_clear();
+    let msg = result.map_err(|err| (err, ExitCode::EditorFailed))?;
+    println!("{}", green(&msg));
+    Ok(ExitCode::Success)
+}
+
+/// Run the tests repeatedly and report any flaky tests.
+fn repeat_tests(project_path: &Path, mode: unitool::TestMode, selection: &TestSelection, platform: Option<&unitool::build::BuildTarget>, runs: u32, random_order: bool, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Compiling and running tests...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    let start = random_seed();
+    let mut test_options = unitool::TestOptions { platform: platform.cloned(), ..Default::default() };
+    let mut results = vec![];
+    let mut seeds = vec![];
+    for run in 0..runs {
+        spinner.set_message(format!("Running tests (run {}/{})...", run + 1, runs));
+        let options = run_options(global, &spinner);
+        if random_order {
+            let seed = start.wrapping_add(run.wrapping_mul(7919)) % i32::MAX as u32;
+            test_options.random_order_seed = Some(seed);
+            seeds.push(seed);
+        }
+        let result = unitool::test(project_path, &options, mode.clone(),
+            selection.assemblies.as_deref(),
+            selection.filters.clone(), selection.categories.clone(), &test_options);
+        let (errs, summary) = result.map_err(|err| {
+            spinner.finish_and_clear();
+            (err, ExitCode::EditorFailed)
+        })?;
+        let Some(summary) = summary else {
+            spinner.finish_and_clear();
+            if global.format() == OutputFormat::Json {
+                print_json(&TestJson { errors: sorted_errors(&errs), results: None, coverage: None, baseline: None });
+            } else {
+                println!("{}", red("Compilation failed"));
+                print_compile_errors(project_path, &errs, global);
+            }
+            return Ok(ExitCode::CompileFailed);
+        };
+        results.push(summary);
+    }
+    spinner.finish_and_clear();
+
+    let report = unitool::flaky::FlakyReport::new(&results, seeds);
+    if global.format() == OutputFormat::Json {
+        print_json(&report);
+    } else {
+        println!("{}", report);
+    }
+    Ok(if report.passed() { ExitCode::Success } else { ExitCode::TestsFailed })
+}
+
+/// A seed for `-randomOrderSeed`, from the clock.
+fn random_seed() -> u32 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.subsec_nanos())
+        .unwrap_or(0);
+    nanos % i32::MAX as u32
+}
+
+/// Run the tests in a random order and, if a test fails that passes
+/// on its own, bisect the tests that ran before it for the culprit.
+fn bisect_order_dependence(project_path: &Path, mode: unitool::TestMode, selection: &TestSelection, test_options: &unitool::TestOptions, seed: u32, global: &GlobalArgs) -> Outcome {
+    let spinner = spinner("Compiling and running tests...");
+    let _lock = lock_project(&spinner, project_path, global.lock)
+        .map_err(|err| (err, ExitCode::Error))?;
+    let _seat = wait_for_seat(&spinner, global.license_seats)
+        .map_err(|err| (err, ExitCode::Error))?;
+
+    // Runs the selection in the seed's order, or just these
+    // tests in the order given, returning the results
+    let run = |tests: Option<&[String]>, message: String| {
+        spinner.set_message(message);
+        let (filters, test_options) = match tests {
+            Some(tests) => {
+                let order_file = unitool::bisect::save_order(project_path, tests)
+                    .map_err(|err| (err, ExitCode::Error))?;
+                (Some(tests.join(";")), unitool::TestOptions {
+                    random_order_seed: None,
+                    order_file: Some(order_file),
+                    ..test_options.clone()
+                })
+            },
+            None => (selection.filters.clone(), test_options.clone()),
+        };
+        let result = unitool::test(project_path, &run_options(global, &spinner), mode.clone(),
+            selection.assemblies.as_deref(), filters, selection.categories.clone(), &test_options);
+        match result {
+            Ok((_, Some(results))) => Ok(results),
+            Ok((errs, None)) => Err((anyhow::anyhow!("Compilation failed with {} errors", errs.len()), ExitCode::CompileFailed)),
+            Err(err) => Err((err, ExitCode::EditorFailed)),
+        }
+    };
+    let failed = |results: &unitool::TestSummary, name: &str| results.iter_cases()
+        .any(|case| case.key() == name && case.failed());
+
+    let results = run(None, format!("Running tests in random order (seed {})...", seed))
+        .inspect_err(|_| spinner.finish_and_clear())?;
+    // In the order they ran, as the results list them by fixture
+    let mut cases: Vec<_> = results.iter_cases().collect();
+    cases.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+    let order: Vec<String> = cases.iter().map(|case| case.key().to_string()).collect();
+    let failures: Vec<String> = results.failures().iter().map(|case| case.key().to_string()).collect();
+
+    // The first failure that passes on its own
+    let mut failing_alone = vec![];
+    let mut target = None;
+    for name in &failures {
+        let alone = run(Some(std::slice::from_ref(name)), format!("Running {} on its own...", name))
+            .inspect_err(|_| spinner.finish_and_clear())?;
+        if failed(&alone, name) {
+            failing_alone.push(name.clone());
+        } else {
+            target = Some(name.clone());
+            break;
+        }
+    }
+    let Some(target) = target else {
+        spinner.finish_and_clear();
+        if failures.is_empty() {
+            println!("{}", green(&format!("All tests passed in random order (seed {})", seed)));
+            return Ok(ExitCode::Success);
+        }
+        println!("{}", red(&format!("{} tests failed, but fail on their own too, so don't depend on the order:", failing_alone.len())));
+        for name in &failing_alone {
+            println!("  {}", name);
+        }
+        return Ok(ExitCode::TestsFailed);
+    };
+
+    let position = order.iter().position(|name| *name == target).unwrap_or(0);
+    let before = order[..position].to_vec();
+    let exit_code = std::cell::Cell::new(ExitCode::Error);
+    let bisection = unitool::bisect::bisect(&target, seed, before, |tests| {
+        let mut tests = tests.to_vec();
+        tests.push(target.clone());
+        let message = format!("Running {} with {} tests before it...", target, tests.len() - 1);
+        let results = run(Some(&tests), message).map_err(|(err, code)| {
+            exit_code.set(code);
+            err
+        })?;
+        Ok(failed(&results, &target))
+    }).map_err(|err| {
+        spinner.finish_and_clear();
+        (err, exit_code.get())
+    })?;
+    spinner.finish_and_clear();
+    if global.format() == OutputFormat::Json {
+        print_json(&bisection);
+    } else {
+        println!("{}", bisection);
+    }
+    Ok(ExitCode::TestsFailed)
+}
+
+#[derive(Serialize)]
+struct CompileJson<'a> {
+    errors: Vec<&'a unitool::compiler::CompileError>,
+    warnings: Vec<&'a String>,
+    exceptions: &'a [unitool::log::LogException],
+    shaders: &'a unitool::shaders::ShaderDiagnostics,
+    ratchet: Option<&'a unitool::ratchet::RatchetOutcome>,
+    cached: bool,
+    attempts: u32,
+}
+
+#[derive(Serialize)]
+struct TestJson<'a> {
+    errors: Vec<&'a unitool::compiler::CompileError>,
+    results: Option<&'a unitool::TestSummary>,
+    coverage: Option<&'a unitool::coverage::CoverageReport>,
+    baseline: Option<&'a unitool::baseline::BaselineOutcome>,
+}
+
+#[derive(Serialize)]
+struct ExecJson<'a> {
+    errors: Vec<&'a unitool::compiler::CompileError>,
+    exit_code: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct BuildJson<'a> {
+    errors: Vec<&'a unitool::compiler::CompileError>,
+    report: Option<&'a unitool::build::BuildReport>,
+}
+
+/// Compile errors in a stable order.
+fn sorted_errors(errs: &unitool::CompileErrors) -> Vec<&unitool::compiler::CompileError> {
+    let mut errs: Vec<_> = errs.iter().collect();
+    errs.sort();
+    errs
+}
+
+/// Split the command line at the first `--`, as clap would
+/// otherwise take what follows as positional arguments.
+fn split_editor_args() -> (Vec<std::ffi::OsString>, Vec<String>) {
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    match args.iter().position(|arg| arg == "--") {
+        Some(idx) => {
+            let editor_args = args.split_off(idx).into_iter().skip(1)
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect();
+            (args, editor_args)
+        },
+        None => (args, vec![]),
+    }
+}
+
+/// `build` took where to write the build as `--output`, before that
+/// was the output format, so keep scripts passing it working by
+/// taking an `--output` that isn't a format as `--out`.
+fn build_output_args(mut args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    let Some(start) = args.iter().skip(1).position(|arg| arg == "build") else { return args };
+    let is_format = |value: &str| <OutputFormat as clap::ValueEnum>::from_str(value, true).is_ok();
+    for i in start + 2..args.len() {
+        let arg = args[i].to_string_lossy().to_string();
+        if arg == "--output" && args.get(i + 1).is_some_and(|value| !is_format(&value.to_string_lossy())) {
+            args[i] = "--out".into();
+        } else if let Some(value) = arg.strip_prefix("--output=").filter(|value| !is_format(value)) {
+            args[i] = format!("--out={}", value).into();
+        }
+    }
+    args
+}
+
+fn main() -> std::process::ExitCode {
+    let (args, editor_args) = split_editor_args();
+    let args = build_output_args(args);
+    let Args { cmd, mut global } = Args::parse_from(args);
+    global.editor_args = editor_args;
+    let config = match (&global.config, cmd.project_path()) {
+        (Some(path), _) => Config::load(path),
+        (None, Some(project_path)) => Config::for_project(project_path),
+        (None, None) => Ok(Config::default()),
+    }
+        .and_then(|config| global.apply(&config).map(|_| config));
+    set_color_mode(global.color.unwrap_or_default());
+    let config = config.unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+    let outcome = match cmd {
+        SubCommand::Compile { project_path, ratchet, warnings, warnings_as_errors, cached } => {
+            let warning_options = WarningOptions { show: warnings, as_errors: warnings_as_errors };
+            let mut recorded = None;
+            let outcome = compile(&project_path, ratchet, warning_options, cached, &mut recorded, &global);
+            notify(&project_path, "compile", &outcome, recorded.as_ref(), &global);
+            outcome
+        },
+        SubCommand::Test { project_path, mode, mut selection, output, coverage, results_out, platform, failed, interactive, shard, repeat, random_order, order_file, failed_first, shuffle, bisect_order, update_baseline } => {
+            selection.apply(&config.test);
+            if failed {
+                match unitool::rerun::failures_filter(&project_path) {
+                    Ok(Some(filter)) => selection.filters = Some(filter),
+                    Ok(None) => {
+                        println!("{}", green("No tests failed in the last run"));
+                        return ExitCode::Success.into();
+                    },
+                    Err(err) => fail(err, &global, ExitCode::Error),
+                }
+            }
+            let Some(mode) = mode.or(config.test.mode.clone()) else {
+                fail(anyhow::anyhow!("No test mode given; pass `-m` or set `mode` under `[test]` in {}",
+                    unitool::config::CONFIG_FILE), &global, ExitCode::Error);
+            };
+            if interactive {
+                match pick_tests(&project_path, mode.clone(), &global) {
+                    Ok(filter) => selection.filters = Some(filter),
+                    Err((err, code)) => fail(err, &global, code),
+                }
+            }
+            if let Some(shard) = shard {
+                let names = list_test_names(&project_path, mode.clone(), &global)
+                    .unwrap_or_else(|(err, code)| fail(err, &global, code));
+                let selected = shard.select(names.iter().map(|name| name.as_str()));
+                if selected.is_empty() {
+                    println!("{}", green(&format!("No tests are in shard {}", shard)));
+                    return ExitCode::Success.into();
+                }
+                selection.filters = Some(selected.join(";"));
+            }
+            let random_order_seed = shuffle.map(|seed| seed.unwrap_or_else(random_seed));
+            let order_file = match failed_first {
+                true => unitool::rerun::save_failures_order(&project_path)
+                    .unwrap_or_else(|err| fail(err, &global, ExitCode::Error)),
+                false => order_file,
+            };
+            let test_options = unitool::TestOptions { coverage, results_out, platform, random_order_seed, order_file };
+            let mut recorded = None;
+            let outcome = match (repeat, random_order_seed) {
+                (Some(runs), _) => repeat_tests(&project_path, mode, &selection, test_options.platform.as_ref(), runs, random_order, &global),
+                (None, Some(seed)) if bisect_order => bisect_order_dependence(&project_path, mode, &selection, &test_options, seed, &global),
+                (None, _) => test(&project_path, mode, &selection, &output, &test_options, update_baseline, &mut recorded, &global),
+            };
+            notify(&project_path, "test", &outcome, recorded.as_ref(), &global);
+            outcome
+        },
+        SubCommand::Serve { port } => {
+            let served = unitool::server::serve(serve_options(&global, port), |addr, token_path| {
+                eprintln!("{}", muted(&format!("Listening on {}, with the token in {}", addr, token_path.display())));
+            });
+            served.map(|_| ExitCode::Success).map_err(|err| (err, ExitCode::Error))
+        },
+        SubCommand::Mcp => {
+            unitool::mcp::serve(serve_options(&global, 0))
+                .map(|_| ExitCode::Success)
+                .map_err(|err| (err, ExitCode::Error))
+        },
+        SubCommand::Watch { project_path, test: run_tests, mode, mut selection } => {
+            selection.apply(&config.test);
+            let mode = mode.or(config.test.mode.clone()).unwrap_or(unitool::TestMode::EditMode);
+            let mut watcher = unitool::watch::Watcher::new(&project_path);
+            loop {
+                let outcome = if run_tests {
+                    test(&project_path, mode.clone(), &selection, &Default::default(), &Default::default(), false, &mut None, &global)
+                } else {
+                    compile(&project_path, None, WarningOptions::default(), false, &mut None, &global)
+                };
+                if let Err((err, _)) = outcome {
+                    report_error(err, &global.format());
+                }
+
+                if global.format() != OutputFormat::Json {
+                    println!("{}", muted("Watching for changes..."));
+                }
+                let changed = watcher.wait();
+                if global.format() != OutputFormat::Json {
+                    for path in changed.iter().take(5) {
+                        let path = path.strip_prefix(&project_path).unwrap_or(path);
+                        println!("{}", muted(&format!("Changed: {}", path.display())));
+                    }
+                    if changed.len() > 5 {
+                        println!("{}", muted(&format!("...and {} more", changed.len() - 5)));
+                    }
+                }
+            }
+        },
+        SubCommand::Build { project_path, target, out, development, scripting_backend, strip_level, largest, size_budget } => {
+            let options = unitool::build::BuildOptions { development, scripting_backend, strip_level };
+            build(&project_path, target, &out, &options, largest, size_budget, &global)
+        },
+        SubCommand::BurstCheck { project_path, target } => {
+            burst_check(&project_path, target, &global)
+        },
+        SubCommand::Bundles { project_path, target, out } => {
+            let out = out.unwrap_or_else(|| project_path.join("AssetBundles").join(target.arg()));
+            bundles(&project_path, target, BundleBuild::AssetBundles(&out), &global)
+        },
+        SubCommand::Addressables { cmd: AddressablesCommand::Build { project_path, target } } => {
+            bundles(&project_path, target, BundleBuild::Addressables, &global)
+        },
+        SubCommand::Addressables { cmd: AddressablesCommand::Update { project_path, target, state } } => {
+            let build = BundleBuild::AddressablesUpdate(state.as_deref());
+            bundles(&project_path, target, build, &global)
+        },
+        SubCommand::Addressables { cmd: AddressablesCommand::Analyze { project_path, target } } => {
+            analyze_addressables(&project_path, target, &global)
+        },
+        SubCommand::Smoke { project_path } => {
+            smoke(&project_path, &global)
+        },
+        SubCommand::New { project_path, version, template, tests } => {
+            new_project(&project_path, version.as_deref(), template, tests, &global)
+        },
+        SubCommand::Package { cmd } => {
+            let project_path = cmd.project_path().to_path_buf();
+            package(&project_path, cmd, &global)
+        },
+        SubCommand::Exec { project_path, method, args } => {
+            exec(&project_path, &method, &args, &global)
+        },
+        SubCommand::ListTests { project_path, mode } => {
+            list_tests(&project_path, mode, &global)
+        },
+        SubCommand::Open { project_path, scene } => {
+            match unitool::open(&project_path, global.unity_path.as_deref(), scene.as_deref()) {
+                Ok(editor) => println!("{}", muted(&format!("Opened with {}", editor.display()))),
+                Err(err) => fail(err, &global, ExitCode::EditorFailed),
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Cleanup { project_path } => {
+            match unitool::cleanup::cleanup(&project_path) {
+                Ok(cleanup) if global.format() == OutputFormat::Json => print_json(&cleanup),
+                Ok(cleanup) => println!("{}", cleanup),
+                Err(err) => fail(err, &global, ExitCode::Error),
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Check { project_path, tests } => {
+            check(&project_path, tests, &global)
+        },
+        SubCommand::Workspace { cmd: WorkspaceCommand::Compile { workspace: args } } => {
+            workspace(&args, None, &global)
+        },
+        SubCommand::Workspace { cmd: WorkspaceCommand::Test { workspace: args, mode, selection } } => {
+            workspace(&args, Some((mode, &selection)), &global)
+        },
+        SubCommand::Hook { cmd: HookCommand::Install { project_path, hook, tests, force } } => {
+            match unitool::hooks::install(&project_path, hook, tests, force) {
+                Ok(path) => println!("{}", green(&format!("Installed the {} hook at {}", hook.name(), path.display()))),
+                Err(err) => fail(err, &global, ExitCode::Error),
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Hook { cmd: HookCommand::Uninstall { project_path, hook } } => {
+            match unitool::hooks::uninstall(&project_path, hook) {
+                Ok(Some(path)) => println!("Removed {}", path.display()),
+                Ok(None) => println!("{}", muted(&format!("There's no {} hook", hook.name()))),
+                Err(err) => fail(err, &global, ExitCode::Error),
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Validate { project_path, regenerate_guids } => {
+            let validate = || unitool::validate::validate(&project_path)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            let mut report = validate();
+            if regenerate_guids {
+                let duplicates = report.issues.iter()
+                    .filter(|issue| issue.kind == unitool::validate::IssueKind::DuplicateGuid);
+                for issue in duplicates {
+                    let guid = unitool::validate::regenerate_guid(&project_path.join(&issue.path))
+                        .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+                    if global.format() != OutputFormat::Json {
+                        println!("{}", muted(&format!("Gave {} the new GUID {}", issue.path.display(), guid)));
+                    }
+                }
+                report = validate();
+            }
+            if global.format() == OutputFormat::Json {
+                print_json(&report);
+            } else {
+                println!("{}", report);
+            }
+            match report.passed() {
+                true => Ok(ExitCode::Success),
+                false => Ok(ExitCode::ValidationFailed),
+            }
+        },
+        SubCommand::ListAssemblies { project_path } => {
+            match unitool::assemblies::discover(&project_path) {
+                Ok(assemblies) if global.format() == OutputFormat::Json => print_json(&assemblies),
+                Ok(assemblies) => println!("{}", assemblies),
+                Err(err) => fail(err, &global, ExitCode::Error),
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Doctor { project_path, target } => {
+            let diagnosis = unitool::doctor::diagnose(project_path.as_deref(), global.unity_path.as_deref(), &target);
+            if global.format() == OutputFormat::Json {
+                print_json(&diagnosis);
+            } else {
+                println!("{}", diagnosis);
+            }
+            match diagnosis.failed() {
+                true => Ok(ExitCode::Error),
+                false => Ok(ExitCode::Success),
+            }
+        },
+        SubCommand::Explain { code } => {
+            let Some(explanation) = unitool::explain::explain(&code) else {
+                fail(anyhow::anyhow!("There's no explanation for `{}`", code), &global, ExitCode::Error);
+            };
+            if global.format() == OutputFormat::Json {
+                print_json(explanation);
+            } else {
+                println!("{}", explanation);
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::ListEditors { project_path } => {
+            let editors = unitool::editors::installed_editors();
+            let selected = project_path
+                .map(|path| unitool::find_unity_path(&path, global.unity_path.as_deref()))
+                .transpose();
+            if global.format() == OutputFormat::Json {
+                #[derive(Serialize)]
+                struct EditorsJson<'a> {
+                    editors: &'a [unitool::editors::Editor],
+                    selected: Option<&'a Path>,
+                }
+                if let Ok(selected) = &selected {
+                    print_json(&EditorsJson { editors: &editors, selected: selected.as_deref() });
+                }
+            } else {
+                print_editors(&editors, selected.as_ref().ok().and_then(|path| path.as_deref()));
+            }
+            selected.map(|_| ExitCode::Success).map_err(|err| (err, ExitCode::Error))
+        },
+        SubCommand::Logs { project_path, limit, tail } => {
+            let mut logs = unitool::logs::list(&project_path)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            match tail {
+                Some(lines) => {
+                    let Some(latest) = logs.last() else {
+                        fail(anyhow::anyhow!("No logs have been saved for {}", project_path.display()),
+                            &global, ExitCode::Error);
+                    };
+                    let tail = unitool::logs::tail(&latest.path, lines)
+                        .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+                    if global.format() == OutputFormat::Json {
+                        print_json(&tail);
+                    } else {
+                        eprintln!("{}", muted(&latest.path.display().to_string()));
+                        println!("{}", tail.join("\n"));
+                    }
+                },
+                None => {
+                    logs.drain(..logs.len().saturating_sub(limit));
+                    let logs = unitool::logs::SavedLogs(logs);
+                    if global.format() == OutputFormat::Json {
+                        print_json(&logs);
+                    } else {
+                        println!("{}", logs);
+                    }
+                },
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::History { project_path, limit } => {
+            let mut runs = unitool::history::load(&project_path)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            runs.drain(..runs.len().saturating_sub(limit));
+            let history = unitool::history::History(runs);
+            if global.format() == OutputFormat::Json {
+                print_json(&history);
+            } else {
+                println!("{}", history);
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Trends { project_path, limit, filter } => {
+            let mut runs = unitool::history::load(&project_path)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            runs.retain(|run| !run.tests.is_empty());
+            runs.drain(..runs.len().saturating_sub(limit));
+            let trends = unitool::history::Trends::new(&runs, filter.as_deref());
+            if global.format() == OutputFormat::Json {
+                print_json(&trends);
+            } else {
+                println!("{}", trends);
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Diff { results, against_last } => {
+            let diff = diff_runs(&results, against_last.as_deref())
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            match global.format() {
+                OutputFormat::Json => print_json(&diff),
+                OutputFormat::Markdown => println!("{}", unitool::markdown::test_diff(&diff)),
+                _ => println!("{}", diff),
+            }
+            match diff.regressed() {
+                true => Ok(ExitCode::TestsFailed),
+                false => Ok(ExitCode::Success),
+            }
+        },
+        SubCommand::Hotspots { results, by } => {
+            let report = unitool::hotspots(&results, by)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            println!("{}", report);
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Report { results: Some(results), output, .. } => {
+            let results = unitool::load_results(&results)
+                .unwrap_or_else(|err| fail(err, &global, ExitCode::Error));
+            report(results, &output, &global)
+        },
+        SubCommand::Report { cmd: Some(ReportCommand::Merge { results, output }), .. }
+            | SubCommand::MergeResults { results, output } => merge_results(&results, &output, &global),
+        SubCommand::Report { .. } => unreachable!("a results file is required without a subcommand"),
+        SubCommand::Pkg { cmd } => {
+            pkg(cmd, &global)
+        },
+        SubCommand::License { cmd, project } => {
+            license(cmd, project.as_deref(), &global)
+        },
+        SubCommand::Completions { shell } => {
+            println!("{}", unitool::completions::generate(shell, Args::command()));
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Versions => {
+            for editor in unitool::editors::installed_editors() {
+                println!("{}", editor.version);
+            }
+            Ok(ExitCode::Success)
+        },
+        SubCommand::Modules { cmd: ModulesCommand::Install { version, modules } } => {
+            match unitool::hub::install_modules(&version, &modules) {
+                Ok(()) => println!("{}", green("Modules installed")),
+                Err(err) => fail(err, &global, ExitCode::Error),
+            }
+            Ok(ExitCode::Success)
+        },
+    };
+    finish(outcome, &global)
+}
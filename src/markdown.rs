@@ -0,0 +1,153 @@
+//! Rendering results as GitHub-flavored Markdown, compact
+//! enough for a PR comment or `$GITHUB_STEP_SUMMARY`.
+
+use crate::{CompileErrors, CompileWarnings, compiler::group_warnings, diff::TestDiff, testing::TestSummary};
+
+/// The most failures to include, to keep
+/// comments under GitHub's size limit.
+const MAX_FAILURES: usize = 50;
+
+/// A table of fixtures with their counts, followed
+/// by a collapsible block for each failure.
+pub fn test_results(summary: &TestSummary) -> String {
+    let fixtures = summary.fixtures();
+    let (passed, failed, skipped, duration) = fixtures.iter()
+        .fold((0, 0, 0, 0.), |(p, f, s, d), suite| {
+            (p + suite.passed, f + suite.failed, s + suite.skipped, d + suite.duration)
+        });
+
+    let headline = if failed > 0 { "❌ Tests failed" } else { "✅ Tests passed" };
+    let mut lines = vec![
+        format!("### {}", headline),
+        "".to_string(),
+        format!("{} passed, {} failed, {} skipped in {:.2}s", passed, failed, skipped, duration),
+    ];
+    if summary.truncated() {
+        lines.push("".to_string());
+        lines.push("> [!WARNING]".to_string());
+        lines.push("> The results were truncated, some tests may be missing.".to_string());
+    }
+
+    if !fixtures.is_empty() {
+        lines.push("".to_string());
+        lines.push("| | Suite | Passed | Failed | Skipped | Duration |".to_string());
+        lines.push("|---|---|--:|--:|--:|--:|".to_string());
+        for suite in &fixtures {
+            let status = if suite.failed > 0 { "❌" } else if suite.passed == suite.total { "✅" } else { "⚪" };
+            lines.push(format!("| {} | {} | {} | {} | {} | {:.2}s |",
+                status, escape_cell(&suite.name), suite.passed, suite.failed, suite.skipped, suite.duration));
+        }
+    }
+
+    let failures: Vec<_> = summary.cases().into_iter()
+        .filter(|case| case.failed())
+        .collect();
+    if !failures.is_empty() {
+        lines.push("".to_string());
+        lines.push("#### Failures".to_string());
+    }
+    for case in failures.iter().take(MAX_FAILURES) {
+        let mut details = vec![];
+        details.extend(case.message().map(|msg| msg.trim().to_string()));
+        details.extend(case.stack_trace().map(|trace| trace.trim().to_string()));
+        lines.push("".to_string());
+        lines.push(format!("<details><summary><code>{}</code></summary>", escape_html(case.key())));
+        lines.push("".to_string());
+        lines.push(code_block(&details.join("\n\n")));
+        lines.push("".to_string());
+        lines.push("</details>".to_string());
+    }
+    if failures.len() > MAX_FAILURES {
+        lines.push("".to_string());
+        lines.push(format!("…and {} more failures", failures.len() - MAX_FAILURES));
+    }
+    lines.join("\n")
+}
+
+/// A list of compile errors, by file and line, if any.
+pub fn compile_errors(errors: &CompileErrors) -> String {
+    if errors.is_empty() {
+        return "### ✅ Compilation succeeded".to_string();
+    }
+    let mut errors: Vec<_> = errors.iter().collect();
+    errors.sort();
+    let mut lines = vec![
+        "### ❌ Compilation failed".to_string(),
+        "".to_string(),
+    ];
+    for err in errors {
+        let location = match (&err.file, err.line) {
+            (Some(file), Some(line)) => format!("`{}:{}` ", file, line),
+            (Some(file), None) => format!("`{}` ", file),
+            _ => "".to_string(),
+        };
+        let message = match &err.code {
+            Some(code) => format!("**{}** {}", code, err.message),
+            None => err.message.clone(),
+        };
+        lines.push(format!("- {}{}", location, message));
+    }
+    lines.join("\n")
+}
+
+/// A collapsible list of warnings, grouped as in the text output.
+pub fn warnings(warnings: &CompileWarnings) -> String {
+    if warnings.is_empty() {
+        return "".to_string();
+    }
+    let mut lines = vec![
+        format!("<details><summary>{} warnings</summary>", warnings.len()),
+        "".to_string(),
+    ];
+    for group in group_warnings(warnings) {
+        let count = if group.count > 1 { format!(" ×{}", group.count) } else { "".to_string() };
+        lines.push(format!("- **{}** {}{}", group.code.as_deref().unwrap_or("warning"), group.message, count));
+    }
+    lines.push("".to_string());
+    lines.push("</details>".to_string());
+    lines.join("\n")
+}
+
+/// What changed between two test runs, newly failing tests first.
+pub fn test_diff(diff: &TestDiff) -> String {
+    let headline = if diff.regressed() { "❌ Tests regressed" } else { "✅ No regressions" };
+    let mut lines = vec![format!("### {}", headline)];
+    let mut section = |title: &str, items: Vec<String>| {
+        if items.is_empty() {
+            return;
+        }
+        lines.push("".to_string());
+        lines.push(format!("#### {} ({})", title, items.len()));
+        lines.push("".to_string());
+        lines.extend(items.iter().take(MAX_FAILURES).map(|item| format!("- {}", item)));
+        if items.len() > MAX_FAILURES {
+            lines.push(format!("- …and {} more", items.len() - MAX_FAILURES));
+        }
+    };
+    let code = |name: &String| format!("`{}`", name);
+    section("Newly failing", diff.newly_failing.iter().map(code).collect());
+    section("Newly passing", diff.newly_passing.iter().map(code).collect());
+    section("Added", diff.added.iter()
+        .map(|entry| format!("{} ({:?})", code(&entry.name), entry.result))
+        .collect());
+    section("Removed", diff.removed.iter().map(|entry| code(&entry.name)).collect());
+    lines.join("\n")
+}
+
+/// A fenced code block, with a fence longer than
+/// any run of backticks in the text.
+fn code_block(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(|run| run.len()).max().unwrap_or(0);
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{}\n{}\n{}", fence, text, fence)
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,297 @@
+//! A Model Context Protocol server over stdio, so coding assistants
+//! can compile the project, run its tests and read results files as
+//! tools, getting back compile errors and test failures as JSON.
+//!
+//! Requests are handled one at a time, in order; runs send
+//! `notifications/progress` if the request has a progress token.
+
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{io::{self, BufRead, Write}, path::{Path, PathBuf}, sync::Mutex};
+use crate::{
+    TestCase, TestMode, TestSummary, cache, compiler::CompileError,
+    editors::{self, Editor}, events::RunEvent, json::{self, Value},
+    lock::{self, LockMode}, server::ServeOptions,
+};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const TOOLS: &str = r#"[
+  {
+    "name": "compile",
+    "description": "Compile the Unity project's scripts, returning any compile errors and warnings.",
+    "inputSchema": {
+      "type": "object",
+      "properties": {
+        "project": {"type": "string", "description": "The root path of the Unity project"},
+        "cached": {"type": "boolean", "description": "Return the last compile's results if nothing has changed since"}
+      },
+      "required": ["project"]
+    }
+  },
+  {
+    "name": "test",
+    "description": "Run the Unity project's tests, returning any compile errors, or the counts and the failing tests with their messages and stack traces.",
+    "inputSchema": {
+      "type": "object",
+      "properties": {
+        "project": {"type": "string", "description": "The root path of the Unity project"},
+        "mode": {"type": "string", "enum": ["edit-mode", "play-mode", "all"], "description": "Which tests to run (default edit-mode)"},
+        "filters": {"type": "string", "description": "Semicolon-separated test names or regexes"},
+        "categories": {"type": "string", "description": "Semicolon-separated NUnit categories"}
+      },
+      "required": ["project"]
+    }
+  },
+  {
+    "name": "report",
+    "description": "Read an existing NUnit test results file, returning the counts and the failing tests.",
+    "inputSchema": {
+      "type": "object",
+      "properties": {
+        "path": {"type": "string", "description": "The results XML file"}
+      },
+      "required": ["path"]
+    }
+  }
+]"#;
+
+/// Handle requests on stdin until it closes.
+pub fn serve(options: ServeOptions) -> Result<()> {
+    let editors = editors::installed_editors();
+    let stdout = Mutex::new(io::stdout());
+    let send = |message: &Value| {
+        let Ok(line) = json::to_string(message) else { return };
+        let mut stdout = stdout.lock().unwrap();
+        let _ = writeln!(stdout, "{}", line);
+        let _ = stdout.flush();
+    };
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match json::parse(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                send(&failure(&Value::Null, PARSE_ERROR, format!("Invalid JSON: {}", err)));
+                continue;
+            },
+        };
+
+        // Notifications (e.g. `notifications/initialized`) get no response
+        let Some(id) = request.get("id").cloned() else { continue };
+        let params = request.get("params").cloned().unwrap_or(Value::Object(vec![]));
+        let result = match request.get("method").and_then(|method| method.as_str()) {
+            Some("initialize") => Ok(initialize()),
+            Some("ping") => Ok(Value::Object(vec![])),
+            Some("tools/list") => Ok(Value::Object(vec![
+                ("tools".to_string(), json::parse(TOOLS).expect("the tools are valid JSON")),
+            ])),
+            Some("tools/call") => {
+                let token = params.get("_meta").and_then(|meta| meta.get("progressToken")).cloned();
+                let on_progress = |progress: usize, message: String| {
+                    if let Some(token) = &token {
+                        send(&notify("notifications/progress", Value::Object(vec![
+                            ("progressToken".to_string(), token.clone()),
+                            ("progress".to_string(), Value::Number(progress as f64)),
+                            ("message".to_string(), Value::Str(message)),
+                        ])));
+                    }
+                };
+                Ok(call_tool(&params, &options, &editors, &on_progress))
+            },
+            Some(method) => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+            None => Err((INVALID_REQUEST, "The request has no `method`".to_string())),
+        };
+        match result {
+            Ok(result) => send(&success(&id, result)),
+            Err((code, message)) => send(&failure(&id, code, message)),
+        }
+    }
+    Ok(())
+}
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+fn initialize() -> Value {
+    let str = |s: &str| Value::Str(s.to_string());
+    Value::Object(vec![
+        ("protocolVersion".to_string(), str(PROTOCOL_VERSION)),
+        ("capabilities".to_string(), Value::Object(vec![
+            ("tools".to_string(), Value::Object(vec![])),
+        ])),
+        ("serverInfo".to_string(), Value::Object(vec![
+            ("name".to_string(), str("unitool")),
+            ("version".to_string(), str(env!("CARGO_PKG_VERSION"))),
+        ])),
+    ])
+}
+
+/// Call a tool. Failures are returned as tool
+/// results (with `isError`), for the assistant to see.
+fn call_tool(params: &Value, options: &ServeOptions, editors: &[Editor], on_progress: &(dyn Fn(usize, String) + Sync)) -> Value {
+    let args = params.get("arguments").cloned().unwrap_or(Value::Object(vec![]));
+    let result = match params.get("name").and_then(|name| name.as_str()) {
+        Some("compile") => compile(&args, options, editors, on_progress),
+        Some("test") => test(&args, options, editors, on_progress),
+        Some("report") => report(&args),
+        Some(name) => Err(anyhow!("Unknown tool `{}`", name)),
+        None => Err(anyhow!("No tool was named")),
+    };
+    let (text, is_error) = match result {
+        Ok((text, succeeded)) => (text, !succeeded),
+        Err(err) => (format!("{:#}", err), true),
+    };
+    Value::Object(vec![
+        ("content".to_string(), Value::Array(vec![Value::Object(vec![
+            ("type".to_string(), Value::Str("text".to_string())),
+            ("text".to_string(), Value::Str(text)),
+        ])])),
+        ("isError".to_string(), Value::Bool(is_error)),
+    ])
+}
+
+/// A tool's result as JSON, and whether it succeeded.
+type ToolOutput = Result<(String, bool)>;
+
+fn project_arg(args: &Value) -> Result<PathBuf> {
+    match args.get("project").and_then(|project| project.as_str()) {
+        Some(project) => Ok(project.into()),
+        None => bail!("The `project` path is required"),
+    }
+}
+
+/// Pass run events on as progress messages.
+fn with_progress<'a>(options: &mut crate::RunOptions<'a>, on_progress: &'a (dyn Fn(usize, String) + Sync)) {
+    let count = Mutex::new(0);
+    options.test_events = true;
+    options.on_event = Some(Box::new(move |event: RunEvent| {
+        let message = match event {
+            RunEvent::EditorStarted { .. } => "Editor started".to_string(),
+            RunEvent::CompilationStarted => "Compiling".to_string(),
+            RunEvent::CompilingAssembly { name, .. } => format!("Compiling {}", name),
+            RunEvent::TestFinished { name, result } => format!("{:?}: {}", result, name),
+            _ => return,
+        };
+        let mut count = count.lock().unwrap();
+        *count += 1;
+        on_progress(*count, message);
+    }));
+}
+
+fn compile(args: &Value, options: &ServeOptions, editors: &[Editor], on_progress: &(dyn Fn(usize, String) + Sync)) -> ToolOutput {
+    #[derive(Serialize)]
+    struct Compiled<'a> {
+        errors: Vec<&'a CompileError>,
+        warnings: Vec<&'a String>,
+    }
+
+    let project = project_arg(args)?;
+    let mut options = options.run_options(editors, &project);
+    let cached = match args.get("cached").and_then(|cached| cached.as_bool()) {
+        Some(true) => cache::lookup(&project, &options),
+        _ => None,
+    };
+    let output = match cached {
+        Some(output) => output,
+        None => {
+            with_progress(&mut options, on_progress);
+            let _lock = lock::lock_project(&project, LockMode::Fail, |_| {})?;
+            crate::compile(&project, &options)?
+        },
+    };
+    let mut errors: Vec<_> = output.errors.iter().collect();
+    errors.sort();
+    let mut warnings: Vec<_> = output.warnings.iter().collect();
+    warnings.sort();
+    Ok((json::to_string(&Compiled { errors, warnings })?, output.errors.is_empty()))
+}
+
+/// The counts and failing tests, leaving out the passing ones
+/// (which would mostly be noise for the assistant).
+#[derive(Serialize)]
+struct Failures<'a> {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    failures: Vec<&'a TestCase>,
+}
+impl<'a> Failures<'a> {
+    fn new(summary: &'a TestSummary) -> Failures<'a> {
+        Failures {
+            total: summary.total(),
+            passed: summary.passed(),
+            failed: summary.failed(),
+            skipped: summary.skipped(),
+            failures: summary.failures(),
+        }
+    }
+}
+
+fn test(args: &Value, options: &ServeOptions, editors: &[Editor], on_progress: &(dyn Fn(usize, String) + Sync)) -> ToolOutput {
+    #[derive(Serialize)]
+    struct Tested<'a> {
+        errors: Vec<&'a CompileError>,
+        results: Option<Failures<'a>>,
+    }
+
+    let project = project_arg(args)?;
+    let arg = |key: &str| args.get(key).and_then(|value| value.as_str()).map(|value| value.to_string());
+    let mode = match arg("mode") {
+        Some(mode) => TestMode::from_str(&mode, true)
+            .map_err(|_| anyhow!("Unknown test mode `{}`; use `edit-mode`, `play-mode` or `all`", mode))?,
+        None => TestMode::EditMode,
+    };
+
+    let mut options = options.run_options(editors, &project);
+    with_progress(&mut options, on_progress);
+    let _lock = lock::lock_project(&project, LockMode::Fail, |_| {})?;
+    let (errors, results) = crate::test(&project, &options, mode,
+        None, arg("filters"), arg("categories"), &Default::default())?;
+    let mut sorted: Vec<_> = errors.iter().collect();
+    sorted.sort();
+    let succeeded = errors.is_empty() && results.as_ref().is_some_and(|results| results.failed() == 0);
+    let tested = Tested { errors: sorted, results: results.as_ref().map(Failures::new) };
+    Ok((json::to_string(&tested)?, succeeded))
+}
+
+fn report(args: &Value) -> ToolOutput {
+    let Some(path) = args.get("path").and_then(|path| path.as_str()) else {
+        bail!("The results file `path` is required");
+    };
+    let summary = crate::load_results(Path::new(path))?;
+    Ok((json::to_string(&Failures::new(&summary))?, summary.failed() == 0))
+}
+
+fn success(id: &Value, result: Value) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_string(), Value::Str("2.0".to_string())),
+        ("id".to_string(), id.clone()),
+        ("result".to_string(), result),
+    ])
+}
+
+fn failure(id: &Value, code: i32, message: String) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_string(), Value::Str("2.0".to_string())),
+        ("id".to_string(), id.clone()),
+        ("error".to_string(), Value::Object(vec![
+            ("code".to_string(), Value::Number(code as f64)),
+            ("message".to_string(), Value::Str(message)),
+        ])),
+    ])
+}
+
+fn notify(method: &str, params: Value) -> Value {
+    Value::Object(vec![
+        ("jsonrpc".to_string(), Value::Str("2.0".to_string())),
+        ("method".to_string(), Value::Str(method.to_string())),
+        ("params".to_string(), params),
+    ])
+}
@@ -0,0 +1,215 @@
+//! Notifying of a run's outcome when it finishes, for long runs
+//! that finish while you're doing something else: posted to a
+//! webhook, or shown on the desktop. Slack webhooks get a message
+//! formatted for Slack; others get a [`Notification`] as JSON,
+//! posted with `curl`.
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{io::Write, path::Path, process::{Command, Stdio}};
+use crate::{ExitCode, TestResult, display::human_duration, history::RunRecord, json};
+
+/// How many failed tests are listed.
+const MAX_FAILURES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Passed,
+    CompileFailed,
+    TestsFailed,
+
+    /// The run itself failed, e.g. the editor crashed
+    Error,
+}
+
+/// What a run's notification says.
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    /// The project's directory name
+    pub project: String,
+
+    /// `compile` or `test`
+    pub command: String,
+    pub mode: Option<String>,
+
+    pub outcome: Outcome,
+
+    /// Why the run failed, if it errored
+    pub error: Option<String>,
+
+    pub commit: Option<String>,
+    pub compile_errors: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+
+    /// In seconds
+    pub duration: f64,
+
+    /// The first of the failed tests, by full name
+    pub failures: Vec<String>,
+}
+impl Notification {
+    /// A notification for a run that exited with `code`, from its
+    /// record if it got far enough to have one, and its error if any.
+    pub fn new(project_path: &Path, command: &str, record: Option<&RunRecord>, code: ExitCode, error: Option<&anyhow::Error>) -> Notification {
+        let project = std::fs::canonicalize(project_path).unwrap_or(project_path.to_path_buf())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let outcome = match code {
+            ExitCode::Success => Outcome::Passed,
+            ExitCode::CompileFailed => Outcome::CompileFailed,
+            ExitCode::TestsFailed => Outcome::TestsFailed,
+            _ => Outcome::Error,
+        };
+        let failures = record.map(|record| record.tests.iter()
+            .filter(|test| test.result == TestResult::Failed)
+            .take(MAX_FAILURES)
+            .map(|test| test.name.clone())
+            .collect()).unwrap_or_default();
+        Notification {
+            project,
+            command: record.map(|record| record.command.clone()).unwrap_or(command.to_string()),
+            mode: record.and_then(|record| record.mode.clone()),
+            outcome,
+            error: error.map(|err| err.to_string().lines().next().unwrap_or_default().to_string()),
+            commit: record.and_then(|record| record.commit.clone()),
+            compile_errors: record.map(|record| record.compile_errors).unwrap_or(0),
+            passed: record.map(|record| record.passed).unwrap_or(0),
+            failed: record.map(|record| record.failed).unwrap_or(0),
+            skipped: record.map(|record| record.skipped).unwrap_or(0),
+            duration: record.map(|record| record.duration).unwrap_or(0.),
+            failures,
+        }
+    }
+
+    /// A one-line summary, e.g. `✅ Game: edit-mode tests passed (120 passed in 3m 12s)`.
+    pub fn summary(&self) -> String {
+        let (mark, status) = self.status();
+        format!("{} {}: {}", mark, self.project, status)
+    }
+
+    /// A mark for whether the run passed, and how it went.
+    fn status(&self) -> (&'static str, String) {
+        let what = match (self.command.as_str(), &self.mode) {
+            ("test", Some(mode)) => format!("{} tests", mode),
+            ("test", None) => "tests".to_string(),
+            (command, _) => command.to_string(),
+        };
+        let (mark, status) = match self.outcome {
+            Outcome::Passed if self.command == "compile" => ("✅", "compiled".to_string()),
+            Outcome::Passed => ("✅", format!("{} passed", what)),
+            Outcome::CompileFailed => ("❌", format!("compilation failed with {} errors", self.compile_errors)),
+            Outcome::TestsFailed => ("❌", format!("{} failed", what)),
+            Outcome::Error => ("❌", format!("{} couldn't run: {}", what, self.error.as_deref().unwrap_or("unknown error"))),
+        };
+        let mut counts = vec![];
+        if self.command == "test" && matches!(self.outcome, Outcome::Passed | Outcome::TestsFailed) {
+            counts.push(format!("{} passed", self.passed));
+            if self.failed > 0 { counts.push(format!("{} failed", self.failed)) }
+            if self.skipped > 0 { counts.push(format!("{} skipped", self.skipped)) }
+        }
+        let time = if self.duration <= 0. {
+            String::new()
+        } else if counts.is_empty() {
+            format!(" ({})", human_duration(self.duration))
+        } else {
+            format!(" ({} in {})", counts.join(", "), human_duration(self.duration))
+        };
+        (mark, format!("{}{}", status, time))
+    }
+
+    /// The summary and failed tests, as Slack's `mrkdwn`.
+    fn slack_text(&self) -> String {
+        let mut lines = vec![self.summary()];
+        lines.extend(self.failures.iter().map(|name| format!("• `{}`", name)));
+        if self.failed > self.failures.len() {
+            lines.push(format!("…and {} more", self.failed - self.failures.len()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Whether the webhook is Slack's, so gets a Slack message.
+fn is_slack(url: &str) -> bool {
+    url.split("://").nth(1).is_some_and(|rest| rest.starts_with("hooks.slack.com/"))
+}
+
+/// Post the notification to the webhook.
+pub fn send(url: &str, notification: &Notification) -> Result<()> {
+    let body = if is_slack(url) {
+        #[derive(Serialize)]
+        struct SlackMessage { text: String }
+        json::to_string(&SlackMessage { text: notification.slack_text() })?
+    } else {
+        json::to_string(notification)?
+    };
+    let mut child = Command::new("curl")
+        .args(["-fsS", "--max-time", "30", "-X", "POST", "-H", "Content-Type: application/json", "--data-binary", "@-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => anyhow!("Sending notifications needs curl"),
+            _ => anyhow!("Couldn't run curl: {}", err),
+        })?;
+    child.stdin.take().expect("stdin is piped").write_all(body.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Show the notification on the desktop, with `notify-send` on
+/// Linux, `osascript` on macOS, or a toast on Windows.
+pub fn desktop(notification: &Notification) -> Result<()> {
+    let title = format!("unitool: {}", notification.project);
+    let (mark, status) = notification.status();
+    let body = format!("{} {}", mark, status);
+    let mut cmd = desktop_command(&title, &body);
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let output = cmd.stdin(Stdio::null()).output()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => anyhow!("Desktop notifications need `{}`", program),
+            _ => anyhow!("Couldn't run {}: {}", program, err),
+        })?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(format!("display notification {} with title {}", quote(body), quote(title)));
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let script = format!(r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $xml.GetElementsByTagName('text')
+$text.Item(0).AppendChild($xml.CreateTextNode({})) | Out-Null
+$text.Item(1).AppendChild($xml.CreateTextNode({})) | Out-Null
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('unitool').Show([Windows.UI.Notifications.ToastNotification]::new($xml))
+"#, quote(title), quote(body));
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-Command", &script]);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.args(["--app-name", "unitool", title, body]);
+    cmd
+}
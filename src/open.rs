@@ -0,0 +1,111 @@
+//! Opening the first failure (a compile error, or where a test
+//! failed) in an editor, at its line. The command is a template,
+//! e.g. `code --goto {file}:{line}:{column}`, which is worked out
+//! from `$VISUAL` or `$EDITOR` for well-known editors if not
+//! configured.
+
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use std::{fmt::Display, path::{Path, PathBuf}, process::Command};
+use crate::{CompileErrors, TestSummary, trace};
+
+/// When to open the first failure.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum OpenEditor {
+    /// Open it straight away
+    Always,
+
+    /// Ask first, if there's a terminal to ask in
+    Ask,
+
+    #[default]
+    Never,
+}
+
+/// Where a failure happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+}
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.path.display(), self.line)
+    }
+}
+
+/// The first compile error with a location, by file and line.
+pub fn first_compile_error(project_path: &Path, errors: &CompileErrors) -> Option<Location> {
+    let mut errors: Vec<_> = errors.iter().collect();
+    errors.sort();
+    errors.iter().find_map(|err| Some(Location {
+        path: project_path.join(err.file.as_ref()?),
+        line: err.line?,
+        column: err.column,
+    }))
+}
+
+/// Where the first failed test with a stack trace
+/// into the project's own scripts failed.
+pub fn first_test_failure(project_path: &Path, results: &TestSummary) -> Option<Location> {
+    results.failures().iter()
+        .filter_map(|case| trace::first_frame(&case.stack_trace()?))
+        .map(|(path, line)| Location { path: project_path.join(path), line, column: None })
+        .find(|location| location.path.is_file())
+}
+
+/// The command template for an editor (e.g. `$EDITOR`), so
+/// it opens at the line for those that are known.
+fn default_template(editor: &str) -> String {
+    let program = editor.split_whitespace().next().unwrap_or(editor);
+    let name = Path::new(program).file_stem()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let args = match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => "--goto {file}:{line}:{column}",
+        "rider" | "rider64" => "--line {line} {file}",
+        "subl" | "hx" | "zed" => "{file}:{line}:{column}",
+        "vi" | "vim" | "nvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" => "+{line} {file}",
+        _ => "{file}",
+    };
+    format!("{} {}", editor, args)
+}
+
+/// The command to open the location with, from the template
+/// (or the default one for `$VISUAL` or `$EDITOR`).
+pub fn command(template: Option<&str>, location: &Location) -> Result<Command> {
+    let template = match template {
+        Some(template) => template.to_string(),
+        None => {
+            let editor = std::env::var("VISUAL").ok()
+                .or_else(|| std::env::var("EDITOR").ok())
+                .filter(|editor| !editor.trim().is_empty())
+                .ok_or_else(|| anyhow!("No editor to open it in; set `$EDITOR`, or `editor_command` in the config"))?;
+            default_template(&editor)
+        },
+    };
+    let file = location.path.to_string_lossy();
+    let (line, column) = (location.line.to_string(), location.column.unwrap_or(1).to_string());
+    let mut args = template.split_whitespace().map(|arg| arg
+        .replace("{file}", &file)
+        .replace("{line}", &line)
+        .replace("{column}", &column));
+    let Some(program) = args.next() else {
+        bail!("The editor command is empty");
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    Ok(cmd)
+}
+
+/// Open the location, waiting for the editor if it runs in the terminal.
+pub fn open(template: Option<&str>, location: &Location) -> Result<()> {
+    let mut cmd = command(template, location)?;
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let status = cmd.status().map_err(|err| anyhow!("Couldn't run `{}`: {}", program, err))?;
+    if !status.success() {
+        bail!("`{}` exited with {}", program, status);
+    }
+    Ok(())
+}
@@ -0,0 +1,327 @@
+//! Managing the project's packages by editing `Packages/manifest.json`
+//! (and `packages-lock.json`, if there is one) directly, so it can
+//! be done without opening the editor. Versions are looked up in
+//! the package registries (with `curl`).
+//!
+//! Only the changed packages are locked; the editor resolves the
+//! rest of the graph (e.g. new transitive dependencies) when the
+//! project's next opened.
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{fmt::Display, fs, io, path::{Path, PathBuf}, process::Command};
+use crate::{json::{self, Value}, display::*};
+
+/// Unity's registry, unless the manifest sets another.
+const DEFAULT_REGISTRY: &str = "https://packages.unity.com";
+
+/// The editor's built-in modules, which aren't versioned.
+const MODULE_PREFIX: &str = "com.unity.modules.";
+
+/// A package the project depends on.
+#[derive(Debug, Serialize)]
+pub struct Package {
+    pub name: String,
+
+    /// The version (or git URL, or `file:` path) in the manifest
+    pub requested: String,
+
+    /// The version resolved in the lock file, if locked
+    pub locked: Option<String>,
+
+    /// e.g. `registry`, `git`, `embedded`
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Packages {
+    pub packages: Vec<Package>,
+}
+impl Display for Packages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        let width = self.packages.iter()
+            .filter(|package| !package.name.starts_with(MODULE_PREFIX))
+            .map(|package| package.name.len())
+            .max().unwrap_or(0);
+        let mut modules = 0;
+        for package in &self.packages {
+            if package.name.starts_with(MODULE_PREFIX) {
+                modules += 1;
+                continue;
+            }
+            let mut line = format!("{:width$} {}", package.name, green(&package.requested), width = width);
+            match &package.locked {
+                Some(locked) if *locked != package.requested => line.push_str(&format!(" {}", muted(&format!("(locked at {})", locked)))),
+                _ => {},
+            }
+            if let Some(source) = package.source.as_deref().filter(|source| *source != "registry") {
+                line.push_str(&format!(" {}", muted(&format!("[{}]", source))));
+            }
+            lines.push(line);
+        }
+        lines.push(muted(&format!("({} built-in modules)", modules)).to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A change to a package's version in the manifest.
+#[derive(Debug, Serialize)]
+pub struct PackageChange {
+    pub name: String,
+
+    /// The version before, if it was a dependency
+    pub from: Option<String>,
+
+    /// The version after, if it still is
+    pub to: Option<String>,
+}
+impl Display for PackageChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.from, &self.to) {
+            (None, Some(to)) => write!(f, "Added {} {}", self.name, green(to)),
+            (Some(_), None) => write!(f, "Removed {}", self.name),
+            (Some(from), Some(to)) if from == to => write!(f, "{} is up to date {}", self.name, muted(&format!("({})", to))),
+            (Some(from), Some(to)) => write!(f, "Updated {} {} → {}", self.name, muted(from), green(to)),
+            (None, None) => write!(f, "{} unchanged", self.name),
+        }
+    }
+}
+
+/// The project's packages, per the manifest and lock file.
+pub fn list(project_path: &Path) -> Result<Packages> {
+    let manifests = Manifests::load(project_path)?;
+    let packages = manifests.dependencies().iter()
+        .map(|(name, requested)| {
+            let locked = manifests.locked(name);
+            Package {
+                name: name.clone(),
+                requested: requested.as_str().unwrap_or_default().to_string(),
+                locked: locked.and_then(|entry| entry.get("version")).and_then(|v| v.as_str()).map(String::from),
+                source: locked.and_then(|entry| entry.get("source")).and_then(|v| v.as_str()).map(String::from),
+            }
+        })
+        .collect();
+    Ok(Packages { packages })
+}
+
+/// Add packages, given as `name` (for the latest version) or
+/// `name@version`, where the version may also be a git URL
+/// or `file:` path. Packages already added are changed to
+/// the given version.
+pub fn add(project_path: &Path, specs: &[String]) -> Result<Vec<PackageChange>> {
+    let mut manifests = Manifests::load(project_path)?;
+    let mut changes = vec![];
+    for spec in specs {
+        let (name, version) = parse_spec(spec);
+        changes.push(manifests.set(name, version)?);
+    }
+    manifests.save()?;
+    Ok(changes)
+}
+
+/// Remove packages from the manifest.
+pub fn remove(project_path: &Path, names: &[String]) -> Result<Vec<PackageChange>> {
+    let mut manifests = Manifests::load(project_path)?;
+    let mut changes = vec![];
+    for name in names {
+        let Some(from) = manifests.manifest.get_mut("dependencies").and_then(|deps| deps.remove(name)) else {
+            bail!("{} isn't a dependency of the project", name);
+        };
+        manifests.unlock(name);
+        changes.push(PackageChange {
+            name: name.clone(),
+            from: from.as_str().map(String::from),
+            to: None,
+        });
+    }
+    manifests.save()?;
+    Ok(changes)
+}
+
+/// Update packages to their latest versions (or `name@version`),
+/// or every registry package if none are given. Git and local
+/// packages are left as they are.
+pub fn update(project_path: &Path, specs: &[String]) -> Result<Vec<PackageChange>> {
+    let mut manifests = Manifests::load(project_path)?;
+    let specs: Vec<String> = if specs.is_empty() {
+        manifests.dependencies().iter()
+            .filter(|(name, version)| !name.starts_with(MODULE_PREFIX)
+                && version.as_str().is_some_and(|version| !is_location(version)))
+            .map(|(name, _)| name.clone())
+            .collect()
+    } else {
+        specs.to_vec()
+    };
+    let mut changes = vec![];
+    for spec in &specs {
+        let (name, version) = parse_spec(spec);
+        if manifests.dependencies().iter().all(|(dep, _)| dep != name) {
+            bail!("{} isn't a dependency of the project; add it instead", name);
+        }
+        changes.push(manifests.set(name, version)?);
+    }
+    manifests.save()?;
+    Ok(changes)
+}
+
+/// Split `name@version` (the version being optional).
+fn parse_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    }
+}
+
+/// Whether a manifest version is a git URL or
+/// local path rather than a registry version.
+fn is_location(version: &str) -> bool {
+    version.contains(':') || version.contains('/')
+}
+
+/// The manifest and lock file, edited together.
+struct Manifests {
+    manifest_path: PathBuf,
+    manifest: Value,
+    lock_path: PathBuf,
+    lock: Option<Value>,
+}
+impl Manifests {
+    fn load(project_path: &Path) -> Result<Manifests> {
+        let manifest_path = project_path.join("Packages/manifest.json");
+        let lock_path = project_path.join("Packages/packages-lock.json");
+        let manifest = read_json(&manifest_path)?
+            .ok_or_else(|| anyhow!("Couldn't find {}, is this a Unity project?", manifest_path.display()))?;
+        let lock = read_json(&lock_path)?;
+        Ok(Manifests { manifest_path, manifest, lock_path, lock })
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(&self.manifest_path, self.manifest.to_pretty_string() + "\n")?;
+        if let Some(lock) = &self.lock {
+            fs::write(&self.lock_path, lock.to_pretty_string() + "\n")?;
+        }
+        Ok(())
+    }
+
+    fn dependencies(&self) -> &[(String, Value)] {
+        self.manifest.get("dependencies").map(|deps| deps.fields()).unwrap_or_default()
+    }
+
+    fn locked(&self, name: &str) -> Option<&Value> {
+        self.lock.as_ref()?.get("dependencies")?.get(name)
+    }
+
+    /// Set a dependency's version, looking up the latest if
+    /// none is given, and lock it if it's from a registry.
+    fn set(&mut self, name: &str, version: Option<&str>) -> Result<PackageChange> {
+        let from = self.dependencies().iter()
+            .find(|(dep, _)| dep == name)
+            .and_then(|(_, version)| version.as_str())
+            .map(String::from);
+        let version = match version {
+            Some(location) if is_location(location) => {
+                // Left for the editor to fetch and lock
+                self.unlock(name);
+                location.to_string()
+            },
+            version => {
+                let registry = self.registry_for(name);
+                let info = fetch(&registry, name)?;
+                let version = match version {
+                    Some(version) => version.to_string(),
+                    None => info.get("dist-tags").and_then(|tags| tags.get("latest")).and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow!("{} has no latest version in {}", name, registry))?
+                        .to_string(),
+                };
+                let Some(release) = info.get("versions").and_then(|versions| versions.get(&version)) else {
+                    bail!("{} has no version {} in {}", name, version, registry);
+                };
+                self.lock(name, &version, release, &registry);
+                version
+            },
+        };
+
+        let Some(deps) = self.manifest.get_mut("dependencies") else {
+            bail!("{} has no dependencies", self.manifest_path.display());
+        };
+        deps.insert(name, Value::Str(version.clone()));
+        deps.sort_keys();
+        Ok(PackageChange { name: name.to_string(), from, to: Some(version) })
+    }
+
+    /// The registry URL for a package, from the manifest's scoped
+    /// registries (the most specific matching scope), if any.
+    fn registry_for(&self, name: &str) -> String {
+        let scoped = self.manifest.get("scopedRegistries");
+        let scoped: &[Value] = match scoped {
+            Some(Value::Array(registries)) => registries,
+            _ => &[],
+        };
+        scoped.iter()
+            .filter_map(|registry| {
+                let url = registry.get("url")?.as_str()?;
+                let scope = registry.get("scopes")?.strings().into_iter()
+                    .filter(|scope| name == *scope || name.starts_with(&format!("{}.", scope)))
+                    .max_by_key(|scope| scope.len())?;
+                Some((scope.len(), url))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, url)| url.to_string())
+            .or_else(|| self.manifest.get("registry").and_then(|url| url.as_str()).map(String::from))
+            .unwrap_or_else(|| DEFAULT_REGISTRY.to_string())
+    }
+
+    /// Lock a direct dependency at a registry version.
+    fn lock(&mut self, name: &str, version: &str, release: &Value, registry: &str) {
+        let Some(deps) = self.lock.as_mut().and_then(|lock| lock.get_mut("dependencies")) else { return };
+        let dependencies = release.get("dependencies").cloned()
+            .unwrap_or(Value::Object(vec![]));
+        deps.insert(name, Value::Object(vec![
+            ("version".to_string(), Value::Str(version.to_string())),
+            ("depth".to_string(), Value::Number(0.)),
+            ("source".to_string(), Value::Str("registry".to_string())),
+            ("dependencies".to_string(), dependencies),
+            ("url".to_string(), Value::Str(registry.to_string())),
+        ]));
+        deps.sort_keys();
+    }
+
+    /// Drop a package from the lock file, unless
+    /// another locked package depends on it.
+    fn unlock(&mut self, name: &str) {
+        let Some(deps) = self.lock.as_mut().and_then(|lock| lock.get_mut("dependencies")) else { return };
+        let required = deps.fields().iter()
+            .any(|(dep, entry)| dep != name && entry.get("dependencies").is_some_and(|deps| deps.get(name).is_some()));
+        if !required {
+            deps.remove(name);
+        } else if let Some(entry) = deps.get_mut(name) {
+            entry.insert("depth", Value::Number(1.));
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Result<Option<Value>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(json::parse(&contents)
+            .map_err(|err| anyhow!("Couldn't parse {}: {}", path.display(), err))?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Fetch a package's metadata from a registry
+/// (which follow npm's registry API).
+fn fetch(registry: &str, name: &str) -> Result<Value> {
+    let url = format!("{}/{}", registry.trim_end_matches('/'), name);
+    let output = Command::new("curl").args(["-fsSL", &url]).output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => anyhow!("Looking up package versions needs curl"),
+            _ => err.into(),
+        })?;
+    if !output.status.success() {
+        bail!("Couldn't find {} in {}: {}", name, registry, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    json::parse(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|err| anyhow!("Couldn't parse {}'s metadata from {}: {}", name, registry, err))
+}
@@ -0,0 +1,145 @@
+//! A fuzzy-find multi-select prompt in the terminal,
+//! for picking tests to run by name.
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use console::{Key, Term};
+use std::collections::BTreeSet;
+use crate::display::*;
+
+/// The most matches shown at once.
+const MAX_SHOWN: usize = 15;
+
+/// Prompt for a selection of the items, returning those picked
+/// (in their original order). If none are marked, the one
+/// under the cursor is picked. Fails if cancelled.
+pub fn pick(prompt: &str, items: &[String]) -> Result<Vec<String>> {
+    let term = Term::stderr();
+    if !term.is_term() {
+        bail!("Picking needs a terminal");
+    }
+    let mut query = String::new();
+    let mut selected: BTreeSet<usize> = BTreeSet::new();
+    let (mut cursor, mut scroll) = (0, 0);
+    let mut screen = Screen::new(&term)?;
+    let result = loop {
+        let matches = matches(&query, items);
+        cursor = cursor.min(matches.len().saturating_sub(1));
+        if cursor < scroll {
+            scroll = cursor;
+        } else if cursor >= scroll + MAX_SHOWN {
+            scroll = cursor + 1 - MAX_SHOWN;
+        }
+
+        let mut lines = vec![format!("{} {}", prompt, query)];
+        for (i, &idx) in matches.iter().enumerate().skip(scroll).take(MAX_SHOWN) {
+            let mark = if selected.contains(&idx) { green("●") } else { muted("○") };
+            let item = if i == cursor { items[idx].bold() } else { items[idx].normal() };
+            let pointer = if i == cursor { ">" } else { " " };
+            lines.push(format!("{} {} {}", pointer, mark, item));
+        }
+        lines.push(muted(&format!("{}/{} matching, {} selected · ↑↓ move · tab select · enter run · esc cancel",
+            matches.len(), items.len(), selected.len())).to_string());
+        screen.draw(&lines)?;
+
+        match term.read_key()? {
+            Key::Escape => break None,
+            Key::Enter => {
+                if selected.is_empty() {
+                    selected.extend(matches.get(cursor));
+                }
+                break Some(selected);
+            },
+            Key::Tab => {
+                if let Some(&idx) = matches.get(cursor) {
+                    if !selected.remove(&idx) {
+                        selected.insert(idx);
+                    }
+                    cursor += 1;
+                }
+            },
+            Key::ArrowUp => cursor = cursor.saturating_sub(1),
+            Key::ArrowDown => cursor += 1,
+            Key::Backspace => { query.pop(); },
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                cursor = 0;
+            },
+            _ => {},
+        }
+    };
+    drop(screen);
+    match result {
+        Some(selected) if !selected.is_empty() => Ok(selected.into_iter().map(|idx| items[idx].clone()).collect()),
+        _ => bail!("Nothing was picked"),
+    }
+}
+
+/// The prompt's lines, cleared (and the cursor shown
+/// again) when dropped, however picking ended.
+struct Screen<'a> {
+    term: &'a Term,
+    drawn: usize,
+}
+impl<'a> Screen<'a> {
+    fn new(term: &'a Term) -> Result<Screen<'a>> {
+        term.hide_cursor()?;
+        Ok(Screen { term, drawn: 0 })
+    }
+
+    /// Redraw the prompt over what was drawn before.
+    fn draw(&mut self, lines: &[String]) -> Result<()> {
+        self.term.clear_last_lines(self.drawn)?;
+        self.drawn = 0;
+        for line in lines {
+            self.term.write_line(&truncate(line, self.term.size().1 as usize))?;
+            self.drawn += 1;
+        }
+        Ok(())
+    }
+}
+impl Drop for Screen<'_> {
+    fn drop(&mut self) {
+        let _ = self.term.clear_last_lines(self.drawn);
+        let _ = self.term.show_cursor();
+    }
+}
+
+/// The indices of the items matching the query, best first.
+fn matches(query: &str, items: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = items.iter().enumerate()
+        .filter_map(|(idx, item)| score(query, item).map(|score| (score, idx)))
+        .collect();
+    scored.sort_by_key(|&(score, idx)| (std::cmp::Reverse(score), idx));
+    scored.into_iter().map(|(_, idx)| idx).collect()
+}
+
+/// How well the query matches, if its characters all appear in
+/// order (ignoring case). Runs of consecutive characters and
+/// matches at the start of a name part score higher.
+fn score(query: &str, item: &str) -> Option<i64> {
+    let item: Vec<char> = item.chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let found = item[pos..].iter()
+            .position(|c| c.to_lowercase().eq(q.to_lowercase()))?;
+        let idx = pos + found;
+        score += match last {
+            Some(last) if idx == last + 1 => 5,
+            _ => -(found as i64).min(10),
+        };
+        if idx == 0 || matches!(item[idx - 1], '.' | '(' | '_') || item[idx].is_uppercase() {
+            score += 3;
+        }
+        last = Some(idx);
+        pos = idx + 1;
+    }
+    Some(score)
+}
+
+/// Cut a line to the terminal's width, so each takes one row.
+fn truncate(line: &str, width: usize) -> String {
+    console::truncate_str(line, width.saturating_sub(1), "…").to_string()
+}
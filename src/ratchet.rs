@@ -0,0 +1,136 @@
+//! Ratchets let legacy projects clean up gradually:
+//! the current counts are recorded as a baseline,
+//! and later runs fail if any count goes up. When
+//! counts go down the baseline is tightened to match.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{fs, fmt::Display, path::{Path, PathBuf}, collections::{BTreeMap, HashSet}};
+use crate::{compiler, state_dir, display::*};
+
+/// What to ratchet.
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+pub enum Ratchet {
+    Warnings,
+}
+
+/// Counts per diagnostic ID, e.g. `CS0618`.
+type Counts = BTreeMap<String, usize>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RatchetOutcome {
+    /// There was no baseline, so one was recorded.
+    Recorded(Counts),
+
+    /// Nothing increased. Holds the codes that
+    /// decreased, if any, which the baseline was tightened to.
+    Held(Vec<(String, usize, usize)>),
+
+    /// These codes increased (code, baseline, current).
+    Failed(Vec<(String, usize, usize)>),
+}
+impl RatchetOutcome {
+    pub fn failed(&self) -> bool {
+        matches!(self, RatchetOutcome::Failed(_))
+    }
+}
+impl Display for RatchetOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            RatchetOutcome::Recorded(counts) => {
+                let total: usize = counts.values().sum();
+                muted(&format!("Recorded warning baseline ({} warnings)", total)).to_string()
+            },
+            RatchetOutcome::Held(improved) => {
+                let mut lines = vec![green("Warning ratchet held").to_string()];
+                for (code, before, after) in improved {
+                    lines.push(format!("  {} {} → {}", code, before, green(&after.to_string())));
+                }
+                lines.join("\n")
+            },
+            RatchetOutcome::Failed(increased) => {
+                let mut lines = vec![red("Warning ratchet failed").to_string()];
+                for (code, before, after) in increased {
+                    lines.push(format!("  {} {} → {}", code, before, red(&after.to_string())));
+                }
+                lines.join("\n")
+            },
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+/// Check the warnings against the project's baseline,
+/// recording or tightening the baseline as needed.
+pub fn ratchet_warnings(project_path: &Path, warnings: &HashSet<String>) -> Result<RatchetOutcome> {
+    let path = baseline_path(project_path);
+    let current = warning_counts(warnings);
+    if !path.exists() {
+        save_counts(&path, &current)?;
+        return Ok(RatchetOutcome::Recorded(current));
+    }
+
+    let baseline = load_counts(&path)?;
+    let mut increased = vec![];
+    let mut decreased = vec![];
+    let codes: HashSet<&String> = baseline.keys().chain(current.keys()).collect();
+    for code in codes {
+        let before = baseline.get(code).copied().unwrap_or(0);
+        let after = current.get(code).copied().unwrap_or(0);
+        if after > before {
+            increased.push((code.clone(), before, after));
+        } else if after < before {
+            decreased.push((code.clone(), before, after));
+        }
+    }
+    increased.sort();
+    decreased.sort();
+
+    if !increased.is_empty() {
+        Ok(RatchetOutcome::Failed(increased))
+    } else {
+        if !decreased.is_empty() {
+            save_counts(&path, &current)?;
+        }
+        Ok(RatchetOutcome::Held(decreased))
+    }
+}
+
+fn baseline_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("warnings-baseline")
+}
+
+/// Count warnings by their diagnostic ID.
+fn warning_counts(warnings: &HashSet<String>) -> Counts {
+    let mut counts = Counts::new();
+    for warning in warnings {
+        if let Some((_, code)) = compiler::diagnostic(warning) {
+            *counts.entry(code.to_string()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// The baseline is stored as one `<code> <count>` pair per line.
+fn load_counts(path: &Path) -> Result<Counts> {
+    let mut counts = Counts::new();
+    for line in fs::read_to_string(path)?.lines() {
+        if let Some((code, count)) = line.split_once(' ') {
+            counts.insert(code.to_string(), count.trim().parse()?);
+        }
+    }
+    Ok(counts)
+}
+
+fn save_counts(path: &Path, counts: &Counts) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents: String = counts.iter()
+        .map(|(code, count)| format!("{} {}\n", code, count))
+        .collect();
+    fs::write(path, contents)?;
+    Ok(())
+}
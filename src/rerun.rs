@@ -0,0 +1,80 @@
+//! Rerunning just the tests that failed last time. After each
+//! run the failed tests' full names are recorded (in
+//! `.unitool/last-failures.json`), and turned into a
+//! `-testFilter` for the next run, or an ordered test list
+//! (for `-orderedTestListFile`) to run them first.
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}};
+use crate::{TestSummary, json, state_dir};
+
+/// The recorded failures, as e.g. `{"failures": ["Game.Tests.Foo"]}`.
+#[derive(Serialize)]
+struct Failures<'a> {
+    failures: Vec<&'a str>,
+}
+
+fn failures_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("last-failures.json")
+}
+
+fn failures_order_path(project_path: &Path) -> PathBuf {
+    state_dir(project_path).join("failures-first.txt")
+}
+
+/// Record the tests that failed in this run,
+/// replacing those recorded for the last one.
+pub fn save_failures(project_path: &Path, results: &TestSummary) -> Result<()> {
+    let path = failures_path(project_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let cases = results.cases();
+    let failures = Failures {
+        failures: cases.iter()
+            .filter(|case| case.failed())
+            .map(|case| case.key())
+            .collect(),
+    };
+    fs::write(path, json::to_string(&failures)? + "\n")?;
+    Ok(())
+}
+
+/// The full names of the tests that failed in the last run.
+pub fn load_failures(project_path: &Path) -> Result<Vec<String>> {
+    let path = failures_path(project_path);
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            let recorded = json::parse(&contents)
+                .map_err(|err| anyhow!("Couldn't parse {}: {}", path.display(), err))?;
+            Ok(recorded.get("failures").map(|failures| failures.strings()).unwrap_or_default()
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect())
+        },
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A `-testFilter` for the tests that failed in the last
+/// run, or `None` if there weren't any (or no last run).
+pub fn failures_filter(project_path: &Path) -> Result<Option<String>> {
+    let failures = load_failures(project_path)?;
+    Ok(if failures.is_empty() { None } else { Some(failures.join(";")) })
+}
+
+/// Write an ordered test list of the tests that failed in the last
+/// run, so a run given it runs them first, returning its path; or
+/// `None` if there weren't any (or no last run).
+pub fn save_failures_order(project_path: &Path) -> Result<Option<PathBuf>> {
+    let failures = load_failures(project_path)?;
+    if failures.is_empty() {
+        return Ok(None);
+    }
+    let path = failures_order_path(project_path);
+    let contents: String = failures.iter().map(|name| format!("{}\n", name)).collect();
+    fs::write(&path, contents)?;
+    Ok(Some(path))
+}
@@ -0,0 +1,394 @@
+//! A builder for configuring test runs, for library consumers:
+//!
+//! ```no_run
+//! use unitool::{TestRun, TestMode};
+//! use std::time::Duration;
+//!
+//! let (errors, results) = TestRun::new("path/to/project")
+//!     .mode(TestMode::PlayMode)
+//!     .categories("Integration")
+//!     .timeout(Duration::from_secs(600))
+//!     .execute()?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Result, anyhow, bail};
+use std::{fs, path::{Path, PathBuf}, process, sync::{atomic::{AtomicUsize, Ordering}, mpsc::{self, Receiver}}, thread::{self, JoinHandle}, time::Duration};
+use crate::{
+    CompileErrors, RunOptions, TestMode, TestOptions, TestSummary,
+    assemblies, coverage, run_unity, run_editor, find_build_editor,
+    build::BuildTarget, compiler::Severity, docker::Image, events::RunEvent, scripts::{self, InjectedScript},
+    testing::{ResultsError, load_test_results},
+};
+
+/// Counts runs in this process, to keep their temp files apart.
+static RUN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Compile errors, or the results if compilation succeeded.
+pub type TestOutput = (CompileErrors, Option<TestSummary>);
+
+pub struct TestRun<'a> {
+    project_path: PathBuf,
+    options: RunOptions<'a>,
+    mode: TestMode,
+    assemblies: Option<String>,
+    filters: Option<String>,
+    categories: Option<String>,
+    results_path: Option<PathBuf>,
+    test_options: TestOptions,
+}
+impl<'a> TestRun<'a> {
+    /// A run of the project's edit mode tests, in the test
+    /// assemblies found from its assembly definitions.
+    pub fn new(project_path: impl Into<PathBuf>) -> TestRun<'a> {
+        TestRun {
+            project_path: project_path.into(),
+            options: RunOptions::default(),
+            mode: TestMode::EditMode,
+            assemblies: None,
+            filters: None,
+            categories: None,
+            results_path: None,
+            test_options: TestOptions::default(),
+        }
+    }
+
+    /// Set all the editor options at once.
+    pub fn options(mut self, options: RunOptions<'a>) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The editor binary to use.
+    pub fn unity_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.unity_path = Some(path.into());
+        self
+    }
+
+    /// Called with each line of the editor log.
+    pub fn on_log(mut self, on_log: impl Fn(&str) + Send + 'a) -> Self {
+        self.options.on_log = Some(Box::new(on_log));
+        self
+    }
+
+    /// Called with progress events as the run goes.
+    pub fn on_event(mut self, on_event: impl Fn(RunEvent) + Send + 'a) -> Self {
+        self.options.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    /// Also send events for each test starting and finishing
+    /// (which injects a script into the project for the run).
+    pub fn test_events(mut self, test_events: bool) -> Self {
+        self.options.test_events = test_events;
+        self
+    }
+
+    /// Kill the editor if it runs longer than this.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry runs that fail for transient reasons (e.g. a licensing
+    /// hiccup) up to `retries` times, waiting `backoff` before the
+    /// first retry and twice as long before each one after.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.options.retries = retries;
+        self.options.retry_backoff = backoff;
+        self
+    }
+
+    /// Run the editor in a Docker container, from the image.
+    pub fn docker(mut self, image: Image) -> Self {
+        self.options.docker = Some(image);
+        self
+    }
+
+    /// Treat diagnostics with the code (e.g. an analyzer's `UNT0001`)
+    /// as errors or warnings, or ignore them.
+    pub fn severity(mut self, code: impl Into<String>, severity: Severity) -> Self {
+        self.options.severities.insert(code.into(), severity);
+        self
+    }
+
+    /// Keep the full editor logs of the last `keep` runs
+    /// in the project's `.unitool/logs`.
+    pub fn keep_logs(mut self, keep: usize) -> Self {
+        self.options.keep_logs = keep;
+        self
+    }
+
+    /// An extra argument to pass to the editor.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.options.extra_args.push(arg.into());
+        self
+    }
+
+    pub fn mode(mut self, mode: TestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// `;`-delimited assembly names, rather
+    /// than those found for the mode.
+    pub fn assemblies(mut self, assemblies: impl Into<String>) -> Self {
+        self.assemblies = Some(assemblies.into());
+        self
+    }
+
+    /// `;`-delimited test filters.
+    pub fn filters(mut self, filters: impl Into<String>) -> Self {
+        self.filters = Some(filters.into());
+        self
+    }
+
+    /// `;`-delimited NUnit categories.
+    pub fn categories(mut self, categories: impl Into<String>) -> Self {
+        self.categories = Some(categories.into());
+        self
+    }
+
+    /// Keep the raw results XML at this path. Otherwise it's
+    /// written to a temp file, removed after the run. When
+    /// running all modes, each mode's results are kept
+    /// separately, e.g. `results-EditMode.xml`.
+    pub fn results_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.results_path = Some(path.into());
+        self
+    }
+
+    /// Record code coverage, to be loaded
+    /// after with [`coverage::load_coverage`].
+    pub fn coverage(mut self, coverage: bool) -> Self {
+        self.test_options.coverage = coverage;
+        self
+    }
+
+    /// Run play mode tests in a player built for the platform
+    /// (which the editor needs the build support module for),
+    /// rather than in the editor.
+    pub fn platform(mut self, platform: BuildTarget) -> Self {
+        self.test_options.platform = Some(platform);
+        self
+    }
+
+    /// Run the tests in a random order, from the seed, to
+    /// find tests that depend on the order they run in.
+    pub fn random_order_seed(mut self, seed: u32) -> Self {
+        self.test_options.random_order_seed = Some(seed);
+        self
+    }
+
+    /// Run the tests in the order listed in the file,
+    /// one full test name per line.
+    pub fn order_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.test_options.order_file = Some(path.into());
+        self
+    }
+
+    /// Run the tests, returning any compile errors,
+    /// or the results if compilation succeeded.
+    pub fn execute(self) -> Result<TestOutput> {
+        self.run(&self.options)
+    }
+
+    /// Run on another thread, returning a channel of progress
+    /// events, including each test's (which closes when the run
+    /// is done), and a handle to join for the results.
+    pub fn run_with_events(self) -> (Receiver<RunEvent>, JoinHandle<Result<TestOutput>>)
+        where 'a: 'static
+    {
+        let (tx, rx) = mpsc::channel();
+        let run = self.test_events(true).on_event(move |event| {
+            let _ = tx.send(event);
+        });
+        (rx, thread::spawn(move || run.execute()))
+    }
+
+    /// Run with the given editor options, rather than
+    /// the run's own; for [`crate::test`], which borrows them.
+    pub(crate) fn run(&self, options: &RunOptions) -> Result<TestOutput> {
+        // Clear out coverage from a previous run; this is done
+        // here rather than per mode so both modes' results are kept
+        if self.test_options.coverage {
+            let _ = fs::remove_dir_all(coverage::coverage_dir(&self.project_path));
+        }
+        if self.mode == TestMode::EditMode && self.test_options.platform.is_some() {
+            bail!("Edit mode tests can only run in the editor; use a platform with play mode tests");
+        }
+
+        match self.mode {
+            TestMode::All if passed_results(&options.extra_args, &self.project_path)?.is_some() =>
+                bail!("Running both modes with `-testResults` would write both to the same path; use `--results-out`, which keeps them apart"),
+            TestMode::All => {
+                let (errors, edit) = self.run_mode(options, TestMode::EditMode)?;
+                let Some(edit) = edit else { return Ok((errors, None)) };
+                let (errors, play) = self.run_mode(options, TestMode::PlayMode)?;
+                Ok((errors, play.map(|play| TestSummary::merge(vec![edit, play]))))
+            },
+            ref mode => self.run_mode(options, mode.clone()),
+        }
+    }
+
+    /// Run the tests for a single mode.
+    fn run_mode(&self, options: &RunOptions, mode: TestMode) -> Result<TestOutput> {
+        let target = match mode {
+            TestMode::PlayMode => self.test_options.platform.as_ref(),
+            _ => None,
+        };
+        let platform = match (&mode, target) {
+            (TestMode::EditMode, _) => "EditMode",
+            (TestMode::PlayMode, None) => "PlayMode",
+            (TestMode::PlayMode, Some(target)) => target.test_platform(),
+            (TestMode::All, _) => unreachable!("both modes are run separately"),
+        };
+
+        // Results asked for with an extra `-testResults` are
+        // written there, so are read from (and kept) there
+        let passed_results = passed_results(&options.extra_args, &self.project_path)?;
+        if let (Some(_), Some(_)) = (&passed_results, &self.results_path) {
+            bail!("Both a results path (`--results-out`) and `-testResults` were given; pass just one");
+        }
+        let results_file = match (&self.results_path, &passed_results) {
+            (_, Some(path)) => path.clone(),
+            // Made absolute, as the editor would resolve
+            // a relative one from the project
+            (Some(path), _) if self.mode == TestMode::All => std::path::absolute(mode_path(path, platform))?,
+            (Some(path), _) => std::path::absolute(path)?,
+            (None, None) => std::env::temp_dir().join(format!("unitool-results-{}-{}.xml",
+                    process::id(), RUN_COUNT.fetch_add(1, Ordering::Relaxed))),
+        };
+        // With none found, Unity runs all the mode's tests, which
+        // isn't what's wanted if they're in the ones skipped
+        let assemblies = match &self.assemblies {
+            Some(assemblies) => Some(assemblies.clone()),
+            None => {
+                let found = assemblies::discover(&self.project_path)?;
+                let names = found.for_mode(&mode);
+                if names.is_none() && !found.invalid.is_empty() {
+                    let skipped: Vec<String> = found.invalid.iter()
+                        .map(|invalid| format!("  {}: {}", invalid.path.display(), invalid.error))
+                        .collect();
+                    let mode = if mode == TestMode::EditMode { "edit mode" } else { "play mode" };
+                    bail!("Found no {} test assemblies, and couldn't read these assembly definitions:\n{}",
+                        mode, skipped.join("\n"));
+                }
+                names
+            },
+        };
+
+        let results_path = results_file.to_string_lossy();
+        let filters = self.filters.as_deref().unwrap_or("");
+        let mut args = vec![
+          "-runTests",
+          "-testPlatform", platform,
+          "-testFilter", filters,
+        ];
+        if passed_results.is_none() {
+            args.extend(["-testResults", &results_path]);
+        }
+        if let Some(assemblies) = &assemblies {
+            args.extend(["-assemblyNames", assemblies]);
+        }
+        if let Some(categories) = &self.categories {
+            args.extend(["-testCategory", categories]);
+        }
+        if let Some(target) = target {
+            args.extend(["-buildTarget", target.arg()]);
+        }
+        let seed = self.test_options.random_order_seed.map(|seed| seed.to_string());
+        if let Some(seed) = &seed {
+            args.extend(["-randomOrderSeed", seed]);
+        }
+        // Made absolute, as the editor may not resolve it from here
+        let order_file = match &self.test_options.order_file {
+            Some(path) => Some(fs::canonicalize(path)
+                .map_err(|err| anyhow!("Couldn't read the order file `{}`: {}", path.display(), err))?
+                .to_string_lossy().to_string()),
+            None => None,
+        };
+        if let Some(order_file) = &order_file {
+            args.extend(["-orderedTestListFile", order_file]);
+        }
+        // Made absolute, as the editor would resolve
+        // a relative one from the project
+        let coverage_args = if self.test_options.coverage {
+            let dir = std::path::absolute(coverage::coverage_dir(&self.project_path))?;
+            coverage::coverage_args(&dir, assemblies.as_deref().unwrap_or(""))
+        } else {
+            vec![]
+        };
+        args.extend(coverage_args.iter().map(|arg| arg.as_str()));
+
+        // Edit mode tests need to run synchronously
+        // or they lock up Unity in batchmode
+        if mode == TestMode::EditMode {
+            args.push("-runSynchronously");
+        }
+
+        // Clear out any results from a previous run
+        // so they can't be mistaken for this run's
+        let _ = fs::remove_file(&results_file);
+        if let Some(dir) = results_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Per-test events need the editor's help, as
+        // does knowing which test hung if it's killed
+        let test_events = options.test_events && options.on_event.is_some();
+        let _script = if test_events || options.timeout.is_some() {
+            Some(InjectedScript::inject(&self.project_path, scripts::TEST_EVENTS)?)
+        } else {
+            None
+        };
+
+        let output = match target {
+            Some(target) => find_build_editor(&self.project_path, options, target)
+                .and_then(|editor| run_editor(&editor, Some(&self.project_path), options, args)),
+            None => run_unity(&self.project_path, options, args),
+        };
+        let results = match &output {
+            Ok(output) if output.errors.is_empty() => Some(load_test_results(&results_file)),
+            _ => None,
+        };
+        if self.results_path.is_none() && passed_results.is_none() {
+            let _ = fs::remove_file(&results_file);
+        }
+
+        let output = output?;
+        match results {
+            Some(results) => {
+                let results = results.map_err(|problem| ResultsError {
+                    problem,
+                    exit_code: output.exit_code,
+                    log_tail: output.log_tail,
+                })?;
+                Ok((output.errors, Some(results)))
+            },
+            None => Ok((output.errors, None)),
+        }
+    }
+}
+
+/// Where the extra editor args ask for results to be written, if
+/// anywhere. A relative path is resolved from the project, as the
+/// editor resolves it.
+fn passed_results(extra_args: &[String], project_path: &Path) -> Result<Option<PathBuf>> {
+    let Some(idx) = extra_args.iter().position(|arg| arg == "-testResults") else { return Ok(None) };
+    match extra_args.get(idx + 1) {
+        Some(path) if !path.starts_with('-') => Ok(Some(project_path.join(path))),
+        _ => bail!("`-testResults` needs a path"),
+    }
+}
+
+/// The path with the test platform added to its
+/// name, e.g. `results.xml` to `results-EditMode.xml`.
+fn mode_path(path: &Path, platform: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, platform, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, platform),
+    };
+    path.with_file_name(name)
+}
@@ -0,0 +1,228 @@
+//! Creating new projects: which template to create them from (the
+//! ones that ship with the editor, under its
+//! `Resources/PackageManager/ProjectTemplates`, or one of the user's),
+//! and seeding them with test assemblies.
+//!
+//! A user's template is a template package (a `.tgz`, or its unpacked
+//! directory), with the project under `ProjectData~`, or just a project.
+//! Its `Packages/manifest.json` and `ProjectSettings` are copied
+//! into the new project:
+//!
+//! ```text
+//! package/ProjectData~/Packages/manifest.json
+//! package/ProjectData~/ProjectSettings/ProjectSettings.asset
+//! ```
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{fmt::Display, fs, io, path::{Path, PathBuf}, process::Command, str::FromStr};
+use crate::editors::compare_versions;
+
+/// The test assemblies a new project can be seeded with, as
+/// (path relative to the project, contents), matching the
+/// `EditTests;PlayTests` layout unitool's docs assume.
+const TEST_LAYOUT: &[(&str, &str)] = &[
+    ("Assets/Tests/EditTests/EditTests.asmdef", include_str!("scaffold/EditTests.asmdef")),
+    ("Assets/Tests/EditTests/ExampleTests.cs", include_str!("scaffold/EditTests.cs")),
+    ("Assets/Tests/PlayTests/PlayTests.asmdef", include_str!("scaffold/PlayTests.asmdef")),
+    ("Assets/Tests/PlayTests/ExampleTests.cs", include_str!("scaffold/PlayTests.cs")),
+];
+
+/// The built-in templates, by name.
+const BUILT_IN: &[(&str, Template)] = &[
+    ("3d", Template::ThreeD),
+    ("2d", Template::TwoD),
+    ("urp", Template::Urp),
+    ("hdrp", Template::Hdrp),
+    ("vr", Template::Vr),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Template {
+    /// The built-in render pipeline, in 3D
+    ThreeD,
+
+    /// The built-in render pipeline, in 2D
+    TwoD,
+
+    /// The Universal Render Pipeline
+    Urp,
+
+    /// The High Definition Render Pipeline
+    Hdrp,
+
+    /// For VR, with the XR plugins
+    Vr,
+
+    /// The user's own, at this path
+    Custom(PathBuf),
+}
+impl Template {
+    /// The start of the template package's name,
+    /// or `None` for the user's own.
+    fn package(&self) -> Option<&'static str> {
+        match self {
+            Template::ThreeD => Some("com.unity.template.3d"),
+            Template::TwoD => Some("com.unity.template.2d"),
+            Template::Urp => Some("com.unity.template.universal"),
+            Template::Hdrp => Some("com.unity.template.hdrp-blank"),
+            Template::Vr => Some("com.unity.template.vr"),
+            Template::Custom(_) => None,
+        }
+    }
+}
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, template)) = BUILT_IN.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            return Ok(template.clone());
+        }
+        let path = PathBuf::from(s);
+        if path.exists() {
+            Ok(Template::Custom(path))
+        } else {
+            let names: Vec<&str> = BUILT_IN.iter().map(|(name, _)| *name).collect();
+            Err(format!("`{}` isn't one of {}, or the path to a template", s, names.join(", ")))
+        }
+    }
+}
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Template::Custom(path) => write!(f, "{}", path.display()),
+            template => {
+                let name = BUILT_IN.iter()
+                    .find(|(_, built_in)| built_in == template)
+                    .map_or("", |(name, _)| *name);
+                write!(f, "{}", name)
+            },
+        }
+    }
+}
+impl Serialize for Template {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// A project that was created.
+#[derive(Debug, Serialize)]
+pub struct NewProject {
+    pub path: PathBuf,
+
+    /// The editor version it was created with
+    pub version: Option<String>,
+    pub template: Template,
+
+    /// The test assembly files it was seeded with, if any
+    pub tests: Vec<PathBuf>,
+
+    /// What went wrong when it was first opened (and its
+    /// assets imported), which should be nothing
+    pub import: crate::smoke::SmokeReport,
+}
+
+/// The editor's template for `template`, to create a project
+/// from with `-cloneFromTemplate`, or `None` for the user's own.
+pub(crate) fn template_file(editor: &Path, template: &Template) -> Result<Option<PathBuf>> {
+    let Some(package) = template.package() else { return Ok(None) };
+    let dir = templates_dir(editor);
+    // By version, e.g. from `com.unity.template.3d-8.1.1.tgz`,
+    // and not e.g. `com.unity.template.3d-cross-platform-...`
+    let mut found: Vec<(String, PathBuf)> = fs::read_dir(&dir).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = name.strip_prefix(package)?
+                .strip_prefix(['-', '@'])?
+                .strip_suffix(".tgz")?;
+            version.starts_with(|c: char| c.is_ascii_digit())
+                .then(|| (version.to_string(), entry.path()))
+        })
+        .collect();
+    found.sort_by(|(a, _), (b, _)| compare_versions(a, b));
+    match found.pop() {
+        Some((_, file)) => Ok(Some(file)),
+        None => bail!("The editor at {} has no {} template (looked in {})",
+            editor.display(), package, dir.display()),
+    }
+}
+
+/// Where the editor keeps its project templates.
+fn templates_dir(editor: &Path) -> PathBuf {
+    let templates = "Resources/PackageManager/ProjectTemplates";
+    if cfg!(target_os = "macos") {
+        // `Unity.app/Contents/MacOS/Unity`
+        editor.parent().and_then(Path::parent).unwrap_or(editor).join(templates)
+    } else {
+        // `Editor/Unity`
+        editor.parent().unwrap_or(editor).join("Data").join(templates)
+    }
+}
+
+/// Add the edit and play mode test assemblies to the project,
+/// returning the files added.
+pub(crate) fn seed_tests(project_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut added = vec![];
+    for (path, contents) in TEST_LAYOUT {
+        let path = project_path.join(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, contents)?;
+        added.push(path);
+    }
+    Ok(added)
+}
+
+/// Copy the user's template's `Packages/manifest.json` and
+/// `ProjectSettings` into the project, unpacking it (with `tar`)
+/// into a temp directory first if it's a `.tgz`.
+pub(crate) fn apply_template(project_path: &Path, template: &Path) -> Result<()> {
+    if template.is_dir() {
+        return copy_template(project_path, template);
+    }
+    let dir = std::env::temp_dir().join(format!("unitool-template-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = unpack(template, &dir).and_then(|_| copy_template(project_path, &dir));
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn unpack(file: &Path, dir: &Path) -> Result<()> {
+    let output = Command::new("tar").arg("-xzf").arg(file).arg("-C").arg(dir).output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => anyhow!("Unpacking templates needs tar"),
+            _ => err.into(),
+        })?;
+    if !output.status.success() {
+        bail!("Couldn't unpack the template {}: {}", file.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn copy_template(project_path: &Path, template: &Path) -> Result<()> {
+    let Some(root) = [template.join("package/ProjectData~"), template.join("ProjectData~"), template.to_path_buf()]
+        .into_iter()
+        .find(|root| root.join("Packages/manifest.json").is_file() || root.join("ProjectSettings").is_dir())
+        else {
+            bail!("{} isn't a template: it has no `Packages/manifest.json` or `ProjectSettings`", template.display());
+        };
+
+    let manifest = root.join("Packages/manifest.json");
+    if manifest.is_file() {
+        fs::create_dir_all(project_path.join("Packages"))?;
+        fs::copy(&manifest, project_path.join("Packages/manifest.json"))?;
+    }
+    let settings = root.join("ProjectSettings");
+    if settings.is_dir() {
+        fs::create_dir_all(project_path.join("ProjectSettings"))?;
+        for entry in fs::read_dir(&settings)? {
+            let entry = entry?;
+            // Keeping the version of the editor it was created with
+            if !entry.file_type()?.is_file() || entry.file_name() == "ProjectVersion.txt" { continue }
+            fs::copy(entry.path(), project_path.join("ProjectSettings").join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
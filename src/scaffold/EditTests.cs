@@ -0,0 +1,13 @@
+using NUnit.Framework;
+
+namespace EditTests
+{
+    public class ExampleTests
+    {
+        [Test]
+        public void Passes()
+        {
+            Assert.That(1 + 1, Is.EqualTo(2));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+using System.Collections;
+using NUnit.Framework;
+using UnityEngine;
+using UnityEngine.TestTools;
+
+namespace PlayTests
+{
+    public class ExampleTests
+    {
+        [UnityTest]
+        public IEnumerator PassesAfterAFrame()
+        {
+            var frame = Time.frameCount;
+            yield return null;
+            Assert.That(Time.frameCount, Is.GreaterThan(frame));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Editor scripts that unitool injects into a project
+//! for the duration of a run, e.g. to drive a build
+//! through `-executeMethod`.
+
+use anyhow::Result;
+use std::{fs, path::{Path, PathBuf}};
+
+/// Where injected scripts are put, relative to the project.
+const SCRIPTS_DIR: &str = "Assets/Editor/Unitool";
+
+pub const BUILD: (&str, &str) = ("Build.cs", include_str!("scripts/Build.cs"));
+pub const TEST_EVENTS: (&str, &str) = ("TestEvents.cs", include_str!("scripts/TestEvents.cs"));
+pub const LIST_TESTS: (&str, &str) = ("ListTests.cs", include_str!("scripts/ListTests.cs"));
+pub const BUILD_BUNDLES: (&str, &str) = ("BuildBundles.cs", include_str!("scripts/BuildBundles.cs"));
+pub const BUILD_ADDRESSABLES: (&str, &str) = ("BuildAddressables.cs", include_str!("scripts/BuildAddressables.cs"));
+pub const ANALYZE_ADDRESSABLES: (&str, &str) = ("AnalyzeAddressables.cs", include_str!("scripts/AnalyzeAddressables.cs"));
+
+/// A script injected into the project, which is removed
+/// (along with any directories created for it and the
+/// `.meta` files Unity generated) when dropped.
+pub struct InjectedScript {
+    path: PathBuf,
+    created_dirs: Vec<PathBuf>,
+}
+impl InjectedScript {
+    pub fn inject(project_path: &Path, (name, source): (&str, &str)) -> Result<InjectedScript> {
+        let dir = project_path.join(SCRIPTS_DIR);
+        let mut created_dirs: Vec<PathBuf> = dir.ancestors()
+            .take_while(|dir| !dir.exists())
+            .map(|dir| dir.to_path_buf())
+            .collect();
+        created_dirs.reverse();
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(name);
+        fs::write(&path, source)?;
+        Ok(InjectedScript { path, created_dirs })
+    }
+}
+impl Drop for InjectedScript {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_file(meta_path(&self.path));
+        for dir in self.created_dirs.iter().rev() {
+            // Only succeeds if the directory is now empty
+            if fs::remove_dir(dir).is_ok() {
+                let _ = fs::remove_file(meta_path(dir));
+            }
+        }
+    }
+}
+
+fn meta_path(path: &Path) -> PathBuf {
+    let mut meta = path.as_os_str().to_owned();
+    meta.push(".meta");
+    meta.into()
+}
@@ -0,0 +1,64 @@
+// Injected by unitool to run the project's Addressables analyze rules
+// (the built-in ones, e.g. for duplicated dependencies, and any the
+// project defines), without fixing anything. Results are logged as
+// `[unitool] <key> <value>` lines, with a
+// `[unitool] finding <rule>\t<severity>\t<result>` line per result,
+// where the result is the rule's `:`-separated path to the issue.
+using System;
+using UnityEditor;
+using UnityEditor.AddressableAssets;
+using UnityEditor.AddressableAssets.Build.AnalyzeRules;
+using UnityEngine;
+
+namespace Unitool
+{
+    public static class AnalyzeAddressables
+    {
+        public static void Run()
+        {
+            var settings = AddressableAssetSettingsDefaultObject.Settings;
+            if (settings == null)
+            {
+                Log("error", "The project has no Addressables settings");
+                Log("result", "Failed");
+                EditorApplication.Exit(1);
+                return;
+            }
+
+            var failed = false;
+            foreach (var type in TypeCache.GetTypesDerivedFrom<AnalyzeRule>())
+            {
+                if (type.IsAbstract || type.GetConstructor(Type.EmptyTypes) == null)
+                    continue;
+                var rule = (AnalyzeRule)Activator.CreateInstance(type);
+                Log("rule", rule.ruleName);
+                try
+                {
+                    foreach (var result in rule.RefreshAnalysis(settings))
+                    {
+                        if (result.resultName == AnalyzeRule.noErrors)
+                            continue;
+                        Log("finding", rule.ruleName + "\t" + result.severity + "\t" + result.resultName);
+                    }
+                }
+                catch (Exception e)
+                {
+                    Log("error", rule.ruleName + ": " + e.Message);
+                    failed = true;
+                }
+                finally
+                {
+                    rule.ClearAnalysis();
+                }
+            }
+            Log("result", failed ? "Failed" : "Succeeded");
+
+            EditorApplication.Exit(failed ? 1 : 0);
+        }
+
+        static void Log(string key, string value)
+        {
+            Debug.Log("[unitool] " + key + " " + value.Replace("\n", " "));
+        }
+    }
+}
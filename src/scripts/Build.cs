@@ -0,0 +1,79 @@
+// Injected by unitool to build the player.
+// Results are logged as `[unitool] <key> <value>` lines.
+using System;
+using System.Linq;
+using UnityEditor;
+using UnityEditor.Build.Reporting;
+using UnityEngine;
+
+namespace Unitool
+{
+    public static class Build
+    {
+        public static void Run()
+        {
+            var args = Environment.GetCommandLineArgs();
+            var output = Arg(args, "-unitoolOutput");
+            var scenes = EditorBuildSettings.scenes
+                .Where(scene => scene.enabled)
+                .Select(scene => scene.path)
+                .ToArray();
+
+            var options = BuildOptions.None;
+            if (args.Contains("-unitoolDevelopment"))
+                options |= BuildOptions.Development;
+
+            // Override the player settings just for this build,
+            // so the project's own are left as they were
+            var group = BuildPipeline.GetBuildTargetGroup(EditorUserBuildSettings.activeBuildTarget);
+            var backend = PlayerSettings.GetScriptingBackend(group);
+            var stripLevel = PlayerSettings.GetManagedStrippingLevel(group);
+            var backendArg = Arg(args, "-unitoolScriptingBackend");
+            if (backendArg != null)
+                PlayerSettings.SetScriptingBackend(group, (ScriptingImplementation)Enum.Parse(typeof(ScriptingImplementation), backendArg));
+            var stripLevelArg = Arg(args, "-unitoolStripLevel");
+            if (stripLevelArg != null)
+                PlayerSettings.SetManagedStrippingLevel(group, (ManagedStrippingLevel)Enum.Parse(typeof(ManagedStrippingLevel), stripLevelArg));
+
+            BuildReport report;
+            try
+            {
+                report = BuildPipeline.BuildPlayer(new BuildPlayerOptions
+                {
+                    scenes = scenes,
+                    locationPathName = output,
+                    target = EditorUserBuildSettings.activeBuildTarget,
+                    options = options,
+                });
+            }
+            finally
+            {
+                PlayerSettings.SetScriptingBackend(group, backend);
+                PlayerSettings.SetManagedStrippingLevel(group, stripLevel);
+            }
+
+            var summary = report.summary;
+            foreach (var step in report.steps)
+                foreach (var message in step.messages)
+                    if (message.type == LogType.Error || message.type == LogType.Exception)
+                        Log("error", message.content);
+            Log("warnings", summary.totalWarnings.ToString());
+            Log("size", summary.totalSize.ToString());
+            Log("time", summary.totalTime.TotalSeconds.ToString(System.Globalization.CultureInfo.InvariantCulture));
+            Log("result", summary.result.ToString());
+
+            EditorApplication.Exit(summary.result == BuildResult.Succeeded ? 0 : 1);
+        }
+
+        static void Log(string key, string value)
+        {
+            Debug.Log("[unitool] " + key + " " + value.Replace("\n", " "));
+        }
+
+        static string Arg(string[] args, string name)
+        {
+            var idx = Array.IndexOf(args, name);
+            return idx >= 0 && idx + 1 < args.Length ? args[idx + 1] : null;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Injected by unitool to build the project's Addressables content,
+// or a content update of a previous build (only when asked to, as
+// it needs the Addressables package). Results are logged as
+// `[unitool] <key> <value>` lines, with a
+// `[unitool] bundle <name>\t<size>` line per bundle.
+using System;
+using System.IO;
+using UnityEditor;
+using UnityEditor.AddressableAssets;
+using UnityEditor.AddressableAssets.Build;
+using UnityEditor.AddressableAssets.Settings;
+using UnityEngine;
+
+namespace Unitool
+{
+    public static class BuildAddressables
+    {
+        public static void Run()
+        {
+            var settings = AddressableAssetSettingsDefaultObject.Settings;
+            if (settings == null)
+            {
+                Fail("The project has no Addressables settings");
+                return;
+            }
+
+            AddressableAssetSettings.BuildPlayerContent(out var result);
+            Report(result);
+        }
+
+        // Build only what changed since the build the content
+        // state file (`addressables_content_state.bin`) is from
+        public static void Update()
+        {
+            var settings = AddressableAssetSettingsDefaultObject.Settings;
+            if (settings == null)
+            {
+                Fail("The project has no Addressables settings");
+                return;
+            }
+            var statePath = Arg(Environment.GetCommandLineArgs(), "-unitoolContentState")
+                ?? ContentUpdateScript.GetContentStateDataPath(false);
+            if (string.IsNullOrEmpty(statePath) || !File.Exists(statePath))
+            {
+                Fail("No content state from a previous build at " + statePath + "; build the content first");
+                return;
+            }
+
+            var result = ContentUpdateScript.BuildContentUpdate(settings, statePath);
+            if (result == null)
+            {
+                Fail("The content update couldn't be built from " + statePath);
+                return;
+            }
+            Report(result);
+        }
+
+        static void Report(AddressablesPlayerBuildResult result)
+        {
+            var succeeded = string.IsNullOrEmpty(result.Error);
+            if (!succeeded)
+                Log("error", result.Error);
+            foreach (var bundle in result.AssetBundleBuildResults)
+                if (File.Exists(bundle.FilePath))
+                    Log("bundle", Path.GetFileName(bundle.FilePath) + "\t" + new FileInfo(bundle.FilePath).Length);
+            Log("output", result.OutputPath ?? "");
+            Log("time", result.Duration.ToString(System.Globalization.CultureInfo.InvariantCulture));
+            Log("result", succeeded ? "Succeeded" : "Failed");
+
+            EditorApplication.Exit(succeeded ? 0 : 1);
+        }
+
+        static void Fail(string error)
+        {
+            Log("error", error);
+            Log("result", "Failed");
+            EditorApplication.Exit(1);
+        }
+
+        static void Log(string key, string value)
+        {
+            Debug.Log("[unitool] " + key + " " + value.Replace("\n", " "));
+        }
+
+        static string Arg(string[] args, string name)
+        {
+            var idx = Array.IndexOf(args, name);
+            return idx >= 0 && idx + 1 < args.Length ? args[idx + 1] : null;
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Injected by unitool to build the project's AssetBundles.
+// Results are logged as `[unitool] <key> <value>` lines,
+// with a `[unitool] bundle <name>\t<size>` line per bundle.
+using System;
+using System.Diagnostics;
+using System.IO;
+using UnityEditor;
+using UnityEngine;
+using Debug = UnityEngine.Debug;
+
+namespace Unitool
+{
+    public static class BuildBundles
+    {
+        public static void Run()
+        {
+            var output = Arg(Environment.GetCommandLineArgs(), "-unitoolOutput");
+            Directory.CreateDirectory(output);
+
+            // Bundle errors are only logged, not returned
+            Application.logMessageReceived += OnLog;
+            var timer = Stopwatch.StartNew();
+            var manifest = BuildPipeline.BuildAssetBundles(output,
+                BuildAssetBundleOptions.None, EditorUserBuildSettings.activeBuildTarget);
+            Application.logMessageReceived -= OnLog;
+
+            if (manifest != null)
+                foreach (var name in manifest.GetAllAssetBundles())
+                    Log("bundle", name + "\t" + new FileInfo(Path.Combine(output, name)).Length);
+            Log("time", timer.Elapsed.TotalSeconds.ToString(System.Globalization.CultureInfo.InvariantCulture));
+            Log("result", manifest != null ? "Succeeded" : "Failed");
+
+            EditorApplication.Exit(manifest != null ? 0 : 1);
+        }
+
+        static void OnLog(string message, string stackTrace, LogType type)
+        {
+            if ((type == LogType.Error || type == LogType.Exception) && !message.StartsWith("[unitool]"))
+                Log("error", message);
+        }
+
+        static void Log(string key, string value)
+        {
+            Debug.Log("[unitool] " + key + " " + value.Replace("\n", " "));
+        }
+
+        static string Arg(string[] args, string name)
+        {
+            var idx = Array.IndexOf(args, name);
+            return idx >= 0 && idx + 1 < args.Length ? args[idx + 1] : null;
+        }
+    }
+}
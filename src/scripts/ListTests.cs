@@ -0,0 +1,74 @@
+// Injected by unitool to list the tests without running them.
+// Each test is logged as a `[unitool] test <mode>\t<assembly>\t<full name>\t<categories>`
+// line, then `[unitool] done` once every mode has been listed.
+using System;
+using System.Collections.Generic;
+using System.Linq;
+using UnityEditor;
+using UnityEditor.TestTools.TestRunner.Api;
+using UnityEngine;
+
+namespace Unitool
+{
+    public static class ListTests
+    {
+        static TestRunnerApi api;
+        static Queue<TestMode> modes;
+
+        public static void Run()
+        {
+            var mode = Arg(Environment.GetCommandLineArgs(), "-unitoolTestMode");
+            modes = new Queue<TestMode>();
+            if (mode != "PlayMode")
+                modes.Enqueue(TestMode.EditMode);
+            if (mode != "EditMode")
+                modes.Enqueue(TestMode.PlayMode);
+
+            api = ScriptableObject.CreateInstance<TestRunnerApi>();
+            Next();
+        }
+
+        // The lists arrive asynchronously, so the editor
+        // is kept open until the last one is in
+        static void Next()
+        {
+            if (modes.Count == 0)
+            {
+                Log("done");
+                EditorApplication.Exit(0);
+                return;
+            }
+            var mode = modes.Dequeue();
+            api.RetrieveTestList(mode, root =>
+            {
+                Visit(root, mode);
+                Next();
+            });
+        }
+
+        static void Visit(ITestAdaptor test, TestMode mode)
+        {
+            if (!test.IsSuite)
+            {
+                var assembly = test.TypeInfo != null ? test.TypeInfo.Assembly.GetName().Name : "";
+                var categories = string.Join(";", test.Categories ?? new string[0]);
+                Log("test " + string.Join("\t", new[] { mode.ToString(), assembly, test.FullName, categories }));
+                return;
+            }
+            foreach (var child in test.Children)
+                Visit(child, mode);
+        }
+
+        // Skip the stack trace Unity would otherwise log after each line
+        static void Log(string msg)
+        {
+            Debug.LogFormat(LogType.Log, LogOption.NoStacktrace, null, "[unitool] {0}", msg.Replace("\n", " "));
+        }
+
+        static string Arg(string[] args, string name)
+        {
+            var idx = Array.IndexOf(args, name);
+            return idx >= 0 && idx + 1 < args.Length ? args[idx + 1] : null;
+        }
+    }
+}
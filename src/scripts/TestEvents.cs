@@ -0,0 +1,42 @@
+// Injected by unitool to report test progress.
+// Events are logged as `[unitool] <event> <args>` lines.
+using UnityEditor;
+using UnityEditor.TestTools.TestRunner.Api;
+using UnityEngine;
+
+namespace Unitool
+{
+    [InitializeOnLoad]
+    public static class TestEvents
+    {
+        static TestEvents()
+        {
+            var api = ScriptableObject.CreateInstance<TestRunnerApi>();
+            api.RegisterCallbacks(new Callbacks());
+        }
+
+        class Callbacks : ICallbacks
+        {
+            public void RunStarted(ITestAdaptor tests) { }
+            public void RunFinished(ITestResultAdaptor result) { }
+
+            public void TestStarted(ITestAdaptor test)
+            {
+                if (!test.IsSuite)
+                    Log("test-started " + test.FullName);
+            }
+
+            public void TestFinished(ITestResultAdaptor result)
+            {
+                if (!result.Test.IsSuite)
+                    Log("test-finished " + result.TestStatus + " " + result.Test.FullName);
+            }
+        }
+
+        // Skip the stack trace Unity would otherwise log after each line
+        static void Log(string msg)
+        {
+            Debug.LogFormat(LogType.Log, LogOption.NoStacktrace, null, "[unitool] {0}", msg);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Coordinating a limited number of Unity license seats
+//! between unitool processes on the same machine.
+//!
+//! Each seat is a lock file in a shared directory, holding
+//! the PID of the process using it. A seat is freed when
+//! its holder is dropped, or if the holding process died.
+
+use anyhow::Result;
+use std::{env, fs, io::{ErrorKind, Write}, path::{Path, PathBuf}, process::Command, thread, time::Duration};
+
+/// Environment variable for the number of available seats.
+pub const SEATS_VAR: &str = "UNITOOL_LICENSE_SEATS";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A held license seat, released on drop.
+#[derive(Debug)]
+pub struct Seat {
+    path: PathBuf,
+}
+impl Drop for Seat {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The number of seats to coordinate, if configured.
+pub fn seats_from_env() -> Option<usize> {
+    env::var(SEATS_VAR).ok()?.parse().ok().filter(|n| *n > 0)
+}
+
+/// Wait for one of `seats` seats to be free, and take it.
+/// `on_wait` is called with the number of seats in use
+/// each time a free seat isn't found.
+pub fn acquire(seats: usize, mut on_wait: impl FnMut(usize)) -> Result<Seat> {
+    let dir = env::temp_dir().join("unitool-seats");
+    fs::create_dir_all(&dir)?;
+    loop {
+        let mut in_use = 0;
+        for i in 0..seats {
+            let path = dir.join(format!("seat-{}.lock", i));
+            if claim(&path)? {
+                return Ok(Seat { path });
+            }
+            in_use += 1;
+        }
+        on_wait(in_use);
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Take the lock file (e.g. a seat) for this process, if it's free
+/// or its holder is gone, returning whether it was taken.
+///
+/// A stale file isn't removed and created again, as another process
+/// could take it in between (and have it removed from under it by a
+/// third that also found it stale). Instead, the one process that
+/// creates its `.replacing` file renames its own over it.
+pub(crate) fn claim(path: &Path) -> Result<bool> {
+    if create(path)? {
+        return Ok(true);
+    }
+    if !is_stale(path) {
+        return Ok(false);
+    }
+
+    let replacing = path.with_extension("lock.replacing");
+    if !create(&replacing)? {
+        // Left by a process that died while replacing it
+        if is_stale(&replacing) {
+            let _ = fs::remove_file(&replacing);
+        }
+        return Ok(false);
+    }
+    // Checked again, as it may have been replaced since
+    let claimed = is_stale(path) && {
+        let own = path.with_extension(format!("lock.{}", std::process::id()));
+        fs::write(&own, std::process::id().to_string())?;
+        fs::rename(&own, path)?;
+        true
+    };
+    let _ = fs::remove_file(&replacing);
+    Ok(claimed)
+}
+
+/// Create the lock file, holding this process's
+/// PID, returning whether it didn't already exist.
+fn create(path: &Path) -> Result<bool> {
+    match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            write!(file, "{}", std::process::id())?;
+            Ok(true)
+        },
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Whether the process holding a lock file (e.g. a seat) is gone.
+pub(crate) fn is_stale(path: &Path) -> bool {
+    match fs::read_to_string(path) {
+        Ok(pid) => match pid.trim().parse::<u32>() {
+            Ok(pid) => !is_running(pid),
+
+            // Possibly still being written by its holder
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn is_running(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+#[cfg(windows)]
+pub(crate) fn is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains(&pid.to_string()))
+        .unwrap_or(true)
+}
@@ -0,0 +1,417 @@
+//! A long-running server for editor plugins and IDEs, which
+//! keeps state between runs (the installed editors, compile
+//! caches and run history) and takes requests over a local TCP
+//! socket.
+//!
+//! Anything on the machine can connect, so each request needs the
+//! server's token, a random one written on start to
+//! `.unitool/serve-token` where it was started (readable only by the
+//! user that started it). Requests and responses are JSON objects,
+//! one per line. A request has the `token`, an `id`, a `method` and
+//! its `params`:
+//!
+//! ```text
+//! {"token": "...", "id": 1, "method": "compile", "params": {"project": "path/to/project", "cached": true}}
+//! {"token": "...", "id": 2, "method": "test", "params": {"project": "path/to/project", "mode": "edit-mode", "filters": "Game.Core"}}
+//! {"token": "...", "id": 3, "method": "status"}
+//! {"token": "...", "id": 4, "method": "cancel", "params": {"request": 2}}
+//! ```
+//!
+//! While a run goes its progress is streamed as `{"id": 2, "event": ...}`
+//! (see [`RunEvent`]), and it finishes with `{"id": 2, "result": ...}`
+//! or `{"id": 2, "error": "..."}`. Several runs can go at once,
+//! on different projects.
+
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    collections::{VecDeque, hash_map::RandomState}, fs, hash::{BuildHasher, Hasher}, io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf}, sync::{Arc, Mutex}, thread, time::{Duration, Instant},
+};
+use crate::{
+    GraphicsApi, RunOptions, TestMode, TestSummary, UNITY_PATH_VAR,
+    cache, compiler::CompileError, editors::{self, Editor}, events::RunEvent,
+    json::{self, Value}, lock::{self, LockMode}, project_version, state_dir, watchdog,
+};
+
+pub const DEFAULT_PORT: u16 = 7417;
+
+/// Where the token is written, under the state directory.
+const TOKEN_FILE: &str = "serve-token";
+
+/// How many finished runs to remember.
+const MAX_HISTORY: usize = 20;
+
+/// Options for the server, applied to every run.
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// Listen on this port (on localhost); 0 picks a free one
+    pub port: u16,
+
+    /// Overrides which editor binary is used
+    pub unity_path: Option<PathBuf>,
+
+    pub timeout: Option<Duration>,
+    pub nographics: bool,
+    pub graphics_api: Option<GraphicsApi>,
+    pub extra_args: Vec<String>,
+    pub retries: u32,
+    pub retry_backoff: Duration,
+    pub keep_logs: usize,
+    pub docker: Option<crate::docker::Image>,
+    pub severities: crate::compiler::SeverityOverrides,
+    pub accept_api_update: bool,
+}
+impl ServeOptions {
+    /// Editor options for a run on the project, using already
+    /// found editors rather than looking for them again.
+    pub(crate) fn run_options<'a>(&self, editors: &[Editor], project: &Path) -> RunOptions<'a> {
+        let unity_path = self.unity_path.clone()
+            .or_else(|| std::env::var_os(UNITY_PATH_VAR).map(PathBuf::from))
+            .or_else(|| {
+                let version = project_version(project)?;
+                editors.iter()
+                    .find(|editor| editor.version == version)
+                    .map(|editor| editor.path.clone())
+            });
+        RunOptions {
+            unity_path,
+            on_log: None,
+            on_event: None,
+            test_events: false,
+            timeout: self.timeout,
+            nographics: self.nographics,
+            graphics_api: self.graphics_api,
+            extra_args: self.extra_args.clone(),
+            retries: self.retries,
+            retry_backoff: self.retry_backoff,
+            on_retry: None,
+            keep_logs: self.keep_logs,
+            on_log_saved: None,
+            docker: self.docker.clone(),
+            severities: self.severities.clone(),
+            accept_api_update: self.accept_api_update,
+        }
+    }
+}
+
+/// A run in progress.
+#[derive(Debug, Clone, Serialize)]
+struct ActiveRun {
+    id: u64,
+    method: String,
+    project: PathBuf,
+
+    /// The editor's pid, once it's started
+    #[serde(skip)]
+    pid: Option<u32>,
+
+    #[serde(skip)]
+    cancelled: bool,
+
+    #[serde(skip)]
+    started: Instant,
+}
+
+/// A finished run.
+#[derive(Debug, Clone, Serialize)]
+struct PastRun {
+    id: u64,
+    method: String,
+    project: PathBuf,
+
+    /// `succeeded`, `failed`, `cancelled` or `error`
+    outcome: &'static str,
+    seconds: f64,
+}
+
+#[derive(Default)]
+struct State {
+    options: ServeOptions,
+    token: String,
+    editors: Vec<Editor>,
+    active: Vec<ActiveRun>,
+    history: VecDeque<PastRun>,
+}
+type Shared = Arc<Mutex<State>>;
+
+/// Listen for requests until the process is stopped. `on_listen`
+/// is called with the address once bound, and where the token is.
+pub fn serve(options: ServeOptions, on_listen: impl FnOnce(SocketAddr, &Path)) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, options.port))?;
+    let token = new_token();
+    let token_path = state_dir(&std::env::current_dir()?).join(TOKEN_FILE);
+    write_token(&token_path, &token)
+        .map_err(|err| anyhow!("Couldn't write the token to {}: {}", token_path.display(), err))?;
+    on_listen(listener.local_addr()?, &token_path);
+
+    let state = Arc::new(Mutex::new(State {
+        options,
+        token,
+        editors: editors::installed_editors(),
+        ..State::default()
+    }));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let state = state.clone();
+        thread::spawn(move || {
+            let _ = handle_connection(stream, state);
+        });
+    }
+    Ok(())
+}
+
+/// The write half of a connection, shared by its runs.
+#[derive(Clone)]
+struct Responder(Arc<Mutex<TcpStream>>);
+impl Responder {
+    fn send(&self, message: &impl Serialize) {
+        let Ok(line) = json::to_string(message) else { return };
+        let mut stream = self.0.lock().unwrap();
+        let _ = writeln!(stream, "{}", line);
+    }
+
+    fn event(&self, id: u64, event: &RunEvent) {
+        #[derive(Serialize)]
+        struct Event<'a> { id: u64, event: &'a RunEvent }
+        self.send(&Event { id, event });
+    }
+
+    fn result(&self, id: u64, result: &impl Serialize) {
+        #[derive(Serialize)]
+        struct Success<'a, T: Serialize> { id: u64, result: &'a T }
+        self.send(&Success { id, result });
+    }
+
+    fn error(&self, id: u64, err: &anyhow::Error) {
+        #[derive(Serialize)]
+        struct Failure { id: u64, error: String }
+        self.send(&Failure { id, error: err.to_string() });
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: Shared) -> Result<()> {
+    let responder = Responder(Arc::new(Mutex::new(stream.try_clone()?)));
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match json::parse(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                responder.error(0, &anyhow!("Invalid request: {}", err));
+                continue;
+            },
+        };
+        let id = request.get("id").and_then(as_id).unwrap_or(0);
+        let token = request.get("token").and_then(|token| token.as_str());
+        if token != Some(state.lock().unwrap().token.as_str()) {
+            responder.error(id, &anyhow!("Missing or wrong `token` (see `.unitool/{}` where the server was started)", TOKEN_FILE));
+            continue;
+        }
+        let params = request.get("params").cloned().unwrap_or(Value::Object(vec![]));
+        match request.get("method").and_then(|method| method.as_str()) {
+            Some("status") => responder.result(id, &status(&state)),
+            Some("cancel") => match params.get("request").and_then(as_id) {
+                Some(target) => {
+                    #[derive(Serialize)]
+                    struct Cancelled { cancelled: bool }
+                    responder.result(id, &Cancelled { cancelled: cancel(&state, target) });
+                },
+                None => responder.error(id, &anyhow!("`cancel` needs the `request` to cancel")),
+            },
+            Some(method @ ("compile" | "test")) => {
+                let (method, state, responder) = (method.to_string(), state.clone(), responder.clone());
+                thread::spawn(move || run(id, &method, &params, &state, &responder));
+            },
+            Some(method) => responder.error(id, &anyhow!("Unknown method `{}`", method)),
+            None => responder.error(id, &anyhow!("The request has no `method`")),
+        }
+    }
+    Ok(())
+}
+
+/// 128 random bits, as hex, from std's randomly keyed hasher.
+fn new_token() -> String {
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+/// Write the token where only this user can read it.
+fn write_token(path: &Path, token: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // An existing file keeps its permissions otherwise
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    writeln!(options.open(path)?, "{}", token)
+}
+
+fn as_id(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) if *n >= 0. && n.fract() == 0. => Some(*n as u64),
+        _ => None,
+    }
+}
+
+#[derive(Serialize)]
+struct Status {
+    active: Vec<ActiveRun>,
+    history: Vec<PastRun>,
+    editors: Vec<Editor>,
+}
+
+fn status(state: &Shared) -> Status {
+    let state = state.lock().unwrap();
+    Status {
+        active: state.active.clone(),
+        history: state.history.iter().cloned().collect(),
+        editors: state.editors.clone(),
+    }
+}
+
+/// Cancel a run, killing its editor if it's started.
+/// Returns whether the run was found.
+fn cancel(state: &Shared, id: u64) -> bool {
+    let mut state = state.lock().unwrap();
+    let Some(run) = state.active.iter_mut().find(|run| run.id == id) else { return false };
+    run.cancelled = true;
+    if let Some(pid) = run.pid {
+        watchdog::kill_tree(pid);
+    }
+    true
+}
+
+/// Run a compile or test request, streaming its events.
+fn run(id: u64, method: &str, params: &Value, state: &Shared, responder: &Responder) {
+    let Some(project) = params.get("project").and_then(|project| project.as_str()).map(PathBuf::from) else {
+        responder.error(id, &anyhow!("`{}` needs a `project`", method));
+        return;
+    };
+    state.lock().unwrap().active.push(ActiveRun {
+        id,
+        method: method.to_string(),
+        project: project.clone(),
+        pid: None,
+        cancelled: false,
+        started: Instant::now(),
+    });
+
+    let on_state = state.clone();
+    let on_responder = responder.clone();
+    let mut options = {
+        let state = state.lock().unwrap();
+        state.options.run_options(&state.editors, &project)
+    };
+    options.test_events = true;
+    options.on_event = Some(Box::new(move |event: RunEvent| {
+        if let RunEvent::EditorStarted { pid } = event {
+            let mut state = on_state.lock().unwrap();
+            if let Some(run) = state.active.iter_mut().find(|run| run.id == id) {
+                run.pid = Some(pid);
+
+                // Cancelled before the editor had started
+                if run.cancelled {
+                    watchdog::kill_tree(pid);
+                }
+            }
+        }
+        on_responder.event(id, &event);
+    }));
+
+    let result = match method {
+        "compile" => compile(&project, &options, params, responder, id),
+        _ => test(&project, &options, params, responder, id),
+    };
+
+    let mut state = state.lock().unwrap();
+    let Some(idx) = state.active.iter().position(|run| run.id == id) else { return };
+    let run = state.active.remove(idx);
+    let outcome = match result {
+        _ if run.cancelled => {
+            responder.error(id, &anyhow!("Cancelled"));
+            "cancelled"
+        },
+        Ok(true) => "succeeded",
+        Ok(false) => "failed",
+        Err(err) => {
+            responder.error(id, &err);
+            "error"
+        },
+    };
+    state.history.push_front(PastRun {
+        id,
+        method: run.method,
+        project: run.project,
+        outcome,
+        seconds: run.started.elapsed().as_secs_f64(),
+    });
+    state.history.truncate(MAX_HISTORY);
+}
+
+/// Compile, sending the result. Returns whether it succeeded.
+fn compile(project: &Path, options: &RunOptions, params: &Value, responder: &Responder, id: u64) -> Result<bool> {
+    #[derive(Serialize)]
+    struct Compiled<'a> {
+        errors: Vec<&'a CompileError>,
+        warnings: Vec<&'a String>,
+        cached: bool,
+    }
+
+    let cached = match params.get("cached").and_then(|cached| cached.as_bool()) {
+        Some(true) => cache::lookup(project, options),
+        _ => None,
+    };
+    let (output, cached) = match cached {
+        Some(output) => (output, true),
+        None => {
+            let _lock = lock::lock_project(project, LockMode::Fail, |_| {})?;
+            (crate::compile(project, options)?, false)
+        },
+    };
+    let mut errors: Vec<_> = output.errors.iter().collect();
+    errors.sort();
+    let mut warnings: Vec<_> = output.warnings.iter().collect();
+    warnings.sort();
+    responder.result(id, &Compiled { errors, warnings, cached });
+    Ok(output.errors.is_empty())
+}
+
+/// Run tests, sending the result. Returns whether they all passed.
+fn test(project: &Path, options: &RunOptions, params: &Value, responder: &Responder, id: u64) -> Result<bool> {
+    #[derive(Serialize)]
+    struct Tested<'a> {
+        errors: Vec<&'a CompileError>,
+        results: Option<&'a TestSummary>,
+    }
+
+    let param = |key: &str| params.get(key).and_then(|value| value.as_str()).map(|value| value.to_string());
+    let mode = match param("mode") {
+        Some(mode) => match TestMode::from_str(&mode, true) {
+            Ok(mode) => mode,
+            Err(_) => bail!("Unknown test mode `{}`; use `edit-mode`, `play-mode` or `all`", mode),
+        },
+        None => TestMode::EditMode,
+    };
+
+    let _lock = lock::lock_project(project, LockMode::Fail, |_| {})?;
+    let (errors, results) = crate::test(project, options, mode,
+        param("assemblies").as_deref(), param("filters"), param("categories"),
+        &Default::default())?;
+    let mut sorted: Vec<_> = errors.iter().collect();
+    sorted.sort();
+    responder.result(id, &Tested { errors: sorted, results: results.as_ref() });
+    Ok(errors.is_empty() && results.is_some_and(|results| results.failed() == 0))
+}
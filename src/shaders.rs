@@ -0,0 +1,137 @@
+//! Shader errors and warnings, from the editor log. They're logged
+//! when shaders are imported, in their own form rather than as
+//! `error CS`, followed by which pass and variant was compiling:
+//!
+//! ```text
+//! Shader error in 'Custom/Water': undeclared identifier 'foam' at Assets/Shaders/Water.shader(42) (on d3d11)
+//!
+//! Compiling Subshader: 0, Pass: ForwardBase, Vertex program with DIRECTIONAL
+//! ```
+//!
+//! Older versions give just the line, e.g. `at line 42 (on vulkan)`.
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::display::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShaderDiagnostic {
+    /// The shader's name, e.g. `Custom/Water`
+    pub shader: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+
+    /// The graphics API it was compiled for, e.g. `d3d11`
+    pub platform: Option<String>,
+
+    /// The pass it's in, if logged
+    pub pass: Option<String>,
+}
+impl Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location = match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!(" {}:{}", file, line),
+            (Some(file), None) => format!(" {}", file),
+            (None, Some(line)) => format!(" line {}", line),
+            (None, None) => String::new(),
+        };
+        let context = [
+            self.pass.as_ref().map(|pass| format!("pass {}", pass)),
+            self.platform.as_ref().map(|platform| format!("on {}", platform)),
+        ].into_iter().flatten().collect::<Vec<_>>();
+        write!(f, "{}{} {}", self.shader, muted(&location), self.message)?;
+        if !context.is_empty() {
+            write!(f, " {}", muted(&format!("({})", context.join(", "))))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ShaderDiagnostics {
+    pub errors: Vec<ShaderDiagnostic>,
+    pub warnings: Vec<ShaderDiagnostic>,
+}
+
+/// Whether the line starts a shader error or warning.
+pub(crate) fn severity(line: &str) -> Option<&'static str> {
+    let line = line.trim_start();
+    if line.starts_with("Shader error in '") {
+        Some("error")
+    } else if line.starts_with("Shader warning in '") {
+        Some("warning")
+    } else {
+        None
+    }
+}
+
+/// The shader errors and warnings in the log, each once (they're
+/// often logged for each variant), in the order they were logged.
+pub(crate) fn diagnostics(log: &str) -> ShaderDiagnostics {
+    let mut diagnostics = ShaderDiagnostics::default();
+    // The last one parsed, for the pass logged after it
+    let mut last: Option<(&'static str, ShaderDiagnostic)> = None;
+    for line in log.lines() {
+        let line = line.trim();
+        if let Some(severity) = severity(line) {
+            if let Some(diagnostic) = parse(line) {
+                if let Some((severity, prev)) = last.replace((severity, diagnostic)) {
+                    push(&mut diagnostics, severity, prev);
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("Compiling Subshader: ") {
+            if let Some((_, diagnostic)) = &mut last {
+                diagnostic.pass = rest.split(", ")
+                    .find_map(|part| part.strip_prefix("Pass: "))
+                    .map(|pass| pass.to_string());
+            }
+            if let Some((severity, diagnostic)) = last.take() {
+                push(&mut diagnostics, severity, diagnostic);
+            }
+        }
+    }
+    if let Some((severity, diagnostic)) = last {
+        push(&mut diagnostics, severity, diagnostic);
+    }
+    diagnostics
+}
+
+fn push(diagnostics: &mut ShaderDiagnostics, severity: &str, diagnostic: ShaderDiagnostic) {
+    let list = match severity {
+        "error" => &mut diagnostics.errors,
+        _ => &mut diagnostics.warnings,
+    };
+    if !list.contains(&diagnostic) {
+        list.push(diagnostic);
+    }
+}
+
+/// Parse e.g. `Shader error in 'Custom/Water': <message> at <file>(42) (on d3d11)`.
+fn parse(line: &str) -> Option<ShaderDiagnostic> {
+    let (_, rest) = line.split_once(" in '")?;
+    let (shader, rest) = rest.split_once("': ")?;
+
+    let (rest, platform) = match rest.trim_end().strip_suffix(')').and_then(|rest| rest.rsplit_once(" (on ")) {
+        Some((rest, platform)) => (rest, Some(platform.to_string())),
+        None => (rest.trim_end(), None),
+    };
+    let (message, file, line) = match rest.rsplit_once(" at ") {
+        Some((message, location)) => match location.strip_prefix("line ") {
+            Some(line) => (message, None, line.trim().parse().ok()),
+            None => match location.strip_suffix(')').and_then(|location| location.rsplit_once('(')) {
+                Some((file, line)) => (message, Some(file.to_string()), line.parse().ok()),
+                None => (rest, None, None),
+            },
+        },
+        None => (rest, None, None),
+    };
+    Some(ShaderDiagnostic {
+        shader: shader.to_string(),
+        message: message.trim().to_string(),
+        file,
+        line,
+        platform,
+        pass: None,
+    })
+}
@@ -0,0 +1,48 @@
+//! Splitting a suite across CI machines. Each test goes to a
+//! shard by a hash of its full name, so every machine agrees on
+//! the split without coordinating, and tests mostly stay put as
+//! others are added or removed.
+
+use anyhow::{Result, anyhow, bail};
+use std::{fmt::Display, str::FromStr};
+use crate::cache::Fnv;
+
+/// One of `count` shards, counting from 1, e.g. `2/5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+impl Shard {
+    /// Whether the test (by full name) is in this shard.
+    pub fn contains(&self, name: &str) -> bool {
+        let mut hash = Fnv::default();
+        hash.write(name.as_bytes());
+        (hash.0 % self.count as u64) as usize == self.index - 1
+    }
+
+    /// The tests in this shard, in their original order.
+    pub fn select<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+        names.into_iter().filter(|name| self.contains(name)).collect()
+    }
+}
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Shard> {
+        let Some((index, count)) = s.split_once('/') else {
+            bail!("Expected a shard like `2/5`");
+        };
+        let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| anyhow!("`{}` isn't a number", n));
+        let (index, count) = (parse(index)?, parse(count)?);
+        if count == 0 || index == 0 || index > count {
+            bail!("The shard should be from 1 to the number of shards, e.g. `1/{}`", count.max(1));
+        }
+        Ok(Shard { index, count })
+    }
+}
+impl Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
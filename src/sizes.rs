@@ -0,0 +1,154 @@
+//! The sizes in the "Build Report" section the editor logs after
+//! building a player: uncompressed sizes by category of asset, and
+//! the assets used (largest first), e.g.:
+//!
+//! ```text
+//! Build Report
+//! Uncompressed usage by category (Percentages based on user generated assets only):
+//! Textures               18.3 mb  62.5%
+//! Scripts                1.9 mb  6.4%
+//! Total User Assets      29.3 mb  100.0%
+//! Complete build size    154.8 mb
+//! Used Assets and files from the Resources folder, sorted by uncompressed size:
+//!  4.0 mb  13.7% Assets/Textures/Big.png
+//! -------------------------------------------------------------------------------
+//! ```
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::{build::format_size, display::*};
+
+/// The start of the size section.
+const HEADER: &str = "Uncompressed usage by category";
+
+/// The start of the list of assets, after the categories.
+const ASSETS_HEADER: &str = "Used Assets and files";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategorySize {
+    /// e.g. `Textures`
+    pub name: String,
+
+    /// In bytes
+    pub size: u64,
+
+    /// Of the user assets' total
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetSize {
+    /// e.g. `Assets/Textures/Big.png`
+    pub path: String,
+
+    /// In bytes
+    pub size: u64,
+
+    /// Of the user assets' total
+    pub percent: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SizeReport {
+    pub categories: Vec<CategorySize>,
+
+    /// The total of the categories, in bytes
+    pub user_assets: Option<u64>,
+
+    /// The whole build, including the engine, in bytes
+    pub complete: Option<u64>,
+
+    /// Largest first
+    pub assets: Vec<AssetSize>,
+}
+impl SizeReport {
+    /// Parse the last build report's sizes from the
+    /// editor log, if it logged one.
+    pub(crate) fn parse(log: &str) -> Option<SizeReport> {
+        let lines: Vec<&str> = log.lines().collect();
+        let start = lines.iter().rposition(|line| line.trim_start().starts_with(HEADER))?;
+        let mut report = SizeReport::default();
+        let mut in_assets = false;
+        for line in &lines[start + 1..] {
+            let line = line.trim();
+            if line.starts_with("---") || line.is_empty() { break }
+            if line.starts_with(ASSETS_HEADER) {
+                in_assets = true;
+            } else if in_assets {
+                let Some((size, rest)) = split_size(line) else { break };
+                let (percent, path) = rest.trim().split_once(' ').unwrap_or(("", rest));
+                report.assets.push(AssetSize {
+                    path: path.trim().to_string(),
+                    size,
+                    percent: parse_percent(percent),
+                });
+            } else if let Some(rest) = line.strip_prefix("Total User Assets") {
+                report.user_assets = split_size(rest.trim()).map(|(size, _)| size);
+            } else if let Some(rest) = line.strip_prefix("Complete build size") {
+                report.complete = split_size(rest.trim()).map(|(size, _)| size);
+            } else {
+                // The name, padded up to the size
+                let Some(idx) = line.find(|c: char| c.is_ascii_digit()) else { continue };
+                let Some((size, percent)) = split_size(&line[idx..]) else { continue };
+                report.categories.push(CategorySize {
+                    name: line[..idx].trim().to_string(),
+                    size,
+                    percent: parse_percent(percent.trim()),
+                });
+            }
+        }
+        report.assets.sort_by_key(|asset| std::cmp::Reverse(asset.size));
+        Some(report)
+    }
+
+    /// List the `n` largest assets, largest first.
+    pub fn largest(&self, n: usize) -> String {
+        let mut lines = vec![format!("Largest {} assets:", n.min(self.assets.len()))];
+        for asset in self.assets.iter().take(n) {
+            lines.push(format!("  {} {}",
+                muted(&format!("{:>9}", format_size(asset.size))),
+                asset.path));
+        }
+        lines.join("\n")
+    }
+}
+impl Display for SizeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const COMPLETE: &str = "Complete build";
+        let width = self.categories.iter().map(|category| category.name.len())
+            .chain(self.complete.map(|_| COMPLETE.len()))
+            .max().unwrap_or(0);
+        let mut categories: Vec<&CategorySize> = self.categories.iter()
+            .filter(|category| category.size > 0)
+            .collect();
+        categories.sort_by_key(|category| std::cmp::Reverse(category.size));
+        let mut lines = vec![];
+        for category in categories {
+            lines.push(format!("{:width$} {:>9} {}",
+                category.name, format_size(category.size),
+                muted(&format!("{:.1}%", category.percent)), width = width));
+        }
+        if let Some(complete) = self.complete {
+            lines.push(format!("{:width$} {:>9}", COMPLETE, format_size(complete), width = width));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Split e.g. `18.3 mb  62.5%` into the size (in bytes) and the rest.
+fn split_size(text: &str) -> Option<(u64, &str)> {
+    let mut parts = text.splitn(3, char::is_whitespace);
+    let number: f64 = parts.next()?.parse().ok()?;
+    let multiplier = match parts.next()?.to_lowercase().as_str() {
+        "b" | "bytes" => 1.,
+        "kb" => 1024.,
+        "mb" => 1024. * 1024.,
+        "gb" => 1024. * 1024. * 1024.,
+        _ => return None,
+    };
+    Some(((number * multiplier).round() as u64, parts.next().unwrap_or("")))
+}
+
+fn parse_percent(text: &str) -> f64 {
+    text.trim().trim_end_matches('%').parse().unwrap_or(0.)
+}
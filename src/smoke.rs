@@ -0,0 +1,113 @@
+//! Checking a project opens cleanly: beyond compiling, that opening
+//! it (and so importing its assets) doesn't throw, its packages
+//! resolve, and it doesn't need the API updater or serialized data
+//! upgraded, which an editor opened interactively would prompt for
+//! or quietly do, leaving changes to commit.
+
+use serde::Serialize;
+use std::fmt::Display;
+use crate::{log::LogException, display::*};
+
+/// The API updater's messages about the scripts it updated, or
+/// would have (with `-accept-apiupdate`).
+const API_UPDATER_MARKERS: &[&str] = &["API Updater", "APIUpdater"];
+
+#[derive(Debug, Default, Serialize)]
+pub struct SmokeReport {
+    /// Thrown while opening the project, e.g. by
+    /// importers or asset postprocessors
+    pub exceptions: Vec<LogException>,
+
+    /// Why the packages couldn't be resolved, if they couldn't
+    pub package_errors: Vec<String>,
+
+    /// Scripts using obsolete APIs the API updater would update
+    pub api_updates: Vec<String>,
+
+    /// Assets whose serialized data is from an older
+    /// version (or format), and would be upgraded
+    pub serialization_upgrades: Vec<String>,
+}
+impl SmokeReport {
+    pub(crate) fn parse(log: &str, exceptions: Vec<LogException>) -> SmokeReport {
+        let mut report = SmokeReport {
+            exceptions,
+            package_errors: crate::fatal::package_errors(log).unwrap_or_default(),
+            ..Default::default()
+        };
+        for line in log.lines() {
+            let line = line.trim();
+            let list = if is_api_update(line) {
+                &mut report.api_updates
+            } else if is_serialization_upgrade(line) {
+                &mut report.serialization_upgrades
+            } else {
+                continue;
+            };
+            if !list.iter().any(|seen| seen == line) {
+                list.push(line.to_string());
+            }
+        }
+        report
+    }
+
+    /// The categories, as (name, how many problems).
+    pub fn categories(&self) -> [(&'static str, usize); 4] {
+        [
+            ("exceptions during import", self.exceptions.len()),
+            ("package resolution errors", self.package_errors.len()),
+            ("obsolete API updates", self.api_updates.len()),
+            ("serialization upgrades", self.serialization_upgrades.len()),
+        ]
+    }
+
+    pub fn passed(&self) -> bool {
+        self.categories().iter().all(|(_, count)| *count == 0)
+    }
+}
+impl Display for SmokeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        if !self.exceptions.is_empty() {
+            lines.push(red(&format!("Exceptions during import ({})", self.exceptions.len())).to_string());
+            for exception in &self.exceptions {
+                lines.push(format!("  {}: {}", exception.kind, exception.message));
+                if let Some(frame) = exception.stack_trace.first() {
+                    lines.push(muted(&format!("    {}", frame)).to_string());
+                }
+            }
+        }
+        let sections = [
+            ("Package resolution failed", &self.package_errors,
+                "Check `Packages/manifest.json` (`unitool pkg list` shows what's there)"),
+            ("Scripts use obsolete APIs the API updater would update", &self.api_updates,
+                "Open the project in the editor and accept the update, or pass `--accept-api-update`, then commit the changes"),
+            ("Assets need their serialized data upgraded", &self.serialization_upgrades,
+                "Open the project and reimport them (or `AssetDatabase.ForceReserializeAssets`), then commit the changes"),
+        ];
+        for (title, messages, hint) in sections {
+            if messages.is_empty() { continue }
+            lines.push(red(&format!("{} ({})", title, messages.len())).to_string());
+            for message in messages {
+                lines.push(format!("  {}", message));
+            }
+            lines.push(muted(&format!("  {}", hint)).to_string());
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+fn is_api_update(line: &str) -> bool {
+    crate::fatal::needs_api_update(line)
+        || (API_UPDATER_MARKERS.iter().any(|marker| line.contains(marker))
+            && ["obsolete", "needs", "updated", "accept-apiupdate"].iter().any(|word| line.contains(word)))
+}
+
+/// e.g. `Upgrading serialized data of Assets/Old.prefab` or
+/// `Assets/Level.unity was serialized with an older version`.
+fn is_serialization_upgrade(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    if lower.contains("package") { return false }
+    (lower.contains("serializ") && ["upgrad", "older version", "old format", "version mismatch"].iter().any(|marker| lower.contains(marker)))
+        || (lower.contains("upgrad") && ["prefab", "scene", "material", "asset"].iter().any(|kind| lower.contains(kind)))
+}
@@ -0,0 +1,47 @@
+//! Showing the source around where a compile error or
+//! test failure happened, e.g.:
+//!
+//! ```text
+//!   40 │     public void Divides() {
+//!   41 │         var result = Divide(6, 2);
+//!   42 │         Assert.AreEqual(2, result);
+//!      │         ^
+//!   43 │     }
+//! ```
+
+use colored::Colorize;
+use std::{fs, path::Path};
+use crate::display::*;
+
+/// How many lines either side are shown by default.
+pub const DEFAULT_CONTEXT: usize = 2;
+
+/// The lines around `line` (1-based), with it highlighted and a caret
+/// under `column` if known. `None` if the file can't be read or
+/// doesn't have the line, e.g. it's changed since.
+pub fn snippet(path: &Path, line: usize, column: Option<usize>, context: usize) -> Option<String> {
+    let src = fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = src.lines().collect();
+    if line == 0 || line > lines.len() { return None }
+
+    let first = line.saturating_sub(context).max(1);
+    let last = (line + context).min(lines.len());
+    let width = last.to_string().len();
+    let mut out = vec![];
+    for n in first..=last {
+        let text = lines[n - 1].trim_end();
+        if n == line {
+            out.push(format!("{} {} {}", red(&format!("{:>width$}", n, width = width)), muted("│"), text.bold()));
+            if let Some(column) = column.filter(|&col| col > 0) {
+                // Keep tabs so the caret lines up however they're shown
+                let pad: String = text.chars().take(column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                out.push(format!("{:>width$} {} {}{}", "", muted("│"), pad, red("^"), width = width));
+            }
+        } else {
+            out.push(format!("{} {} {}", muted(&format!("{:>width$}", n, width = width)), muted("│"), text));
+        }
+    }
+    Some(out.join("\n"))
+}
@@ -0,0 +1,74 @@
+//! Rendering results as TAP 13 (the Test Anything Protocol),
+//! for `prove` and other TAP consumers. See:
+//! <https://testanything.org/tap-version-13-specification.html>
+
+use crate::{CompileErrors, testing::{TestSummary, TestResult}};
+
+/// One `ok`/`not ok` line per test case, with a YAML
+/// diagnostics block for each failure.
+pub fn to_tap(summary: &TestSummary) -> String {
+    let cases = summary.cases();
+    let mut lines = vec![
+        "TAP version 13".to_string(),
+        format!("1..{}", cases.len()),
+    ];
+    if summary.truncated() {
+        lines.push("# The results were truncated, some tests may be missing".to_string());
+    }
+    for (i, case) in cases.iter().enumerate() {
+        let n = i + 1;
+        match case.result {
+            TestResult::Passed => lines.push(format!("ok {} - {}", n, description(case.key()))),
+            TestResult::Skipped => {
+                let reason = case.message().unwrap_or_default();
+                let reason = reason.lines().next().unwrap_or("").trim();
+                lines.push(format!("ok {} - {} # SKIP {}", n, description(case.key()), reason).trim_end().to_string());
+            },
+            TestResult::Failed => {
+                lines.push(format!("not ok {} - {}", n, description(case.key())));
+                lines.push("  ---".to_string());
+                if let Some(message) = case.message() {
+                    lines.push("  message: |".to_string());
+                    lines.extend(block(&message));
+                }
+                if let Some(trace) = case.stack_trace() {
+                    lines.push("  stack: |".to_string());
+                    lines.extend(block(&trace));
+                }
+                lines.push(format!("  duration_ms: {}", (case.duration * 1000.).round()));
+                lines.push("  ...".to_string());
+            },
+        }
+    }
+    lines.join("\n")
+}
+
+/// Stop consumers with the compile errors, as no tests ran.
+pub fn bail_out(errors: &CompileErrors) -> String {
+    let mut errors: Vec<_> = errors.iter().collect();
+    errors.sort();
+    let mut lines = vec!["TAP version 13".to_string()];
+    lines.extend(errors.iter().map(|err| format!("# {}", err.raw)));
+    lines.push(format!("Bail out! Compilation failed with {} errors", errors.len()));
+    lines.join("\n")
+}
+
+/// `#` starts a directive, so must be escaped in descriptions.
+fn description(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('#', "\\#")
+}
+
+/// Indent text as a YAML block scalar. Its own common
+/// indentation is removed first, as YAML takes the block's
+/// indentation from its first line.
+fn block(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let common = lines.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines.iter()
+        .map(|line| format!("    {}", line.get(common..).unwrap_or("").trim_end()))
+        .collect()
+}
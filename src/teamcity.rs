@@ -0,0 +1,98 @@
+//! TeamCity service messages, which TeamCity picks out of the
+//! build log to fill in the tests tab and the build's problems. See:
+//! <https://www.jetbrains.com/help/teamcity/service-messages.html>
+
+use crate::{CompileErrors, events::RunEvent, testing::{TestSummary, TestResult}};
+
+/// TeamCity truncates problem descriptions longer than this.
+const MAX_DESCRIPTION: usize = 4000;
+
+/// A service message, e.g. `##teamcity[testStarted name='Foo']`.
+fn message(name: &str, attrs: &[(&str, &str)]) -> String {
+    let attrs: String = attrs.iter()
+        .map(|(key, value)| format!(" {}='{}'", key, escape(value)))
+        .collect();
+    format!("##teamcity[{}{}]", name, attrs)
+}
+
+/// Escape a value with TeamCity's `|` escapes.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            '\u{0085}' => escaped.push_str("|x"),
+            '\u{2028}' => escaped.push_str("|l"),
+            '\u{2029}' => escaped.push_str("|p"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fail the build with a problem, e.g. an error that stopped the run.
+pub fn build_problem(description: &str) -> String {
+    let description: String = description.chars().take(MAX_DESCRIPTION).collect();
+    message("buildProblem", &[("description", &description)])
+}
+
+/// A build problem for each compile error.
+pub fn compile_errors(errors: &CompileErrors) -> Vec<String> {
+    let mut errors: Vec<_> = errors.iter().collect();
+    errors.sort();
+    errors.iter().map(|err| build_problem(&err.raw)).collect()
+}
+
+/// Report a compiler warning, as a warning in the build log.
+pub fn compile_warning(warning: &str) -> String {
+    message("message", &[("text", warning.trim()), ("status", "WARNING")])
+}
+
+/// The tests, a suite per fixture, as if they'd been run just now.
+pub fn test_results(summary: &TestSummary) -> Vec<String> {
+    let mut lines = vec![];
+    for fixture in summary.fixtures() {
+        lines.push(message("testSuiteStarted", &[("name", &fixture.name)]));
+        for case in fixture.cases() {
+            let name = case.key();
+            lines.push(message("testStarted", &[("name", name)]));
+            match case.result {
+                TestResult::Passed => {},
+                TestResult::Skipped => lines.push(message("testIgnored",
+                    &[("name", name), ("message", &case.message().unwrap_or_default())])),
+                TestResult::Failed => lines.push(message("testFailed", &[
+                    ("name", name),
+                    ("message", &case.message().unwrap_or_default()),
+                    ("details", &case.stack_trace().unwrap_or_default()),
+                ])),
+            }
+            if let Some(output) = case.output() {
+                lines.push(message("testStdOut", &[("name", name), ("out", &output)]));
+            }
+            let duration = (case.duration * 1000.).round().to_string();
+            lines.push(message("testFinished", &[("name", name), ("duration", &duration)]));
+        }
+        lines.push(message("testSuiteFinished", &[("name", &fixture.name)]));
+    }
+    lines
+}
+
+/// A progress message for the run's phases, shown as the build's
+/// status while it's running. These are all from the log, so don't
+/// need per-test events; the tests are reported after, from the
+/// results (see [`test_results`]).
+pub fn progress(event: &RunEvent) -> Option<String> {
+    let text = match event {
+        RunEvent::ResolvingPackages => "Resolving packages".to_string(),
+        RunEvent::CompilationStarted => "Compiling scripts".to_string(),
+        RunEvent::CompilingAssembly { name, .. } => format!("Compiling {}", name),
+        RunEvent::ReloadingDomain => "Reloading scripts".to_string(),
+        _ => return None,
+    };
+    Some(format!("##teamcity[progressMessage '{}']", escape(&text)))
+}
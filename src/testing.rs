@@ -1,25 +1,260 @@
-/// Parsing and printing test results
-/// from Unity. Unity outputs an XML file
-/// that summarizes test results.
-///
-/// The basic structure is `<test-run>` as the
-/// root element, then composed of `<test-suite>`
-/// elements, which may be composed of more
-/// `<test-suite>` elements, and finally
-/// there are <test-case>` elements which give the results
-/// of a single test case.
+//! Parsing and printing test results
+//! from Unity. Unity outputs an XML file
+//! that summarizes test results.
+//!
+//! The basic structure is `<test-run>` as the
+//! root element, then composed of `<test-suite>`
+//! elements, which may be composed of more
+//! `<test-suite>` elements, and finally
+//! there are <test-case>` elements which give the results
+//! of a single test case.
 
-use quick_xml::de;
-use serde::Deserialize;
-use colored::Colorize;
-use std::{fs::File, io::BufReader, fmt::Display, path::PathBuf};
-use crate::display::*;
+use quick_xml::{de, events::{Event, BytesEnd}, Reader, Writer};
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+use colored::{ColoredString, Colorize};
+use std::{fs, fmt::Display, path::{Path, PathBuf}};
+use crate::{SortKey, display::*, trace};
 
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct TestSummary {
-    #[serde(rename="$value")]
+    #[serde(rename="$value", default)]
     test_suites: Vec<TestSuite>,
+
+    /// Whether these results were recovered
+    /// from a partially-written file
+    #[serde(skip)]
+    truncated: bool,
+
+    // The run's own stats, from the `<test-run>` root.
+    // The counts are dropped when merging or recovering
+    // truncated results, as they'd no longer match the cases.
+    #[serde(rename="@total", default)]
+    run_total: Option<usize>,
+
+    #[serde(rename="@passed", default)]
+    run_passed: Option<usize>,
+
+    #[serde(rename="@failed", default)]
+    run_failed: Option<usize>,
+
+    #[serde(rename="@skipped", default)]
+    run_skipped: Option<usize>,
+
+    #[serde(rename="@duration", default)]
+    run_duration: Option<f64>,
+
+    #[serde(rename="@start-time", default)]
+    start_time: Option<String>,
+
+    #[serde(rename="@end-time", default)]
+    end_time: Option<String>,
+}
+impl Serialize for TestSummary {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut summary = serializer.serialize_struct("TestSummary", 8)?;
+        summary.serialize_field("total", &self.total())?;
+        summary.serialize_field("passed", &self.passed())?;
+        summary.serialize_field("failed", &self.failed())?;
+        summary.serialize_field("skipped", &self.skipped())?;
+        summary.serialize_field("duration", &self.duration())?;
+        summary.serialize_field("start_time", &self.start_time)?;
+        summary.serialize_field("end_time", &self.end_time)?;
+        summary.serialize_field("suites", &self.test_suites)?;
+        summary.end()
+    }
+}
+impl TestSummary {
+    /// Merge several results into one. Suites with the same type
+    /// and name are combined, and test cases appearing more than
+    /// once (by full name) keep their latest result.
+    pub fn merge(summaries: Vec<TestSummary>) -> TestSummary {
+        let mut merged = TestSummary::default();
+        for summary in summaries {
+            merged.truncated |= summary.truncated;
+            // The timestamps are ISO 8601, so sort as strings
+            merged.start_time = match (merged.start_time, summary.start_time) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            merged.end_time = merged.end_time.max(summary.end_time);
+            for suite in summary.test_suites {
+                match merged.test_suites.iter_mut().find(|s| s.same_as(&suite)) {
+                    Some(existing) => existing.absorb(suite),
+                    None => merged.test_suites.push(suite),
+                }
+            }
+        }
+        merged
+    }
+
+    /// Whether these results were recovered from a truncated
+    /// file, in which case some tests are likely missing.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Whether any test failed.
+    pub fn has_failures(&self) -> bool {
+        self.iter_cases().any(|case| case.failed())
+    }
+
+    /// The number of test cases run.
+    pub fn total(&self) -> usize {
+        self.run_total.unwrap_or_else(|| self.iter_cases().count())
+    }
+
+    pub fn passed(&self) -> usize {
+        self.run_passed.unwrap_or_else(|| self.count(TestResult::Passed))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.run_failed.unwrap_or_else(|| self.count(TestResult::Failed))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.run_skipped.unwrap_or_else(|| self.count(TestResult::Skipped))
+    }
+
+    /// How long the run took, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.run_duration.unwrap_or_else(|| self.test_suites.iter().map(|suite| suite.duration).sum())
+    }
+
+    /// When the run started, e.g. `2023-06-01 10:00:00Z`.
+    pub fn start_time(&self) -> Option<&str> {
+        self.start_time.as_deref()
+    }
+
+    pub fn end_time(&self) -> Option<&str> {
+        self.end_time.as_deref()
+    }
+
+    /// A one-line grand total,
+    /// e.g. `42 passed, 3 failed, 1 skipped in 2m13s`.
+    pub fn totals(&self) -> String {
+        format!("{}, {}, {} in {}",
+            green(&format!("{} passed", self.passed())),
+            red(&format!("{} failed", self.failed())),
+            muted(&format!("{} skipped", self.skipped())),
+            human_duration(self.duration()))
+    }
+
+    fn count(&self, result: TestResult) -> usize {
+        self.iter_cases().filter(|case| case.result == result).count()
+    }
+
+    /// Iterate over all test cases, across every suite.
+    pub fn iter_cases(&self) -> impl Iterator<Item = &TestCase> {
+        self.cases().into_iter()
+    }
+
+    /// The failed test cases.
+    pub fn failures(&self) -> Vec<&TestCase> {
+        self.iter_cases().filter(|case| case.failed()).collect()
+    }
+
+    /// Order the suites at every level of the tree.
+    pub fn sort(&mut self, key: SortKey) {
+        sort_suites(&mut self.test_suites, &key);
+        for suite in &mut self.test_suites {
+            suite.sort(&key);
+        }
+    }
+
+    /// All test cases, across every suite.
+    pub(crate) fn cases(&self) -> Vec<&TestCase> {
+        let mut cases = vec![];
+        for suite in &self.test_suites {
+            suite.collect_cases(&mut cases);
+        }
+        cases
+    }
+
+    /// All test cases, across every suite, to change.
+    pub(crate) fn cases_mut(&mut self) -> Vec<&mut TestCase> {
+        let mut cases = vec![];
+        for suite in &mut self.test_suites {
+            suite.collect_cases_mut(&mut cases);
+        }
+        cases
+    }
+
+    /// The top-level suites.
+    pub(crate) fn suites(&self) -> &[TestSuite] {
+        &self.test_suites
+    }
+
+    /// All fixtures (i.e. test classes), across every suite.
+    pub(crate) fn fixtures(&self) -> Vec<&TestSuite> {
+        let mut fixtures = vec![];
+        for suite in &self.test_suites {
+            suite.collect_fixtures(&mut fixtures);
+        }
+        fixtures
+    }
+
+    /// Render a horizontal bar chart of fixture durations,
+    /// scaled relative to the slowest fixture, or `None` if
+    /// none were timed (e.g. no tests ran).
+    pub fn timing_bars(&self) -> Option<String> {
+        let fixtures = self.fixtures();
+
+        let longest = fixtures.iter().map(|s| s.duration).fold(0., f64::max);
+        if longest <= 0. {
+            return None;
+        }
+        let name_width = fixtures.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+        let bars = fixtures.iter()
+            .map(|suite| {
+                let frac = suite.duration / longest;
+                format!("{:width$} {} {}",
+                    suite.name,
+                    align_left(&bar(frac, BAR_WIDTH), BAR_WIDTH, green),
+                    muted(&format!("{:.2}s", suite.duration)),
+                    width = name_width)
+            })
+            .collect::<Vec<String>>().join("\n");
+        Some(bars)
+    }
+}
+impl TestSummary {
+    /// List the `n` slowest test cases, slowest first.
+    pub fn slowest(&self, n: usize) -> String {
+        let mut cases = self.cases();
+        cases.sort_by(|a, b| b.duration.total_cmp(&a.duration));
+        let mut lines = vec![format!("Slowest {} tests:", n.min(cases.len()))];
+        for case in cases.into_iter().take(n) {
+            lines.push(format!("  {} {}",
+                muted(&format!("{:>8.2}s", case.duration)),
+                case.key()));
+        }
+        lines.join("\n")
+    }
+}
+impl TestSummary {
+    /// List the skipped test cases, with why they were skipped.
+    pub fn skipped_tests(&self) -> String {
+        let skipped: Vec<&TestCase> = self.iter_cases()
+            .filter(|case| case.result == TestResult::Skipped)
+            .collect();
+        let mut lines = vec![format!("{} skipped tests:", skipped.len())];
+        for case in skipped {
+            let reason = case.skip_reason().unwrap_or("no reason given".to_string());
+            lines.push(format!("  {} {} {}", TestResult::Skipped, case.key(), muted(&reason)));
+        }
+        lines.join("\n")
+    }
+}
+impl TestSummary {
+    /// Like the full summary, but with only the failing cases,
+    /// under the suites containing them.
+    pub fn failures_only(&self) -> String {
+        self.test_suites.iter()
+            .filter(|suite| suite.failed > 0)
+            .map(|suite| suite.failures_only())
+            .collect::<Vec<String>>().join("\n")
+    }
 }
 impl Display for TestSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -39,6 +274,7 @@ enum TestDetail {
     Output(String), // Console output
     Properties, // Not much useful info here
     Reason(FailureInfo),
+    Attachments(Attachments),
 }
 impl Display for TestDetail {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -61,15 +297,18 @@ impl Display for TestDetail {
             },
             TestDetail::Reason(reason) => {
                 reason.to_string()
-            }
+            },
+            TestDetail::Attachments(attachments) => {
+                attachments.to_string()
+            },
         };
         write!(f, "{}", msg)
     }
 }
 
 
-#[derive(Debug, Deserialize, PartialEq)]
-enum TestResult {
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum TestResult {
     Failed,
     Passed,
     Skipped,
@@ -100,6 +339,42 @@ impl Display for FailureInfo {
     }
 }
 
+#[derive(Debug, Deserialize, PartialEq)]
+struct Attachments {
+    #[serde(rename="attachment", default)]
+    attachments: Vec<Attachment>,
+}
+impl Display for Attachments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.attachments.is_empty() { return Ok(()) }
+        let mut lines = vec![muted("Attachments:").to_string()];
+        lines.extend(self.attachments.iter().map(|attachment| format!("  {}", attachment)));
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A file a test attached to its result, e.g. the
+/// screenshot a graphics test took when it failed.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Attachment {
+    /// As written by the test, or where it was
+    /// copied to (see [`crate::artifacts::collect`])
+    #[serde(rename(deserialize="filePath"))]
+    pub file_path: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+}
+impl Display for Attachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.description {
+            Some(description) if !description.trim().is_empty() =>
+                write!(f, "{} {}", self.file_path, muted(&format!("({})", description.trim()))),
+            _ => write!(f, "{}", self.file_path),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(rename_all="kebab-case")]
 enum FailureDetail {
@@ -108,25 +383,234 @@ enum FailureDetail {
 }
 impl Display for FailureDetail {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = match self {
-            FailureDetail::Message(msg) => red(msg),
-            FailureDetail::StackTrace(msg) => msg.truecolor(157, 174, 179),
-        };
-        write!(f, "{}", msg)
+        match self {
+            FailureDetail::Message(msg) => match Assertion::parse(msg) {
+                Some(assertion) => write!(f, "{}", assertion),
+                None => write!(f, "{}", red(msg)),
+            },
+            FailureDetail::StackTrace(msg) => write!(f, "{}", trace::render(msg).truecolor(157, 174, 179)),
+        }
     }
 }
 
+/// Values at least this long have where they
+/// differ highlighted, as it's hard to spot.
+const DIFF_MIN_LEN: usize = 10;
+
+/// An NUnit assertion failure, e.g.:
+///
+/// ```text
+///   Expected string length 5 but was 6. Strings differ at index 5.
+///   Expected: "hello"
+///   But was:  "hello!"
+///   ----------------^
+/// ```
+#[derive(Debug, PartialEq)]
+struct Assertion {
+    /// Lines before the values, e.g. the assertion's own message
+    before: Vec<String>,
+    expected: String,
+    actual: String,
+
+    /// Lines after the values, less NUnit's `---^` marker
+    after: Vec<String>,
+}
+impl Assertion {
+    fn parse(msg: &str) -> Option<Assertion> {
+        let lines: Vec<&str> = msg.lines().collect();
+        let i = lines.iter().position(|line| line.trim_start().starts_with("Expected: "))?;
+        let expected = lines[i].trim_start().strip_prefix("Expected: ")?;
+        let actual = lines.get(i + 1)?.trim_start().strip_prefix("But was:")?;
+        let after = lines[i + 2..].iter()
+            .filter(|line| !is_marker(line))
+            .map(|line| line.trim().to_string())
+            .collect();
+        Some(Assertion {
+            before: lines[..i].iter().map(|line| line.trim().to_string()).collect(),
+            expected: expected.trim().to_string(),
+            actual: actual.trim().to_string(),
+            after,
+        })
+    }
+}
+impl Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (expected, actual) = highlight_diff(&self.expected, &self.actual);
+        let mut lines: Vec<String> = self.before.iter().map(|line| red(line).to_string()).collect();
+        lines.push(format!("{} {}", muted("Expected:"), expected));
+        lines.push(format!("{} {}", muted("But was: "), actual));
+        lines.extend(self.after.iter().map(|line| red(line).to_string()));
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// NUnit marks where strings differ with e.g. `-----^`.
+fn is_marker(line: &str) -> bool {
+    let line = line.trim();
+    line.ends_with('^') && line.len() > 1 && line[..line.len() - 1].chars().all(|c| c == '-')
+}
+
+/// Color the expected value green and the actual red, highlighting
+/// the part between their common prefix and suffix if they're long.
+fn highlight_diff(expected: &str, actual: &str) -> (String, String) {
+    let (exp, act): (Vec<char>, Vec<char>) = (expected.chars().collect(), actual.chars().collect());
+    if exp.len().max(act.len()) < DIFF_MIN_LEN {
+        return (green(expected).to_string(), red(actual).to_string());
+    }
+    let prefix = exp.iter().zip(&act).take_while(|(a, b)| a == b).count();
+    let max_suffix = exp.len().min(act.len()) - prefix;
+    let suffix = exp.iter().rev().zip(act.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let split = |chars: &[char], on: fn(&str) -> ColoredString, color: fn(&str) -> ColoredString| {
+        let part = |chars: &[char]| chars.iter().collect::<String>();
+        let middle = &chars[prefix..chars.len() - suffix];
+        format!("{}{}{}", color(&part(&chars[..prefix])), on(&part(middle)), color(&part(&chars[chars.len() - suffix..])))
+    };
+    (split(&exp, on_green, green), split(&act, on_red, red))
+}
+
+/// The result of a single test.
 #[derive(Debug, Deserialize, PartialEq)]
-struct TestCase {
+pub struct TestCase {
     #[serde(rename="@name")]
-    name: String,
+    pub name: String,
+
+    #[serde(rename="@fullname", default)]
+    pub full_name: String,
+
+    #[serde(rename="@classname", default)]
+    pub class_name: String,
 
     #[serde(rename="@result")]
-    result: TestResult,
+    pub result: TestResult,
 
-    #[serde(rename="$value")]
+    /// In seconds
+    #[serde(rename="@duration", default)]
+    pub duration: f64,
+
+    /// When it started, e.g. `2024-05-01 12:00:00Z`. The cases are
+    /// listed by fixture, so this is the only record of the order
+    /// they ran in (with `-randomOrderSeed`)
+    #[serde(rename="@start-time", default)]
+    pub start_time: Option<String>,
+
+    #[serde(rename="$value", default)]
     details: Vec<TestDetail>
 }
+impl Serialize for TestCase {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut case = serializer.serialize_struct("TestCase", 10)?;
+        case.serialize_field("name", &self.name)?;
+        case.serialize_field("full_name", &self.full_name)?;
+        case.serialize_field("class_name", &self.class_name)?;
+        case.serialize_field("result", &self.result)?;
+        case.serialize_field("duration", &self.duration)?;
+        case.serialize_field("start_time", &self.start_time)?;
+        case.serialize_field("message", &self.message())?;
+        case.serialize_field("stack_trace", &self.stack_trace())?;
+        case.serialize_field("output", &self.output())?;
+        case.serialize_field("attachments", &self.attachments())?;
+        case.end()
+    }
+}
+impl TestCase {
+    /// The failure message, or the reason the test was skipped.
+    pub fn message(&self) -> Option<String> {
+        self.failure_details(|detail| match detail {
+            FailureDetail::Message(msg) => Some(msg),
+            _ => None,
+        })
+    }
+
+    /// Why the test was skipped (e.g. the text of its `[Ignore]`),
+    /// on one line, if it was.
+    pub fn skip_reason(&self) -> Option<String> {
+        if self.result != TestResult::Skipped { return None }
+        let reason = self.message()?;
+        Some(reason.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" "))
+    }
+
+    pub fn stack_trace(&self) -> Option<String> {
+        self.failure_details(|detail| match detail {
+            FailureDetail::StackTrace(trace) => Some(trace),
+            _ => None,
+        })
+    }
+
+    /// Console output from the test.
+    pub fn output(&self) -> Option<String> {
+        let output = self.details.iter()
+            .filter_map(|detail| match detail {
+                TestDetail::Output(output) => Some(output.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<&str>>();
+        if output.is_empty() { None } else { Some(output.join("\n")) }
+    }
+
+    /// Files the test attached to its result.
+    pub fn attachments(&self) -> Vec<&Attachment> {
+        self.details.iter()
+            .flat_map(|detail| match detail {
+                TestDetail::Attachments(attachments) => attachments.attachments.iter().collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    pub(crate) fn attachments_mut(&mut self) -> impl Iterator<Item = &mut Attachment> {
+        self.details.iter_mut()
+            .flat_map(|detail| match detail {
+                TestDetail::Attachments(attachments) => attachments.attachments.iter_mut().collect(),
+                _ => vec![],
+            })
+    }
+
+    /// Join the selected parts of the failure/reason info.
+    fn failure_details(&self, select: impl Fn(&FailureDetail) -> Option<&String>) -> Option<String> {
+        let parts = self.details.iter()
+            .filter_map(|detail| match detail {
+                TestDetail::Failure(info) | TestDetail::Reason(info) => Some(info),
+                _ => None,
+            })
+            .flat_map(|info| info.details.iter().filter_map(&select))
+            .map(|part| part.trim_end())
+            .collect::<Vec<&str>>();
+        if parts.is_empty() { None } else { Some(parts.join("\n")) }
+    }
+
+    /// What identifies this test across runs.
+    pub fn key(&self) -> &str {
+        if self.full_name.is_empty() { &self.name } else { &self.full_name }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.result == TestResult::Failed
+    }
+
+    /// If the test failed by exceeding its timeout,
+    /// the timeout in milliseconds.
+    pub fn timeout(&self) -> Option<u64> {
+        if !self.failed() { return None }
+        self.details.iter().find_map(|detail| match detail {
+            TestDetail::Failure(failure) => failure.details.iter()
+                .find_map(|d| match d {
+                    FailureDetail::Message(msg) => timeout_ms(msg),
+                    _ => None,
+                }),
+            _ => None,
+        })
+    }
+}
+
+/// Parse the timeout from messages like `Test exceeded Timeout value of 30000ms`,
+/// which is what both NUnit and UTF's `[UnityTest]`s fail with.
+fn timeout_ms(msg: &str) -> Option<u64> {
+    let (_, rest) = msg.split_once("exceeded Timeout value of ")?;
+    rest.split("ms").next()?.trim().parse().ok()
+}
 impl Display for TestCase {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut lines = vec![];
@@ -135,25 +619,28 @@ impl Display for TestCase {
             lines.push("".to_string()); // Empty line
         }
 
-        lines.push(format!("{} {}", self.result, if failed {
+        let mut line = format!("{} {}", self.result, if failed {
             self.name.bold()
         } else {
             self.name.normal()
-        }));
+        });
+        if let Some(ms) = self.timeout() {
+            line.push_str(&format!(" {}", red(&format!("timed out (>{}s)", ms as f64 / 1000.))));
+        } else if self.duration > 0. {
+            line.push_str(&format!(" {}", muted(&format!("{:.2}s", self.duration))));
+        }
+        if let Some(reason) = self.skip_reason() {
+            line.push_str(&format!(" {}", yellow(&format!("skipped: {}", reason))));
+        }
+        lines.push(line);
 
         for detail in &self.details {
             match detail {
                 TestDetail::Properties => continue,
-                TestDetail::Output(_) => {
-                    // Don't print output if the test passed
-                    if self.result == TestResult::Passed {
-                        continue
-                    } else {
-                        let repr = detail.to_string();
-                        if repr.is_empty() { continue }
-                        lines.push(indent(&repr));
-                    }
-                },
+                // Shown inline
+                TestDetail::Reason(_) if self.result == TestResult::Skipped => continue,
+                // Don't print output or attachments if the test passed
+                TestDetail::Output(_) | TestDetail::Attachments(_) if self.result == TestResult::Passed => continue,
                 _ => {
                     let repr = detail.to_string();
                     if repr.is_empty() { continue }
@@ -166,31 +653,200 @@ impl Display for TestCase {
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
-struct TestSuite {
+pub(crate) struct TestSuite {
     #[serde(rename="@type")]
-    kind: String,
+    pub(crate) kind: String,
 
     #[serde(rename="@name")]
-    name: String,
+    pub(crate) name: String,
 
     #[serde(rename="@failed")]
-    failed: usize,
+    pub(crate) failed: usize,
 
     #[serde(rename="@passed")]
-    passed: usize,
+    pub(crate) passed: usize,
 
     #[serde(rename="@skipped")]
-    skipped: usize,
+    pub(crate) skipped: usize,
 
     #[serde(rename="@total")]
-    total: usize,
+    pub(crate) total: usize,
 
-    #[serde(rename="$value")]
+    #[serde(rename="@duration", default)]
+    pub(crate) duration: f64,
+
+    #[serde(rename="$value", default)]
     details: Vec<TestDetail>
 }
-impl Display for TestSuite {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut lines: Vec<String> = vec![];
+impl Serialize for TestSuite {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let suites = self.child_suites();
+        let cases = self.child_cases();
+
+        let mut suite = serializer.serialize_struct("TestSuite", 9)?;
+        suite.serialize_field("kind", &self.kind)?;
+        suite.serialize_field("name", &self.name)?;
+        suite.serialize_field("passed", &self.passed)?;
+        suite.serialize_field("failed", &self.failed)?;
+        suite.serialize_field("skipped", &self.skipped)?;
+        suite.serialize_field("total", &self.total)?;
+        suite.serialize_field("duration", &self.duration)?;
+        suite.serialize_field("suites", &suites)?;
+        suite.serialize_field("cases", &cases)?;
+        suite.end()
+    }
+}
+impl TestSuite {
+    fn same_as(&self, other: &TestSuite) -> bool {
+        self.kind == other.kind && self.name == other.name
+    }
+
+    /// Merge another run of this suite into this one.
+    fn absorb(&mut self, other: TestSuite) {
+        self.duration += other.duration;
+        for detail in other.details {
+            match detail {
+                TestDetail::TestSuite(suite) => {
+                    let existing = self.details.iter_mut().find_map(|d| match d {
+                        TestDetail::TestSuite(s) if s.same_as(&suite) => Some(s),
+                        _ => None,
+                    });
+                    match existing {
+                        Some(existing) => existing.absorb(suite),
+                        None => self.details.push(TestDetail::TestSuite(suite)),
+                    }
+                },
+                TestDetail::TestCase(case) => {
+                    let existing = self.details.iter_mut().find(|d| matches!(d,
+                        TestDetail::TestCase(c) if c.key() == case.key()));
+                    match existing {
+                        Some(existing) => *existing = TestDetail::TestCase(case),
+                        None => self.details.push(TestDetail::TestCase(case)),
+                    }
+                },
+                other => {
+                    // Keep the suite's own info ahead of its children
+                    if !self.details.contains(&other) {
+                        let idx = self.details.iter()
+                            .position(|d| matches!(d, TestDetail::TestSuite(_) | TestDetail::TestCase(_)))
+                            .unwrap_or(self.details.len());
+                        self.details.insert(idx, other);
+                    }
+                },
+            }
+        }
+        self.recount();
+    }
+
+    /// Recompute the counts from the suite's children.
+    fn recount(&mut self) {
+        let (mut passed, mut failed, mut skipped, mut total) = (0, 0, 0, 0);
+        for detail in &self.details {
+            match detail {
+                TestDetail::TestSuite(suite) => {
+                    passed += suite.passed;
+                    failed += suite.failed;
+                    skipped += suite.skipped;
+                    total += suite.total;
+                },
+                TestDetail::TestCase(case) => {
+                    total += 1;
+                    match case.result {
+                        TestResult::Passed => passed += 1,
+                        TestResult::Failed => failed += 1,
+                        TestResult::Skipped => skipped += 1,
+                    }
+                },
+                _ => continue,
+            }
+        }
+        self.passed = passed;
+        self.failed = failed;
+        self.skipped = skipped;
+        self.total = total;
+    }
+
+    fn sort(&mut self, key: &SortKey) {
+        // Keep the suite's own info (properties, failure messages)
+        // ahead of its children, and only reorder the child suites.
+        let (mut suites, mut rest): (Vec<TestSuite>, Vec<TestDetail>) = (vec![], vec![]);
+        for detail in self.details.drain(..) {
+            match detail {
+                TestDetail::TestSuite(suite) => suites.push(suite),
+                other => rest.push(other),
+            }
+        }
+        sort_suites(&mut suites, key);
+        for mut suite in suites {
+            suite.sort(key);
+            rest.push(TestDetail::TestSuite(suite));
+        }
+        self.details = rest;
+    }
+
+    /// The suites directly under this suite.
+    pub(crate) fn child_suites(&self) -> Vec<&TestSuite> {
+        self.details.iter()
+            .filter_map(|detail| match detail {
+                TestDetail::TestSuite(suite) => Some(suite),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The test cases directly under this suite.
+    pub(crate) fn child_cases(&self) -> Vec<&TestCase> {
+        self.details.iter()
+            .filter_map(|detail| match detail {
+                TestDetail::TestCase(case) => Some(case),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All test cases under this suite.
+    pub(crate) fn cases(&self) -> Vec<&TestCase> {
+        let mut cases = vec![];
+        self.collect_cases(&mut cases);
+        cases
+    }
+
+    fn collect_cases<'a>(&'a self, cases: &mut Vec<&'a TestCase>) {
+        for detail in &self.details {
+            match detail {
+                TestDetail::TestCase(case) => cases.push(case),
+                TestDetail::TestSuite(suite) => suite.collect_cases(cases),
+                _ => continue,
+            }
+        }
+    }
+
+    fn collect_cases_mut<'a>(&'a mut self, cases: &mut Vec<&'a mut TestCase>) {
+        for detail in &mut self.details {
+            match detail {
+                TestDetail::TestCase(case) => cases.push(case),
+                TestDetail::TestSuite(suite) => suite.collect_cases_mut(cases),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Collect the fixtures (i.e. test classes) under this suite.
+    fn collect_fixtures<'a>(&'a self, fixtures: &mut Vec<&'a TestSuite>) {
+        if self.kind == "TestFixture" {
+            fixtures.push(self);
+            return;
+        }
+        for detail in &self.details {
+            if let TestDetail::TestSuite(suite) = detail {
+                suite.collect_fixtures(fixtures);
+            }
+        }
+    }
+}
+impl TestSuite {
+    /// The suite's name and counts.
+    fn header(&self) -> String {
         let name = if self.failed > 0 {
             on_red(&format!(" {} ", self.name))
         } else if self.passed == self.total {
@@ -198,11 +854,33 @@ impl Display for TestSuite {
         } else {
             muted(&self.name).bold()
         };
-        lines.push(format!("{} {} {} {}",
+        format!("{} {} {} {}",
             name,
             green(&self.passed.to_string()),
             red(&self.failed.to_string()),
-            muted(&self.skipped.to_string())));
+            muted(&self.skipped.to_string()))
+    }
+
+    fn failures_only(&self) -> String {
+        let mut lines = vec![self.header()];
+        for detail in &self.details {
+            let repr = match detail {
+                TestDetail::TestSuite(suite) if suite.failed > 0 => suite.failures_only(),
+                TestDetail::TestCase(case) if case.failed() => case.to_string(),
+
+                // e.g. a failing `[OneTimeSetUp]`
+                TestDetail::Failure(_) => detail.to_string(),
+                _ => continue,
+            };
+            if repr.is_empty() { continue }
+            lines.push(indent(&repr));
+        }
+        lines.join("\n")
+    }
+}
+impl Display for TestSuite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines: Vec<String> = vec![self.header()];
         for detail in &self.details {
             if *detail == TestDetail::Properties { continue }
             let repr = detail.to_string();
@@ -213,9 +891,132 @@ impl Display for TestSuite {
     }
 }
 
-pub fn load_test_results(results_path: &PathBuf) -> TestSummary {
-    let file = File::open(results_path).unwrap();
-    let buf_reader = BufReader::new(file);
-    let results: TestSummary = de::from_reader(buf_reader).unwrap();
-    results
+fn sort_suites(suites: &mut [TestSuite], key: &SortKey) {
+    match key {
+        SortKey::Name => suites.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortKey::Time => suites.sort_by(|a, b| b.duration.total_cmp(&a.duration)),
+    }
+}
+
+const BAR_WIDTH: usize = 30;
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A bar filling `frac` of `width` characters,
+/// using partial blocks for the remainder.
+fn bar(frac: f64, width: usize) -> String {
+    let eighths = (frac.clamp(0., 1.) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIAL_BLOCKS[eighths % 8]);
+    }
+    bar
+}
+
+/// What went wrong reading a results file.
+#[derive(Debug)]
+pub enum ResultsProblem {
+    Missing(PathBuf),
+    Empty(PathBuf),
+    Unparseable(PathBuf, String),
+}
+impl Display for ResultsProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultsProblem::Missing(path) =>
+                write!(f, "Test results were not written to {}", path.display()),
+            ResultsProblem::Empty(path) =>
+                write!(f, "Test results at {} are empty", path.display()),
+            ResultsProblem::Unparseable(path, err) =>
+                write!(f, "Test results at {} could not be parsed: {}", path.display(), err),
+        }
+    }
+}
+impl std::error::Error for ResultsProblem {}
+
+/// A results problem along with what the editor
+/// was up to, to help figure out what happened.
+#[derive(Debug)]
+pub struct ResultsError {
+    pub problem: ResultsProblem,
+    pub exit_code: Option<i32>,
+    pub log_tail: Vec<String>,
+}
+impl Display for ResultsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![red(&self.problem.to_string()).to_string()];
+        lines.push(match self.exit_code {
+            Some(code) => format!("Unity exited with code {}.", code),
+            None => "Unity was terminated by a signal.".to_string(),
+        });
+        if !self.log_tail.is_empty() {
+            lines.push(format!("Last {} lines of the editor log:", self.log_tail.len()));
+            lines.push(muted(&indent(&self.log_tail.join("\n"))).to_string());
+        }
+        lines.push(match self.problem {
+            ResultsProblem::Missing(_) =>
+                "Unity may have crashed before writing results, or may not have \
+                run any tests at all; check the test mode and assembly names.",
+            ResultsProblem::Empty(_) | ResultsProblem::Unparseable(..) =>
+                "Unity likely crashed or was killed while writing the results.",
+        }.to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for ResultsError {}
+
+/// Load a test results file. If the file was cut short
+/// (e.g. the editor crashed mid-write), the results that
+/// were written are recovered and the summary is marked truncated.
+pub fn load_test_results(results_path: &Path) -> Result<TestSummary, ResultsProblem> {
+    let xml = fs::read_to_string(results_path)
+        .map_err(|_| ResultsProblem::Missing(results_path.to_path_buf()))?;
+    if xml.trim().is_empty() {
+        return Err(ResultsProblem::Empty(results_path.to_path_buf()));
+    }
+
+    match de::from_str::<TestSummary>(&xml) {
+        Ok(results) => Ok(results),
+        Err(err) => {
+            let repaired = close_truncated(&xml)
+                .and_then(|xml| de::from_str::<TestSummary>(&xml).ok());
+            match repaired {
+                Some(mut results) => {
+                    results.truncated = true;
+                    results.run_total = None;
+                    results.run_passed = None;
+                    results.run_failed = None;
+                    results.run_skipped = None;
+                    Ok(results)
+                },
+                None => Err(ResultsProblem::Unparseable(
+                        results_path.to_path_buf(), err.to_string())),
+            }
+        }
+    }
+}
+
+/// Re-emit the well-formed prefix of an XML document,
+/// closing any elements that were left open.
+fn close_truncated(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut open: Vec<String> = vec![];
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(event) => {
+                match &event {
+                    Event::Start(start) => open.push(
+                        String::from_utf8_lossy(start.name().as_ref()).to_string()),
+                    Event::End(_) => { open.pop(); },
+                    _ => {}
+                }
+                writer.write_event(event).ok()?;
+            }
+        }
+    }
+    for name in open.iter().rev() {
+        writer.write_event(Event::End(BytesEnd::new(name))).ok()?;
+    }
+    String::from_utf8(writer.into_inner()).ok()
 }
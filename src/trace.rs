@@ -0,0 +1,163 @@
+//! Printing test failures' stack traces: frames are shortened to
+//! the method and the file (relative to the project), frames in
+//! the engine and test framework are hidden, and files are
+//! [OSC 8 hyperlinks][osc8] so they can be clicked in terminals
+//! that support them. The source around the first of the project's
+//! own frames is shown after the trace. Frames look like:
+//!
+//! ```text
+//! at Game.Tests.Foo () [0x00001] in /path/to/Assets/Tests/Foo.cs:21
+//! ```
+//!
+//! [osc8]: https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda
+
+use std::{path::{Path, PathBuf}, sync::Mutex};
+use crate::{display::muted, source};
+
+/// Frames in these namespaces are hidden unless the full trace is asked for.
+const INTERNAL_NAMESPACES: &[&str] = &["UnityEngine.", "UnityEditor.", "NUnit.", "System.", "Mono."];
+
+#[derive(Debug, Clone)]
+struct TraceOptions {
+    /// Paths are shown relative to this
+    project_path: Option<PathBuf>,
+
+    /// Show the engine's frames too
+    full: bool,
+
+    /// Lines of source to show either side of the failure
+    context: usize,
+}
+impl Default for TraceOptions {
+    fn default() -> TraceOptions {
+        TraceOptions { project_path: None, full: false, context: source::DEFAULT_CONTEXT }
+    }
+}
+
+static OPTIONS: Mutex<Option<TraceOptions>> = Mutex::new(None);
+
+/// Set how stack traces are printed from here on: relative
+/// to the project (or the current directory if there isn't
+/// one), whether to include the engine's frames, and how
+/// many lines of source to show around the failure (0 for none).
+pub fn set_trace_options(project_path: Option<&Path>, full: bool, context: usize) {
+    *OPTIONS.lock().unwrap() = Some(TraceOptions {
+        project_path: project_path.map(|path| std::fs::canonicalize(path).unwrap_or(path.to_path_buf())),
+        full,
+        context,
+    });
+}
+
+fn options() -> TraceOptions {
+    OPTIONS.lock().unwrap().clone().unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct Frame<'a> {
+    /// e.g. `Game.Tests.Foo ()`
+    method: &'a str,
+    file: Option<&'a str>,
+    line: Option<usize>,
+}
+impl Frame<'_> {
+    fn parse(line: &str) -> Option<Frame<'_>> {
+        let frame = line.trim().strip_prefix("at ")?;
+        let (method, location) = match frame.rsplit_once(" in ") {
+            Some((method, location)) => (method, Some(location.trim())),
+            None => (frame, None),
+        };
+        // Drop the IL offset, e.g. `[0x00001]`
+        let method = match method.rsplit_once(" [0x") {
+            Some((method, _)) => method,
+            None => method,
+        }.trim();
+        let (file, line) = match location.and_then(|location| location.rsplit_once(':')) {
+            Some((file, line)) if line.parse::<usize>().is_ok() => (Some(file), line.parse().ok()),
+            _ => (location, None),
+        };
+        Some(Frame { method, file, line })
+    }
+
+    fn is_internal(&self) -> bool {
+        INTERNAL_NAMESPACES.iter().any(|namespace| self.method.starts_with(namespace))
+    }
+}
+
+/// The stack trace, with the frames shortened and
+/// the engine's hidden (per [`set_trace_options`]).
+pub fn render(trace: &str) -> String {
+    let options = options();
+    let root = options.project_path.clone()
+        .or_else(|| std::env::current_dir().ok())
+        .filter(|root| root.parent().is_some());
+    let links = colored::control::SHOULD_COLORIZE.should_colorize();
+
+    let mut lines = vec![];
+    let mut hidden = 0;
+    let mut snippet = None;
+    for line in trace.lines() {
+        let Some(frame) = Frame::parse(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        if frame.is_internal() && !options.full {
+            hidden += 1;
+            continue;
+        }
+        let location = frame.file.map(|file| {
+            let path = Path::new(file);
+            if snippet.is_none() && options.context > 0 && !frame.is_internal() {
+                let full_path = root.as_deref().map(|root| root.join(path)).unwrap_or(path.to_path_buf());
+                snippet = frame.line.and_then(|line| source::snippet(&full_path, line, None, options.context));
+            }
+            let shown = root.as_deref()
+                .and_then(|root| path.strip_prefix(root).ok())
+                .unwrap_or(path);
+            let text = match frame.line {
+                Some(line) => format!("{}:{}", shown.display(), line),
+                None => shown.display().to_string(),
+            };
+            match (&root, links) {
+                (Some(root), true) => hyperlink(&file_url(&root.join(path)), &text),
+                (None, true) if path.is_absolute() => hyperlink(&file_url(path), &text),
+                _ => text,
+            }
+        });
+        lines.push(match location {
+            Some(location) => format!("at {} in {}", frame.method, location),
+            None => format!("at {}", frame.method),
+        });
+    }
+    if hidden > 0 {
+        let frames = if hidden == 1 { "frame" } else { "frames" };
+        lines.push(muted(&format!("({} engine {} hidden; pass `--full-trace` to show them)", hidden, frames)).to_string());
+    }
+    if let Some(snippet) = snippet {
+        lines.push(snippet);
+    }
+    lines.join("\n")
+}
+
+/// The file and line of the first of the project's own frames,
+/// i.e. where the test failed.
+pub(crate) fn first_frame(trace: &str) -> Option<(PathBuf, usize)> {
+    trace.lines()
+        .filter_map(Frame::parse)
+        .filter(|frame| !frame.is_internal())
+        .find_map(|frame| Some((PathBuf::from(frame.file?), frame.line?)))
+}
+
+/// An OSC 8 hyperlink, which terminals without support show as just the text.
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/").replace(' ', "%20");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        // e.g. `C:/...` on Windows
+        format!("file:///{}", path)
+    }
+}
@@ -0,0 +1,125 @@
+//! Exporting and importing `.unitypackage` files, with the editor's
+//! `-exportPackage` and `-importPackage`. The editor says little about
+//! either, so what a package holds is read from the file itself: a
+//! gzipped tar with a directory per asset (named by its GUID), whose
+//! `pathname` file has the asset's path, e.g.:
+//!
+//! ```text
+//! 6f2b.../asset
+//! 6f2b.../asset.meta
+//! 6f2b.../pathname    Assets/Textures/Big.png
+//! ```
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{fmt::Display, fs, io, path::{Path, PathBuf}, process::Command};
+use crate::display::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Export,
+    Import,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageReport {
+    pub direction: Direction,
+
+    /// The `.unitypackage` file
+    pub file: PathBuf,
+
+    /// The assets in the package (and, for imports,
+    /// in the project after), by path
+    pub assets: Vec<String>,
+
+    /// For imports, the package's assets that
+    /// weren't in the project after
+    pub missing: Vec<String>,
+
+    /// Errors the editor logged about the package
+    pub errors: Vec<String>,
+}
+impl PackageReport {
+    pub fn succeeded(&self) -> bool {
+        self.errors.is_empty() && self.missing.is_empty()
+    }
+}
+impl Display for PackageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![];
+        for asset in &self.assets {
+            lines.push(format!("  {}", asset));
+        }
+        for asset in &self.missing {
+            lines.push(format!("  {} {}", red(asset), muted("(not imported)")));
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        let (done, failed) = match self.direction {
+            Direction::Export => (" Package exported ", " Package export failed "),
+            Direction::Import => (" Package imported ", " Package import failed "),
+        };
+        let stats = format!("{} assets, {}", self.assets.len(), self.file.display());
+        if self.succeeded() {
+            lines.push(format!("{} {}", on_green(done), muted(&stats)));
+        } else {
+            lines.push(format!("{} {}", on_red(failed), muted(&stats)));
+        }
+        for err in &self.errors {
+            lines.push(format!("  {}", red(err)));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The paths of the assets in the package, sorted, read by
+/// unpacking it (with `tar`) into a temp directory.
+pub fn contents(file: &Path) -> Result<Vec<String>> {
+    let dir = std::env::temp_dir().join(format!("unitool-package-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = unpack(file, &dir);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn unpack(file: &Path, dir: &Path) -> Result<Vec<String>> {
+    let output = Command::new("tar").arg("-xzf").arg(file).arg("-C").arg(dir).output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => anyhow!("Reading packages needs tar"),
+            _ => err.into(),
+        })?;
+    if !output.status.success() {
+        bail!("Couldn't read {}: {}", file.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let mut assets = vec![];
+    for entry in fs::read_dir(dir)? {
+        let Ok(pathname) = fs::read_to_string(entry?.path().join("pathname")) else { continue };
+        // Older versions follow the path with other lines
+        if let Some(path) = pathname.lines().next().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            assets.push(path.to_string());
+        }
+    }
+    assets.sort();
+    Ok(assets)
+}
+
+/// The errors the editor logged about exporting or importing a package.
+pub(crate) fn errors(log: &str) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    for line in log.lines() {
+        let line = line.trim();
+        let lower = line.to_lowercase();
+        if !lower.contains("package") { continue }
+        // e.g. `Failed to import package with error: Couldn't decompress package`,
+        // rather than the package manager's `[Package Manager] ...`
+        let is_error = ["error", "failed", "couldn't", "cannot", "can't"].iter()
+            .any(|prefix| lower.starts_with(prefix));
+        if is_error && !errors.iter().any(|seen| seen == line) {
+            errors.push(line.to_string());
+        }
+    }
+    errors
+}
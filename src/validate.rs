@@ -0,0 +1,402 @@
+//! Static checks on a project's assets, without the editor:
+//! scenes, prefabs and other text-serialized assets referencing
+//! scripts or assets that no longer exist, references to objects
+//! missing from the file itself, and `.meta` files left behind
+//! by deleted assets, or copied along with them (outside the
+//! editor) and so sharing their GUIDs.
+//!
+//! Assets are found by the GUIDs in their `.meta` files, under
+//! `Assets/`, `Packages/` and `Library/PackageCache/`, so references
+//! into registry packages are only checked once the project has
+//! been opened (and its packages downloaded).
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt::Display, fs, path::{Path, PathBuf}, process, time::{SystemTime, UNIX_EPOCH}};
+use crate::{cache::Fnv, display::*};
+
+/// Extensions of the assets which are checked for broken references.
+const CHECKED_ASSETS: &[&str] = &["unity", "prefab", "asset", "mat", "controller", "overrideController", "anim"];
+
+/// Built-in resources have GUIDs of this form,
+/// e.g. `0000000000000000f000000000000000`.
+const BUILTIN_GUID_PREFIX: &str = "0000000000000000";
+
+/// The `fileID` scripts are referenced by.
+const SCRIPT_FILE_ID: &str = "11500000";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A component's script is missing
+    MissingScript,
+
+    /// A reference to an asset (by GUID) that doesn't exist
+    BrokenReference,
+
+    /// A reference to an object that isn't in the file
+    MissingObject,
+
+    /// A `.meta` file whose asset is gone
+    OrphanedMeta,
+
+    /// A `.meta` file with the same GUID as another
+    DuplicateGuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+
+    /// Relative to the project root
+    pub path: PathBuf,
+    pub line: Option<usize>,
+
+    /// The object with the reference, e.g. `Player (MonoBehaviour)`
+    pub object: Option<String>,
+
+    /// The field with the reference, e.g. `m_Script`
+    pub field: Option<String>,
+
+    /// The missing GUID or file ID
+    pub target: Option<String>,
+
+    /// For duplicate GUIDs, the (older) `.meta` file with the same GUID
+    pub duplicate_of: Option<PathBuf>,
+}
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            IssueKind::MissingScript => "Missing script",
+            IssueKind::BrokenReference => "Broken reference",
+            IssueKind::MissingObject => "Reference to a missing object",
+            IssueKind::OrphanedMeta => "Orphaned meta file, its asset is gone",
+            IssueKind::DuplicateGuid => "Duplicate",
+        };
+        let mut line = red(what).to_string();
+        if let Some(object) = &self.object {
+            line.push_str(&format!(" on {}", object));
+        }
+        if let Some(field) = &self.field {
+            line.push_str(&format!(" in `{}`", field));
+        }
+        if let Some(target) = &self.target {
+            line.push_str(&format!(" {}", muted(target)));
+        }
+        if let Some(other) = &self.duplicate_of {
+            line.push_str(&format!(", the same as {}", other.display()));
+        }
+        write!(f, "{}", line)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    /// How many assets were checked
+    pub checked: usize,
+    pub issues: Vec<Issue>,
+}
+impl ValidationReport {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.passed() {
+            return write!(f, "{}", green(&format!("No problems found in {} assets", self.checked)));
+        }
+        let mut by_path: BTreeMap<&Path, Vec<&Issue>> = BTreeMap::new();
+        for issue in &self.issues {
+            by_path.entry(&issue.path).or_default().push(issue);
+        }
+        let mut lines = vec![];
+        for (path, issues) in by_path {
+            lines.push(path.display().to_string());
+            for issue in issues {
+                let location = issue.line.map(|line| muted(&format!("{:>5}: ", line)).to_string()).unwrap_or_default();
+                lines.push(format!("  {}{}", location, issue));
+            }
+        }
+        lines.push("".to_string());
+        lines.push(red(&format!("{} problems in {} assets", self.issues.len(), self.checked)).to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Check the project's assets.
+pub fn validate(project_path: &Path) -> Result<ValidationReport> {
+    let assets_dir = project_path.join("Assets");
+    if !assets_dir.is_dir() {
+        bail!("{} isn't a Unity project, it has no Assets folder", project_path.display());
+    }
+    let mut metas = vec![];
+    for dir in ["Assets", "Packages", "Library/PackageCache"] {
+        collect(&project_path.join(dir), &mut metas, &|path| has_extension(path, &["meta"]));
+    }
+    let metas: Vec<(PathBuf, Option<String>)> = metas.into_iter()
+        .map(|path| {
+            let guid = meta_guid(&path);
+            (path, guid)
+        })
+        .collect();
+    let guids: HashSet<&str> = metas.iter()
+        .filter_map(|(_, guid)| guid.as_deref())
+        .collect();
+
+    let mut report = ValidationReport::default();
+    let relative = |path: &Path| path.strip_prefix(project_path).unwrap_or(path).to_path_buf();
+    for (meta, _) in metas.iter().filter(|(path, _)| path.starts_with(&assets_dir)) {
+        if !meta.with_extension("").exists() {
+            report.issues.push(Issue {
+                kind: IssueKind::OrphanedMeta,
+                path: relative(meta),
+                line: None,
+                object: None,
+                field: None,
+                target: None,
+                duplicate_of: None,
+            });
+        }
+    }
+
+    // Packages in the cache are read-only, so only
+    // the project's own duplicates are reported
+    let package_cache = project_path.join("Library/PackageCache");
+    let mut by_guid: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+    for (meta, guid) in &metas {
+        if let Some(guid) = guid {
+            if !meta.starts_with(&package_cache) {
+                by_guid.entry(guid).or_default().push(meta);
+            }
+        }
+    }
+    for (guid, mut paths) in by_guid {
+        if paths.len() < 2 {
+            continue;
+        }
+
+        // The oldest is most likely the original, and the rest copies
+        paths.sort_by_key(|path| (modified(path), path.to_path_buf()));
+        for copy in &paths[1..] {
+            report.issues.push(Issue {
+                kind: IssueKind::DuplicateGuid,
+                path: relative(copy),
+                line: None,
+                object: None,
+                field: None,
+                target: Some(format!("guid {}", guid)),
+                duplicate_of: Some(relative(paths[0])),
+            });
+        }
+    }
+
+    let mut assets = vec![];
+    collect(&assets_dir, &mut assets, &|path| has_extension(path, CHECKED_ASSETS));
+    assets.sort();
+    for asset in assets {
+        // Binary-serialized assets can't be checked
+        let Ok(src) = fs::read_to_string(&asset) else { continue };
+        if !src.starts_with("%YAML") {
+            continue;
+        }
+        report.checked += 1;
+        report.issues.extend(check_asset(&src, &guids).into_iter().map(|mut issue| {
+            issue.path = relative(&asset);
+            issue
+        }));
+    }
+    Ok(report)
+}
+
+/// An object in a YAML asset, e.g. a GameObject or component.
+#[derive(Debug, Default)]
+struct Object {
+    class: String,
+    name: Option<String>,
+
+    /// For components, the GameObject they're on
+    game_object: Option<String>,
+}
+
+/// A `{fileID: ..., guid: ...}` reference.
+struct Reference<'a> {
+    line: usize,
+    field: &'a str,
+    file_id: &'a str,
+    guid: Option<&'a str>,
+
+    /// The object (by file ID) it's in
+    owner: Option<&'a str>,
+}
+
+fn check_asset(src: &str, guids: &HashSet<&str>) -> Vec<Issue> {
+    let mut objects: HashMap<&str, Object> = HashMap::new();
+    let mut references = vec![];
+    let mut current: Option<&str> = None;
+    for (i, line) in src.lines().enumerate() {
+        // Each object starts with e.g. `--- !u!114 &123456`
+        if let Some(header) = line.strip_prefix("--- ") {
+            current = header.split_whitespace()
+                .find_map(|part| part.strip_prefix('&'));
+            if let Some(id) = current {
+                objects.insert(id, Object::default());
+            }
+            continue;
+        }
+        let Some(object) = current.and_then(|id| objects.get_mut(id)) else { continue };
+        if !line.starts_with(' ') {
+            if let Some(class) = line.strip_suffix(':') {
+                object.class = class.to_string();
+            }
+            continue;
+        }
+
+        let trimmed = line.trim_start().trim_start_matches("- ");
+        if let Some(name) = trimmed.strip_prefix("m_Name: ") {
+            if !name.trim().is_empty() {
+                object.name = Some(name.trim().to_string());
+            }
+        }
+        let field = trimmed.split_once(':').map(|(field, _)| field.trim()).unwrap_or("");
+        for reference in parse_references(line) {
+            let (file_id, guid) = reference;
+            if field == "m_GameObject" {
+                object.game_object = Some(file_id.to_string());
+            }
+            references.push(Reference { line: i + 1, field, file_id, guid, owner: current });
+        }
+    }
+
+    let describe = |owner: Option<&str>| -> Option<String> {
+        let object = objects.get(owner?)?;
+        let name = object.name.clone().or_else(|| {
+            objects.get(object.game_object.as_deref()?)?.name.clone()
+        });
+        Some(match name {
+            Some(name) => format!("{} ({})", name, object.class),
+            None => object.class.clone(),
+        })
+    };
+
+    let mut issues = vec![];
+    for reference in references {
+        let kind = match reference.guid {
+            _ if reference.field == "m_Script" && reference.file_id == "0" => IssueKind::MissingScript,
+            Some(guid) if guid.starts_with(BUILTIN_GUID_PREFIX) || guids.contains(guid) => continue,
+            Some(_) if reference.field == "m_Script" || reference.file_id == SCRIPT_FILE_ID => IssueKind::MissingScript,
+            Some(_) => IssueKind::BrokenReference,
+            None if reference.file_id == "0" || objects.contains_key(reference.file_id) => continue,
+            None => IssueKind::MissingObject,
+        };
+        let target = match reference.guid {
+            Some(guid) => format!("guid {}", guid),
+            None => format!("fileID {}", reference.file_id),
+        };
+        issues.push(Issue {
+            kind,
+            path: PathBuf::new(),
+            line: Some(reference.line),
+            object: describe(reference.owner),
+            field: Some(reference.field.to_string()),
+            target: Some(target),
+            duplicate_of: None,
+        });
+    }
+    issues
+}
+
+/// The `(fileID, guid)` of each `{fileID: ...}` on the line.
+fn parse_references(line: &str) -> Vec<(&str, Option<&str>)> {
+    let mut references = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find("{fileID:") {
+        let Some(end) = rest[start..].find('}') else { break };
+        let inner = &rest[start + 1..start + end];
+        let mut file_id = None;
+        let mut guid = None;
+        for pair in inner.split(',') {
+            match pair.split_once(':').map(|(key, value)| (key.trim(), value.trim())) {
+                Some(("fileID", value)) => file_id = Some(value),
+                Some(("guid", value)) => guid = Some(value),
+                _ => {},
+            }
+        }
+        if let Some(file_id) = file_id {
+            references.push((file_id, guid));
+        }
+        rest = &rest[start + end..];
+    }
+    references
+}
+
+/// Give a `.meta` file a new GUID, e.g. one that duplicates
+/// another's. References to its asset by the old GUID will
+/// go to the other asset instead.
+pub fn regenerate_guid(meta_path: &Path) -> Result<String> {
+    let mut src = fs::read_to_string(meta_path)?;
+    let guid = new_guid(meta_path);
+    // Replacing just the old value, leaving the rest
+    // (e.g. its line endings) as it was
+    let mut start = 0;
+    let old = src.split_inclusive('\n')
+        .find_map(|line| {
+            let value = line.strip_prefix("guid:").map(|rest| {
+                let rest = rest.trim_start();
+                let offset = start + line.len() - rest.len();
+                let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                offset..offset + len
+            });
+            start += line.len();
+            value
+        })
+        .filter(|range| !range.is_empty());
+    let Some(old) = old else {
+        bail!("{} has no GUID", meta_path.display());
+    };
+    src.replace_range(old, &guid);
+    fs::write(meta_path, src)?;
+    Ok(guid)
+}
+
+/// A random-enough GUID, as 32 hex digits like Unity's.
+fn new_guid(meta_path: &Path) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_nanos()).unwrap_or(0);
+    let mut high = Fnv::default();
+    high.write(&nanos.to_le_bytes());
+    high.write(&process::id().to_le_bytes());
+    high.write(meta_path.to_string_lossy().as_bytes());
+    let mut low = Fnv(high.0);
+    low.write(&nanos.to_be_bytes());
+    format!("{:016x}{:016x}", high.0, low.0)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// The GUID in a `.meta` file.
+fn meta_guid(path: &Path) -> Option<String> {
+    let src = fs::read_to_string(path).ok()?;
+    src.lines()
+        .find_map(|line| line.strip_prefix("guid:"))
+        .map(|guid| guid.trim().to_string())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext))
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>, include: &dyn Fn(&Path) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files, include);
+        } else if include(&path) {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Watching a project's scripts and packages for changes.
+//! This polls modification times rather than using each
+//! platform's notification API; a project has few enough
+//! scripts that a scan is cheap.
+
+use std::{fs, collections::{BTreeMap, BTreeSet}, path::{Path, PathBuf}, thread, time::{Duration, SystemTime}};
+
+/// How often to scan for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long changes have to settle before they count,
+/// so that e.g. saving several files triggers one run.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Modification times by path.
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+pub struct Watcher {
+    project_path: PathBuf,
+    snapshot: Snapshot,
+}
+impl Watcher {
+    pub fn new(project_path: &Path) -> Watcher {
+        Watcher {
+            project_path: project_path.to_path_buf(),
+            snapshot: snapshot(project_path),
+        }
+    }
+
+    /// Block until something changes and the changes settle.
+    /// Returns the paths that were added, removed or modified.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let mut current = snapshot(&self.project_path);
+            if current == self.snapshot { continue }
+
+            loop {
+                thread::sleep(DEBOUNCE);
+                let next = snapshot(&self.project_path);
+                if next == current { break }
+                current = next;
+            }
+            let changed = self.snapshot.keys().chain(current.keys())
+                .filter(|path| self.snapshot.get(*path) != current.get(*path))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            self.snapshot = current;
+            return changed.into_iter().collect();
+        }
+    }
+}
+
+/// Scripts (and assembly definitions, which change how
+/// they're compiled) under `Assets/`, and everything
+/// under `Packages/`.
+fn snapshot(project_path: &Path) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    collect(&project_path.join("Assets"), &mut snapshot, &|path| {
+        matches!(path.extension().and_then(|ext| ext.to_str()), Some("cs" | "asmdef" | "asmref"))
+    });
+    collect(&project_path.join("Packages"), &mut snapshot, &|_| true);
+    snapshot
+}
+
+fn collect(dir: &Path, snapshot: &mut Snapshot, include: &dyn Fn(&Path) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            collect(&path, snapshot, include);
+        } else if include(&path) {
+            if let Ok(modified) = meta.modified() {
+                snapshot.insert(path, modified);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Killing the editor if it runs too long. In batchmode
+//! Unity can hang forever, e.g. on a license prompt or
+//! a stuck domain reload.
+//!
+//! The editor is started in its own process group
+//! (see [`isolate`]) so that any processes it spawned
+//! (shader compilers, the package manager, etc.)
+//! are killed along with it.
+
+use std::{fmt::Display, process::{Command, Stdio}, sync::mpsc::{self, Sender, RecvTimeoutError}, thread::{self, JoinHandle}, time::Duration};
+use crate::display::*;
+
+/// Watches a process, killing its tree if the deadline passes.
+pub(crate) struct Watchdog {
+    done: Sender<()>,
+    handle: JoinHandle<bool>,
+}
+impl Watchdog {
+    pub(crate) fn start(pid: u32, timeout: Duration) -> Watchdog {
+        let (done, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            match rx.recv_timeout(timeout) {
+                Err(RecvTimeoutError::Timeout) => {
+                    kill_tree(pid);
+                    true
+                },
+                _ => false,
+            }
+        });
+        Watchdog { done, handle }
+    }
+
+    /// Stop watching. Returns whether the
+    /// deadline passed and the process was killed.
+    pub(crate) fn stop(self) -> bool {
+        let _ = self.done.send(());
+        self.handle.join().unwrap_or(false)
+    }
+}
+
+/// Put the command in its own process group, so that
+/// its whole tree can be killed at once.
+#[cfg(unix)]
+pub(crate) fn isolate(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+}
+
+#[cfg(windows)]
+pub(crate) fn isolate(_cmd: &mut Command) {
+    // `taskkill /T` follows the tree on its own
+}
+
+#[cfg(unix)]
+pub(crate) fn kill_tree(pid: u32) {
+    // The group ID is the leader's pid
+    let _ = Command::new("kill")
+        .args(["-s", "KILL", "--", &format!("-{}", pid)])
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(windows)]
+pub(crate) fn kill_tree(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// The editor was killed for running past the timeout.
+#[derive(Debug)]
+pub struct TimeoutError {
+    pub timeout: Duration,
+
+    /// The test that was running when it was
+    /// killed, if the run was of tests
+    pub last_test: Option<String>,
+
+    /// The last lines of the editor log
+    pub log_tail: Vec<String>,
+}
+impl Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hung_in = match &self.last_test {
+            Some(test) => format!(", hung in {}", test),
+            None => String::new(),
+        };
+        let mut lines = vec![red(&format!(
+            "Unity didn't finish within {}s and was killed{}.", self.timeout.as_secs(), hung_in)).to_string()];
+        if !self.log_tail.is_empty() {
+            lines.push(format!("Last {} lines of the editor log:", self.log_tail.len()));
+            lines.push(muted(&indent(&self.log_tail.join("\n"))).to_string());
+        }
+        lines.push("Unity may be waiting on a license prompt or stuck \
+            reloading; if the work is just slow, raise `--timeout`.".to_string());
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+impl std::error::Error for TimeoutError {}
@@ -0,0 +1,245 @@
+//! Workspaces of several projects (e.g. in a mono-repo), listed
+//! in a `unitool.workspace.toml`, which are run together and
+//! reported on as one:
+//!
+//! ```toml
+//! # How many projects to run at once [default: 1]
+//! jobs = 2
+//!
+//! [projects]
+//! game = "Game"
+//! level_editor = "Tools/LevelEditor"
+//! ```
+//!
+//! Project paths are relative to the workspace file.
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::{fmt::Display, fs, path::{Path, PathBuf}, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, thread};
+use crate::{ExitCode, TestSummary, compiler::CompileError, config::{self, Value}, display::*};
+
+pub const WORKSPACE_FILE: &str = "unitool.workspace.toml";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Member {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub projects: Vec<Member>,
+
+    /// How many projects to run at once
+    pub jobs: Option<usize>,
+}
+impl Workspace {
+    /// Load the workspace file in the directory or the nearest
+    /// directory above it, or from a path to the file itself.
+    pub fn find(path: &Path) -> Result<Workspace> {
+        if path.is_file() {
+            return Workspace::load(path);
+        }
+        let dir = fs::canonicalize(path)
+            .map_err(|err| anyhow!("Couldn't read {}: {}", path.display(), err))?;
+        match dir.ancestors().map(|dir| dir.join(WORKSPACE_FILE)).find(|path| path.is_file()) {
+            Some(path) => Workspace::load(&path),
+            None => bail!("There's no {} in {} or above it", WORKSPACE_FILE, dir.display()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Workspace> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Couldn't read {}: {}", path.display(), err))?;
+        let mut workspace = Workspace::parse(&src)
+            .map_err(|err| anyhow!("{}:{}", path.display(), err))?;
+        let root = path.parent().unwrap_or(Path::new("."));
+        for project in &mut workspace.projects {
+            project.path = root.join(&project.path);
+            if !project.path.join("ProjectSettings").is_dir() {
+                bail!("{}: `{}` at {} isn't a Unity project", path.display(), project.name, project.path.display());
+            }
+        }
+        if workspace.projects.is_empty() {
+            bail!("{}: No projects listed under `[projects]`", path.display());
+        }
+        Ok(workspace)
+    }
+
+    /// Errors are prefixed with the line number.
+    fn parse(src: &str) -> Result<Workspace> {
+        let mut workspace = Workspace::default();
+        let mut in_projects = false;
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if config::is_comment(line) { continue }
+            let line_no = i + 1;
+
+            if let Some(rest) = line.strip_prefix('[') {
+                let Some((name, rest)) = rest.split_once(']') else {
+                    bail!("{}: Unclosed table header", line_no);
+                };
+                if !config::is_comment(rest) {
+                    bail!("{}: Unexpected text after table header", line_no);
+                }
+                if name.trim() != "projects" {
+                    bail!("{}: Unknown table `{}`", line_no, name.trim());
+                }
+                in_projects = true;
+                continue;
+            }
+
+            let Some((key, rest)) = line.split_once('=') else {
+                bail!("{}: Expected `key = value`", line_no);
+            };
+            let key = key.trim().trim_matches('"');
+            let (value, rest) = config::parse_value(rest.trim())
+                .map_err(|err| anyhow!("{}: {}", line_no, err))?;
+            if !config::is_comment(rest) {
+                bail!("{}: Unexpected text after value", line_no);
+            }
+            match (in_projects, key, value) {
+                (true, name, Value::Str(path)) => {
+                    if workspace.projects.iter().any(|project| project.name == name) {
+                        bail!("{}: `{}` is listed twice", line_no, name);
+                    }
+                    workspace.projects.push(Member { name: name.to_string(), path: path.into() });
+                },
+                (true, name, value) =>
+                    bail!("{}: `{}` should be a path, not {}", line_no, name, value.kind()),
+                (false, "jobs", Value::Int(n)) if n > 0 => workspace.jobs = Some(n as usize),
+                (false, "jobs", _) => bail!("{}: `jobs` should be a positive integer", line_no),
+                (false, key, _) => bail!("{}: Unknown key `{}`", line_no, key),
+            }
+        }
+        Ok(workspace)
+    }
+}
+
+/// Run each project, up to `jobs` at once, returning the
+/// results in the order the projects are listed.
+pub fn run_all<T: Send>(projects: &[Member], jobs: usize, run: impl Fn(&Member) -> T + Sync) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new(projects.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, projects.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(project) = projects.get(i) else { break };
+                let result = run(project);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|result| result.expect("every project is run")).collect()
+}
+
+/// How a project's run went.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ProjectOutcome {
+    Compiled {
+        warnings: usize,
+    },
+    CompileFailed {
+        errors: Vec<CompileError>,
+    },
+    Tested {
+        results: TestSummary,
+
+        /// The failed tests that aren't in the project's baseline
+        failures: Vec<String>,
+    },
+
+    /// The run itself failed, e.g. the editor crashed
+    Failed {
+        error: String,
+
+        #[serde(skip)]
+        exit_code: ExitCode,
+    },
+}
+impl ProjectOutcome {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            ProjectOutcome::Compiled { .. } => ExitCode::Success,
+            ProjectOutcome::CompileFailed { .. } => ExitCode::CompileFailed,
+            ProjectOutcome::Tested { failures, .. } if failures.is_empty() => ExitCode::Success,
+            ProjectOutcome::Tested { .. } => ExitCode::TestsFailed,
+            ProjectOutcome::Failed { exit_code, .. } => *exit_code,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectReport {
+    #[serde(flatten)]
+    pub project: Member,
+
+    #[serde(flatten)]
+    pub outcome: ProjectOutcome,
+
+    /// In seconds
+    pub time: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceReport {
+    pub projects: Vec<ProjectReport>,
+}
+impl WorkspaceReport {
+    /// The exit code for the worst outcome: a failed run,
+    /// then compile errors, then failed tests.
+    pub fn exit_code(&self) -> ExitCode {
+        let rank = |code: &ExitCode| match code {
+            ExitCode::Success => 0,
+            ExitCode::TestsFailed => 1,
+            ExitCode::CompileFailed => 2,
+            _ => 3,
+        };
+        self.projects.iter()
+            .map(|report| report.outcome.exit_code())
+            .max_by_key(rank)
+            .unwrap_or(ExitCode::Success)
+    }
+}
+impl Display for WorkspaceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.projects.iter().map(|report| report.project.name.len()).max().unwrap_or(0);
+        let mut lines = vec![];
+        for report in &self.projects {
+            let (mark, status) = match &report.outcome {
+                ProjectOutcome::Compiled { warnings: 0 } => (green("✓"), green("compiled").to_string()),
+                ProjectOutcome::Compiled { warnings } =>
+                    (green("✓"), format!("{} {}", green("compiled"), yellow(&format!("({} warnings)", warnings)))),
+                ProjectOutcome::CompileFailed { errors } =>
+                    (red("✗"), red(&format!("compilation failed with {} errors", errors.len())).to_string()),
+                ProjectOutcome::Tested { results, failures } if failures.is_empty() =>
+                    (green("✓"), green(&format!("{} tests passed", results.passed())).to_string()),
+                ProjectOutcome::Tested { results, failures } =>
+                    (red("✗"), red(&format!("{} of {} tests failed", failures.len(), results.total())).to_string()),
+                ProjectOutcome::Failed { .. } => (red("✗"), red("failed to run").to_string()),
+            };
+            lines.push(format!("{} {:<width$}  {} {}", mark, report.project.name, status,
+                muted(&human_duration(report.time)), width = width));
+
+            let details: Vec<String> = match &report.outcome {
+                ProjectOutcome::CompileFailed { errors } => errors.iter().map(|err| err.to_string()).collect(),
+                ProjectOutcome::Tested { failures, .. } => failures.clone(),
+                ProjectOutcome::Failed { error, .. } => error.lines().map(|line| line.to_string()).collect(),
+                ProjectOutcome::Compiled { .. } => vec![],
+            };
+            lines.extend(details.iter().map(|line| format!("    {}", line)));
+        }
+
+        let failed = self.projects.iter()
+            .filter(|report| report.outcome.exit_code() != ExitCode::Success)
+            .count();
+        lines.push(String::new());
+        lines.push(match failed {
+            0 => green(&format!("All {} projects passed", self.projects.len())).to_string(),
+            n => red(&format!("{} of {} projects failed", n, self.projects.len())).to_string(),
+        });
+        write!(f, "{}", lines.join("\n"))
+    }
+}